@@ -0,0 +1,3 @@
+a = 3;
+b = a * (a + 2) - 4 / (a - 1);
+return b + (a == 3);
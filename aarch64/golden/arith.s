@@ -0,0 +1,30 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #16
+.Lmain.0:
+	mov x16, #3
+	str x16, [sp, #0]
+	ldr x9, [sp, #0]
+	ldr x10, [sp, #0]
+	add x11, x10, #2
+	mul x10, x9, x11
+	ldr x9, [sp, #0]
+	sub x11, x9, #1
+	mov x16, #4
+	sdiv x9, x16, x11
+	sub x11, x10, x9
+	str x11, [sp, #8]
+	ldr x9, [sp, #8]
+	ldr x10, [sp, #0]
+	cmp x10, #3
+	cset x11, eq
+	mov x10, x11
+	add x11, x9, x10
+	sxtw x9, w11
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
@@ -0,0 +1,56 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #128
+.Lmain.0:
+	mov x16, #3
+	str x16, [sp, #0]
+	ldr x16, [sp, #0]
+	str x16, [sp, #16]
+	ldr x16, [sp, #0]
+	str x16, [sp, #24]
+	ldr x16, [sp, #24]
+	add x16, x16, #2
+	str x16, [sp, #32]
+	ldr x16, [sp, #16]
+	ldr x17, [sp, #32]
+	mul x16, x16, x17
+	str x16, [sp, #40]
+	ldr x16, [sp, #0]
+	str x16, [sp, #48]
+	ldr x16, [sp, #48]
+	sub x16, x16, #1
+	str x16, [sp, #56]
+	mov x16, #4
+	ldr x17, [sp, #56]
+	sdiv x16, x16, x17
+	str x16, [sp, #64]
+	ldr x16, [sp, #40]
+	ldr x17, [sp, #64]
+	sub x16, x16, x17
+	str x16, [sp, #72]
+	ldr x16, [sp, #72]
+	str x16, [sp, #8]
+	ldr x16, [sp, #8]
+	str x16, [sp, #80]
+	ldr x16, [sp, #0]
+	str x16, [sp, #88]
+	ldr x16, [sp, #88]
+	cmp x16, #3
+	cset x16, eq
+	str x16, [sp, #96]
+	ldr x16, [sp, #96]
+	str x16, [sp, #104]
+	ldr x16, [sp, #80]
+	ldr x17, [sp, #104]
+	add x16, x16, x17
+	str x16, [sp, #112]
+	ldr x16, [sp, #112]
+	sxtw x16, w16
+	str x16, [sp, #120]
+	ldr x0, [sp, #120]
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
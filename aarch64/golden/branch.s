@@ -0,0 +1,29 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #16
+.Lmain.0:
+	mov x16, #5
+	str x16, [sp, #0]
+	ldr x9, [sp, #0]
+	mov x16, #3
+	cmp x16, x9
+	b.ge .Lmain.3
+.Lmain.1:
+	ldr x9, [sp, #0]
+	mov x17, #2
+	mul x10, x9, x17
+	sxtw x9, w10
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
+.Lmain.3:
+	mov x16, #0
+	sub x9, x16, #1
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
@@ -0,0 +1,3 @@
+int add(int a, int b);
+long x = add(1, 2);
+return add(3, 4) + x;
@@ -0,0 +1,23 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #16
+.Lmain.0:
+	mov x0, #1
+	mov x1, #2
+	bl add
+	mov x9, x0
+	str x9, [sp, #0]
+	mov x0, #3
+	mov x1, #4
+	bl add
+	mov x9, x0
+	ldr x10, [sp, #0]
+	add x11, x9, x10
+	sxtw x9, w11
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
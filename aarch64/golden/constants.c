@@ -0,0 +1,3 @@
+long big = 81985529216486895;
+long neg = 0 - 70000;
+return big > neg;
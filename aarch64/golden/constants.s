@@ -0,0 +1,26 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #16
+.Lmain.0:
+	mov x16, #52719
+	movk x16, #35243, lsl #16
+	movk x16, #17767, lsl #32
+	movk x16, #291, lsl #48
+	str x16, [sp, #0]
+	mov x16, #0
+	mov x17, #4464
+	movk x17, #1, lsl #16
+	sub x9, x16, x17
+	str x9, [sp, #8]
+	ldr x9, [sp, #8]
+	ldr x10, [sp, #0]
+	cmp x9, x10
+	cset x11, lt
+	mov x9, x11
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
@@ -0,0 +1,4 @@
+b = 0;
+for (a = 0; a < 10; a = a + 1)
+    b = b + a;
+return b;
@@ -0,0 +1,31 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #16
+.Lmain.0:
+	mov x16, #0
+	str x16, [sp, #0]
+	mov x16, #0
+	str x16, [sp, #8]
+.Lmain.1:
+	ldr x9, [sp, #8]
+	cmp x9, #10
+	b.ge .Lmain.3
+.Lmain.2:
+	ldr x9, [sp, #0]
+	ldr x10, [sp, #8]
+	add x11, x9, x10
+	str x11, [sp, #0]
+	ldr x9, [sp, #8]
+	add x10, x9, #1
+	str x10, [sp, #8]
+	b .Lmain.1
+.Lmain.3:
+	ldr x9, [sp, #0]
+	sxtw x10, w9
+	mov x0, x10
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
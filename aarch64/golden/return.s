@@ -0,0 +1,10 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+.Lmain.0:
+	mov x0, #42
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
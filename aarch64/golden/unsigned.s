@@ -0,0 +1,40 @@
+	.text
+	.globl main
+main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #32
+.Lmain.0:
+	mov x16, #0
+	str x16, [sp, #0]
+	mov x16, #-56
+	str x16, [sp, #8]
+	mov x16, #7
+	str x16, [sp, #16]
+	ldr x9, [sp, #0]
+	sub x10, x9, #1
+	mov w9, w10
+	mov x16, #5
+	cmp x16, x9
+	b.hs .Lmain.2
+.Lmain.1:
+	ldr x9, [sp, #16]
+	mov x17, #2
+	udiv x10, x9, x17
+	ldr x9, [sp, #8]
+	add x11, x10, x9
+	sxtw x9, w11
+	mov x0, x9
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
+.Lmain.2:
+	ldr x9, [sp, #8]
+	sxtw x10, w9
+	cmp x10, #0
+	cset x9, lt
+	mov x10, x9
+	mov x0, x10
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
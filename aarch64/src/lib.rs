@@ -0,0 +1,355 @@
+use std::fmt::{self, Display};
+
+use backend::{Backend, Frame, LoadStore};
+use ir::instr::{BinOp, CmpOp, Operand, Slot, Value};
+
+/// A general purpose register, printed as `x<n>` or `w<n>` for its low half.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reg(u8);
+
+impl Reg {
+    fn w(self) -> String {
+        format!("w{}", self.0)
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x{}", self.0)
+    }
+}
+
+const X0: Reg = Reg(0);
+
+// AAPCS64 passes the first eight integer arguments in x0 to x7
+const REGISTERS: [Reg; 8] = [X0, Reg(1), Reg(2), Reg(3), Reg(4), Reg(5), Reg(6), Reg(7)];
+
+// x16 and x17 stay free as temporaries, x18 is the platform register and the
+// argument registers are only written when setting up calls
+const CALLER_SAVED: [Reg; 7] = [Reg(9), Reg(10), Reg(11), Reg(12), Reg(13), Reg(14), Reg(15)];
+const CALLEE_SAVED: [Reg; 10] = [
+    Reg(19),
+    Reg(20),
+    Reg(21),
+    Reg(22),
+    Reg(23),
+    Reg(24),
+    Reg(25),
+    Reg(26),
+    Reg(27),
+    Reg(28),
+];
+const TMP0: Reg = Reg(16);
+const TMP1: Reg = Reg(17);
+
+// memory operand of a frame offset
+fn addr(offset: i64) -> String {
+    format!("[sp, #{offset}]")
+}
+
+/// Code generator for AArch64 Linux, taking the IR to GNU assembler syntax.
+#[derive(Debug)]
+pub struct Aarch64Backend {
+    out: String,
+}
+
+impl Default for Aarch64Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Aarch64Backend {
+    pub fn new() -> Self {
+        Self {
+            out: "\t.text\n".to_string(),
+        }
+    }
+
+    // set the flags from comparing lhs with rhs
+    fn flags(&mut self, frame: &Frame<Reg>, lhs: &Operand, rhs: &Operand) {
+        let l = self.read(frame, lhs, TMP0);
+        match rhs {
+            Operand::Const(n) if is_imm12(*n) => self.emit(format!("cmp {l}, #{n}")),
+            Operand::Const(n) if n.checked_neg().is_some_and(is_imm12) => {
+                self.emit(format!("cmn {l}, #{}", -n));
+            }
+            _ => {
+                let r = self.read(frame, rhs, TMP1);
+                self.emit(format!("cmp {l}, {r}"));
+            }
+        }
+    }
+
+    // mov takes 16 bits, wider constants are built up with movk
+    fn load_imm(&mut self, reg: Reg, n: i64) {
+        if (-0xffff..=0xffff).contains(&n) {
+            self.emit(format!("mov {reg}, #{n}"));
+            return;
+        }
+        let bits = n as u64;
+        self.emit(format!("mov {reg}, #{}", bits & 0xffff));
+        for shift in [16, 32, 48] {
+            let chunk = (bits >> shift) & 0xffff;
+            if chunk != 0 {
+                self.emit(format!("movk {reg}, #{chunk}, lsl #{shift}"));
+            }
+        }
+    }
+
+    fn adjust_sp(&mut self, op: &str, size: i64) {
+        if is_imm12(size) {
+            self.emit(format!("{op} sp, sp, #{size}"));
+        } else {
+            self.load_imm(TMP0, size);
+            self.emit(format!("{op} sp, sp, {TMP0}"));
+        }
+    }
+
+    fn emit(&mut self, instr: impl AsRef<str>) {
+        self.out.push('\t');
+        self.out.push_str(instr.as_ref());
+        self.out.push('\n');
+    }
+}
+
+impl LoadStore for Aarch64Backend {
+    const SCRATCH: Reg = TMP0;
+
+    fn constant(&mut self, n: i64, tmp: Reg) -> Reg {
+        self.load_imm(tmp, n);
+        tmp
+    }
+
+    fn load_offset(&mut self, dst: Reg, offset: i64) {
+        self.emit(format!("ldr {dst}, {}", addr(offset)));
+    }
+
+    fn store_offset(&mut self, offset: i64, src: Reg) {
+        self.emit(format!("str {src}, {}", addr(offset)));
+    }
+
+    fn move_reg(&mut self, dst: Reg, src: Reg) {
+        self.emit(format!("mov {dst}, {src}"));
+    }
+}
+
+impl Backend for Aarch64Backend {
+    type Reg = Reg;
+    type Output = String;
+
+    fn argument_registers(&self) -> &'static [Reg] {
+        &REGISTERS
+    }
+
+    fn caller_saved(&self) -> &'static [Reg] {
+        &CALLER_SAVED
+    }
+
+    fn callee_saved(&self) -> &'static [Reg] {
+        &CALLEE_SAVED
+    }
+
+    fn prologue(&mut self, frame: &Frame<Reg>, params: &[Value]) {
+        self.out
+            .push_str(&format!("\t.globl {0}\n{0}:\n", frame.name));
+        self.emit("stp x29, x30, [sp, #-16]!");
+        self.emit("mov x29, sp");
+        if frame.size() > 0 {
+            self.adjust_sp("sub", frame.size());
+        }
+        for (i, reg) in frame.alloc.callee_saved.iter().enumerate() {
+            self.emit(format!("str {reg}, {}", addr(frame.saved(i))));
+        }
+        for (param, reg) in params.iter().zip(REGISTERS.iter()) {
+            self.write(frame, *param, *reg);
+        }
+    }
+
+    fn epilogue(&mut self, frame: &Frame<Reg>) {
+        for (i, reg) in frame.alloc.callee_saved.iter().enumerate() {
+            self.emit(format!("ldr {reg}, {}", addr(frame.saved(i))));
+        }
+        self.emit("mov sp, x29");
+        self.emit("ldp x29, x30, [sp], #16");
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push_str(&format!("{label}:\n"));
+    }
+
+    fn load(&mut self, frame: &Frame<Reg>, dst: Value, slot: Slot) {
+        let d = self.dst_reg(frame, dst);
+        self.load_offset(d, frame.slot(slot));
+        self.write(frame, dst, d);
+    }
+
+    fn store(&mut self, frame: &Frame<Reg>, slot: Slot, src: &Operand) {
+        let s = self.read(frame, src, TMP0);
+        self.store_offset(frame.slot(slot), s);
+    }
+
+    fn copy(&mut self, frame: &Frame<Reg>, dst: Value, src: &Operand) {
+        let s = self.read(frame, src, self.dst_reg(frame, dst));
+        self.write(frame, dst, s);
+    }
+
+    fn extend(&mut self, frame: &Frame<Reg>, dst: Value, src: &Operand, bits: u32, signed: bool) {
+        let s = self.read(frame, src, TMP0);
+        let d = self.dst_reg(frame, dst);
+        match (bits, signed) {
+            (8, true) => self.emit(format!("sxtb {d}, {}", s.w())),
+            (8, false) => self.emit(format!("and {d}, {s}, #0xff")),
+            (32, true) => self.emit(format!("sxtw {d}, {}", s.w())),
+            // writing the low half clears the upper one
+            (32, false) => self.emit(format!("mov {}, {}", d.w(), s.w())),
+            _ => self.emit(format!("mov {d}, {s}")),
+        }
+        self.write(frame, dst, d);
+    }
+
+    fn binary(&mut self, frame: &Frame<Reg>, op: BinOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+        let l = self.read(frame, lhs, TMP0);
+        let d = self.dst_reg(frame, dst);
+        match (op, rhs) {
+            (BinOp::Add | BinOp::Sub, Operand::Const(n)) if is_imm12(*n) => {
+                self.emit(format!("{} {d}, {l}, #{n}", mnemonic(op)));
+            }
+            _ => {
+                let r = self.read(frame, rhs, TMP1);
+                self.emit(format!("{} {d}, {l}, {r}", mnemonic(op)));
+            }
+        }
+        self.write(frame, dst, d);
+    }
+
+    fn compare(&mut self, frame: &Frame<Reg>, op: CmpOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+        self.flags(frame, lhs, rhs);
+        let d = self.dst_reg(frame, dst);
+        self.emit(format!("cset {d}, {}", cond(op)));
+        self.write(frame, dst, d);
+    }
+
+    fn compare_and_branch(
+        &mut self,
+        frame: &Frame<Reg>,
+        op: CmpOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        target: &str,
+    ) {
+        match (op, rhs) {
+            (CmpOp::Eq | CmpOp::Ne, Operand::Const(0)) => {
+                let l = self.read(frame, lhs, TMP0);
+                let branch = if op == CmpOp::Eq { "cbz" } else { "cbnz" };
+                self.emit(format!("{branch} {l}, {target}"));
+            }
+            _ => {
+                self.flags(frame, lhs, rhs);
+                self.emit(format!("b.{} {target}", cond(op)));
+            }
+        }
+    }
+
+    fn jump(&mut self, target: &str) {
+        self.emit(format!("b {target}"));
+    }
+
+    fn call(&mut self, frame: &Frame<Reg>, dst: Value, func: &str, args: &[Operand]) {
+        self.arguments(frame, args);
+        self.emit(format!("bl {func}"));
+        self.write(frame, dst, X0);
+    }
+
+    fn ret(&mut self, frame: &Frame<Reg>, value: &Operand) {
+        self.move_to(frame, X0, value);
+        self.epilogue(frame);
+        self.emit("ret");
+    }
+
+    fn tail_call(&mut self, frame: &Frame<Reg>, func: &str, args: &[Operand]) {
+        self.arguments(frame, args);
+        self.epilogue(frame);
+        self.emit(format!("b {func}"));
+    }
+
+    fn data(&mut self, name: &str, values: &[i64]) {
+        self.out
+            .push_str(&format!("\t.data\n\t.globl {name}\n{name}:\n"));
+        for value in values.iter() {
+            self.emit(format!(".quad {value}"));
+        }
+        self.out.push_str("\t.text\n");
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::replace(&mut self.out, "\t.text\n".to_string())
+    }
+}
+
+// immediates of add, sub and cmp
+fn is_imm12(n: i64) -> bool {
+    (0..4096).contains(&n)
+}
+
+fn mnemonic(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "sub",
+        BinOp::Mul => "mul",
+        BinOp::Div => "sdiv",
+        BinOp::UDiv => "udiv",
+    }
+}
+
+fn cond(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Eq => "eq",
+        CmpOp::Ne => "ne",
+        CmpOp::Lt => "lt",
+        CmpOp::Le => "le",
+        CmpOp::Gt => "gt",
+        CmpOp::Ge => "ge",
+        CmpOp::Ult => "lo",
+        CmpOp::Ule => "ls",
+        CmpOp::Ugt => "hi",
+        CmpOp::Uge => "hs",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use backend::Backend;
+    use rstest::rstest;
+
+    use super::Aarch64Backend;
+
+    #[rstest(
+        name,
+        regalloc,
+        case("return", true),
+        case("arith", true),
+        case("arith_stack", false),
+        case("branch", true),
+        case("loop", true),
+        case("call", true),
+        case("unsigned", true),
+        case("constants", true)
+    )]
+    fn test_golden(name: &str, regalloc: bool) {
+        golden::check(env!("CARGO_MANIFEST_DIR"), name, "s", |nodes| {
+            let module = ir::lower::lower(&nodes).unwrap();
+            let mut aarch64 = Aarch64Backend::new();
+            backend::generate_module(&mut aarch64, &module, regalloc).unwrap();
+            aarch64.finish()
+        });
+    }
+
+    #[test]
+    fn test_data() {
+        let mut aarch64 = Aarch64Backend::new();
+        aarch64.data("table", &[1, -2]);
+        let expect = "\t.text\n\t.data\n\t.globl table\ntable:\n\t.quad 1\n\t.quad -2\n\t.text\n";
+        assert_eq!(expect, aarch64.finish());
+    }
+}
@@ -0,0 +1,345 @@
+use std::io::{self, Write};
+
+use crate::object::{Object, RelocKind, RelocTarget, SectionId};
+
+const EHDR_SIZE: u64 = 64;
+const SHDR_SIZE: u64 = 64;
+const SYM_SIZE: u64 = 24;
+const RELA_SIZE: u64 = 24;
+
+const ET_REL: u16 = 1;
+const EM_X86_64: u16 = 62;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+
+const SHF_WRITE: u64 = 0x1;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+const SHF_INFO_LINK: u64 = 0x40;
+
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_NOTYPE: u8 = 0;
+const STT_SECTION: u8 = 3;
+
+const R_X86_64_PC32: u64 = 2;
+const R_X86_64_PLT32: u64 = 4;
+
+// section header indices
+const TEXT: u16 = 1;
+const DATA: u16 = 2;
+const RELA_TEXT: u16 = 3;
+const SYMTAB: u16 = 4;
+const STRTAB: u16 = 5;
+const SHSTRTAB: u16 = 6;
+const NOTE_GNU_STACK: u16 = 7;
+const SECTIONS: u16 = 8;
+
+// symbol table indices of the section symbols
+const TEXT_SYM: u64 = 1;
+const DATA_SYM: u64 = 2;
+
+/// Write an object as an ELF64 relocatable file for x86-64.
+pub fn write_elf<W: Write>(object: &Object, w: &mut W) -> io::Result<()> {
+    let mut shstrtab = StrTab::default();
+    let names: Vec<u32> = [
+        "",
+        ".text",
+        ".data",
+        ".rela.text",
+        ".symtab",
+        ".strtab",
+        ".shstrtab",
+        ".note.GNU-stack",
+    ]
+    .iter()
+    .map(|n| shstrtab.add(n))
+    .collect();
+
+    // locals have to precede globals in the symbol table
+    let mut strtab = StrTab::default();
+    let mut symtab = Vec::new();
+    write_sym(&mut symtab, 0, 0, 0, 0, 0);
+    write_sym(&mut symtab, 0, (STB_LOCAL << 4) | STT_SECTION, TEXT, 0, 0);
+    write_sym(&mut symtab, 0, (STB_LOCAL << 4) | STT_SECTION, DATA, 0, 0);
+    let mut order: Vec<&crate::object::Symbol> =
+        object.symbols.iter().filter(|s| !s.global).collect();
+    let first_global = (order.len() + 3) as u32;
+    order.extend(object.symbols.iter().filter(|s| s.global));
+    for sym in order.iter() {
+        let bind = if sym.global { STB_GLOBAL } else { STB_LOCAL };
+        let shndx = match sym.section {
+            Some(SectionId::Text) => TEXT,
+            Some(SectionId::Data) => DATA,
+            None => 0,
+        };
+        let name = strtab.add(&sym.name);
+        write_sym(
+            &mut symtab,
+            name,
+            (bind << 4) | STT_NOTYPE,
+            shndx,
+            sym.offset,
+            0,
+        );
+    }
+
+    let mut rela = Vec::new();
+    for reloc in object.relocations.iter() {
+        let sym = match &reloc.target {
+            RelocTarget::Section(SectionId::Text) => TEXT_SYM,
+            RelocTarget::Section(SectionId::Data) => DATA_SYM,
+            RelocTarget::Symbol(name) => {
+                let index = order.iter().position(|s| &s.name == name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("undefined symbol: {name}"),
+                    )
+                })?;
+                index as u64 + 3
+            }
+        };
+        let kind = match reloc.kind {
+            RelocKind::Pc32 => R_X86_64_PC32,
+            RelocKind::Plt32 => R_X86_64_PLT32,
+        };
+        rela.extend_from_slice(&reloc.offset.to_le_bytes());
+        rela.extend_from_slice(&((sym << 32) | kind).to_le_bytes());
+        rela.extend_from_slice(&reloc.addend.to_le_bytes());
+    }
+
+    // lay out the section contents after the ELF header
+    let text_off = EHDR_SIZE;
+    let data_off = align(text_off + object.text.len() as u64, 8);
+    let rela_off = align(data_off + object.data.len() as u64, 8);
+    let symtab_off = rela_off + rela.len() as u64;
+    let strtab_off = symtab_off + symtab.len() as u64;
+    let shstrtab_off = strtab_off + strtab.0.len() as u64;
+    let shdr_off = align(shstrtab_off + shstrtab.0.len() as u64, 8);
+
+    let mut buf = Vec::new();
+    // e_ident
+    buf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+    buf.extend_from_slice(&[0; 8]);
+    buf.extend_from_slice(&ET_REL.to_le_bytes());
+    buf.extend_from_slice(&EM_X86_64.to_le_bytes());
+    buf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    buf.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+    buf.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+    buf.extend_from_slice(&shdr_off.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    buf.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+    buf.extend_from_slice(&0u16.to_le_bytes()); // e_phentsize
+    buf.extend_from_slice(&0u16.to_le_bytes()); // e_phnum
+    buf.extend_from_slice(&(SHDR_SIZE as u16).to_le_bytes());
+    buf.extend_from_slice(&SECTIONS.to_le_bytes());
+    buf.extend_from_slice(&SHSTRTAB.to_le_bytes());
+
+    buf.extend_from_slice(&object.text);
+    buf.resize(data_off as usize, 0);
+    buf.extend_from_slice(&object.data);
+    buf.resize(rela_off as usize, 0);
+    buf.extend_from_slice(&rela);
+    buf.extend_from_slice(&symtab);
+    buf.extend_from_slice(&strtab.0);
+    buf.extend_from_slice(&shstrtab.0);
+    buf.resize(shdr_off as usize, 0);
+
+    let headers = [
+        SectionHeader::default(),
+        SectionHeader {
+            name: names[TEXT as usize],
+            kind: SHT_PROGBITS,
+            flags: SHF_ALLOC | SHF_EXECINSTR,
+            offset: text_off,
+            size: object.text.len() as u64,
+            align: 16,
+            ..Default::default()
+        },
+        SectionHeader {
+            name: names[DATA as usize],
+            kind: SHT_PROGBITS,
+            flags: SHF_WRITE | SHF_ALLOC,
+            offset: data_off,
+            size: object.data.len() as u64,
+            align: 8,
+            ..Default::default()
+        },
+        SectionHeader {
+            name: names[RELA_TEXT as usize],
+            kind: SHT_RELA,
+            flags: SHF_INFO_LINK,
+            offset: rela_off,
+            size: rela.len() as u64,
+            link: SYMTAB as u32,
+            info: TEXT as u32,
+            align: 8,
+            entsize: RELA_SIZE,
+        },
+        SectionHeader {
+            name: names[SYMTAB as usize],
+            kind: SHT_SYMTAB,
+            offset: symtab_off,
+            size: symtab.len() as u64,
+            link: STRTAB as u32,
+            info: first_global,
+            align: 8,
+            entsize: SYM_SIZE,
+            ..Default::default()
+        },
+        SectionHeader {
+            name: names[STRTAB as usize],
+            kind: SHT_STRTAB,
+            offset: strtab_off,
+            size: strtab.0.len() as u64,
+            align: 1,
+            ..Default::default()
+        },
+        SectionHeader {
+            name: names[SHSTRTAB as usize],
+            kind: SHT_STRTAB,
+            offset: shstrtab_off,
+            size: shstrtab.0.len() as u64,
+            align: 1,
+            ..Default::default()
+        },
+        // marks the stack as non executable
+        SectionHeader {
+            name: names[NOTE_GNU_STACK as usize],
+            kind: SHT_PROGBITS,
+            offset: shdr_off,
+            align: 1,
+            ..Default::default()
+        },
+    ];
+    for header in headers.iter() {
+        header.write(&mut buf);
+    }
+
+    w.write_all(&buf)
+}
+
+#[derive(Debug, Default)]
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+impl SectionHeader {
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.name.to_le_bytes());
+        buf.extend_from_slice(&self.kind.to_le_bytes());
+        buf.extend_from_slice(&self.flags.to_le_bytes());
+        buf.extend_from_slice(&0u64.to_le_bytes()); // sh_addr
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+        buf.extend_from_slice(&self.size.to_le_bytes());
+        buf.extend_from_slice(&self.link.to_le_bytes());
+        buf.extend_from_slice(&self.info.to_le_bytes());
+        buf.extend_from_slice(&self.align.to_le_bytes());
+        buf.extend_from_slice(&self.entsize.to_le_bytes());
+    }
+}
+
+// string table starting with the empty string
+#[derive(Debug)]
+struct StrTab(Vec<u8>);
+
+impl Default for StrTab {
+    fn default() -> Self {
+        Self(vec![0])
+    }
+}
+
+impl StrTab {
+    fn add(&mut self, s: &str) -> u32 {
+        if s.is_empty() {
+            return 0;
+        }
+        let offset = self.0.len() as u32;
+        self.0.extend_from_slice(s.as_bytes());
+        self.0.push(0);
+        offset
+    }
+}
+
+fn write_sym(buf: &mut Vec<u8>, name: u32, info: u8, shndx: u16, value: u64, size: u64) {
+    buf.extend_from_slice(&name.to_le_bytes());
+    buf.push(info);
+    buf.push(0); // st_other
+    buf.extend_from_slice(&shndx.to_le_bytes());
+    buf.extend_from_slice(&value.to_le_bytes());
+    buf.extend_from_slice(&size.to_le_bytes());
+}
+
+fn align(n: u64, to: u64) -> u64 {
+    n.div_ceil(to) * to
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::{Object, RelocKind, RelocTarget, Relocation, SectionId, Symbol};
+
+    use super::write_elf;
+
+    fn read_u16(buf: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes(buf[at..at + 2].try_into().unwrap())
+    }
+
+    fn read_u64(buf: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_write_elf() {
+        let object = Object {
+            text: vec![0xe8, 0, 0, 0, 0, 0xc3],
+            data: vec![],
+            symbols: vec![
+                Symbol {
+                    name: "main".to_string(),
+                    section: Some(SectionId::Text),
+                    offset: 0,
+                    global: true,
+                },
+                Symbol {
+                    name: "foo".to_string(),
+                    section: None,
+                    offset: 0,
+                    global: true,
+                },
+            ],
+            relocations: vec![Relocation {
+                offset: 1,
+                kind: RelocKind::Plt32,
+                target: RelocTarget::Symbol("foo".to_string()),
+                addend: -4,
+            }],
+        };
+        let mut buf = Vec::new();
+        write_elf(&object, &mut buf).unwrap();
+
+        assert_eq!(&[0x7f, b'E', b'L', b'F', 2, 1, 1], &buf[0..7]);
+        assert_eq!(1, read_u16(&buf, 16)); // ET_REL
+        assert_eq!(62, read_u16(&buf, 18)); // EM_X86_64
+        assert_eq!(8, read_u16(&buf, 60)); // e_shnum
+                                           // the text section directly follows the header
+        assert_eq!(&object.text[..], &buf[64..70]);
+
+        // .rela.text: r_offset, r_info(sym 4 = foo after 3 section entries and main, PLT32)
+        let shoff = read_u64(&buf, 40) as usize;
+        let rela = read_u64(&buf, shoff + 64 * 3 + 24) as usize;
+        assert_eq!(1, read_u64(&buf, rela));
+        assert_eq!((4 << 32) | 4, read_u64(&buf, rela + 8));
+        assert_eq!(-4, read_u64(&buf, rela + 16) as i64);
+    }
+}
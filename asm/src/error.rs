@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unsupported instruction: {0}")]
+    UnsupportedInstruction(String),
+    #[error("unsupported operand: {0}")]
+    UnsupportedOperand(String),
+    #[error("duplicate label: {0}")]
+    DuplicateLabel(String),
+    #[error("immediate out of range: {0}")]
+    ImmediateOutOfRange(i64),
+}
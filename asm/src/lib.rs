@@ -1 +1,4 @@
+pub mod elf;
+pub mod error;
+pub mod object;
 pub mod x86;
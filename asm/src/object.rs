@@ -0,0 +1,53 @@
+/// Sections of an object file produced by the assembler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionId {
+    Text,
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    /// `None` for symbols defined in another object.
+    pub section: Option<SectionId>,
+    pub offset: u64,
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocKind {
+    /// 32 bit pc-relative address, R_X86_64_PC32
+    Pc32,
+    /// 32 bit pc-relative address of a function through the PLT, R_X86_64_PLT32
+    Plt32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelocTarget {
+    Symbol(String),
+    Section(SectionId),
+}
+
+/// Relocation applied to the 32 bit field at `offset` in the text section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    pub offset: u64,
+    pub kind: RelocKind,
+    pub target: RelocTarget,
+    pub addend: i64,
+}
+
+/// Machine code and data of one translation unit, independent of the file format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Object {
+    pub text: Vec<u8>,
+    pub data: Vec<u8>,
+    pub symbols: Vec<Symbol>,
+    pub relocations: Vec<Relocation>,
+}
+
+impl Object {
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+}
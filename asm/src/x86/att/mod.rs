@@ -0,0 +1,3 @@
+mod printer;
+
+pub use printer::{format_instr, format_operand, write_module};
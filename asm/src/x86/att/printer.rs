@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+
+use crate::x86::{
+    directive::Directive,
+    instr::Instr,
+    module::{AsmModule, Item},
+    operand::{Memory, Operand},
+    reg::Size,
+};
+
+pub const ATT_SYNTAX: &str = ".att_syntax";
+
+pub fn write_module<W: Write>(module: &AsmModule, w: &mut W) -> io::Result<()> {
+    writeln!(w, "{ATT_SYNTAX}")?;
+    for item in module.items.iter() {
+        match item {
+            Item::Directive(d) => writeln!(w, "{}", format_directive(d))?,
+            Item::Label(l) => writeln!(w, "{l}:")?,
+            Item::Instr(i) => writeln!(w, "\t{}", format_instr(i))?,
+        }
+    }
+    Ok(())
+}
+
+fn format_directive(directive: &Directive) -> String {
+    match directive {
+        Directive::Globl(s) => format!(".globl {s}"),
+        Directive::Text => ".text".to_string(),
+        Directive::Data => ".data".to_string(),
+        Directive::Quad(n) => format!(".quad {n}"),
+        Directive::Zero(n) => format!(".zero {n}"),
+    }
+}
+
+pub fn format_instr(instr: &Instr) -> String {
+    let mnemonic = match instr {
+        Instr::Cqo => "cqto".to_string(),
+        // movzx encodes both widths in AT&T, e.g. movzbq
+        Instr::Movzx(dst, src) => format!(
+            "movz{}{}",
+            suffix(src.size().unwrap_or(Size::Byte)),
+            suffix(dst.size().unwrap_or(Size::Qword))
+        ),
+        Instr::Movsx(dst, src) => format!(
+            "movs{}{}",
+            suffix(src.size().unwrap_or(Size::Byte)),
+            suffix(dst.size().unwrap_or(Size::Qword))
+        ),
+        Instr::Set(_, _) | Instr::Jmp(_) | Instr::Jcc(_, _) | Instr::Call(_) | Instr::Ret => {
+            instr.mnemonic()
+        }
+        _ => {
+            let size = instr
+                .operands()
+                .iter()
+                .find_map(|op| op.size())
+                .unwrap_or(Size::Qword);
+            format!("{}{}", instr.mnemonic(), suffix(size))
+        }
+    };
+    let operands: Vec<String> = instr.operands().iter().rev().map(format_operand).collect();
+    if operands.is_empty() {
+        mnemonic
+    } else {
+        format!("{mnemonic} {}", operands.join(", "))
+    }
+}
+
+pub fn format_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Reg(reg, size) => format!("%{}", reg.name(*size)),
+        Operand::Imm(n) => format!("${n}"),
+        Operand::Mem(m) => format_memory(m),
+        Operand::Label(l) => l.clone(),
+    }
+}
+
+fn format_memory(m: &Memory) -> String {
+    let mut s = String::new();
+    if let Some(symbol) = &m.symbol {
+        s += symbol;
+        if m.disp > 0 {
+            s += &format!("+{}", m.disp);
+        } else if m.disp < 0 {
+            s += &m.disp.to_string();
+        }
+    } else if m.disp != 0 || (m.base.is_none() && m.index.is_none()) {
+        s += &m.disp.to_string();
+    }
+    match (&m.base, &m.index) {
+        (Some(base), Some((index, scale))) => s += &format!("(%{base},%{index},{scale})"),
+        (Some(base), None) => s += &format!("(%{base})"),
+        (None, Some((index, scale))) => s += &format!("(,%{index},{scale})"),
+        (None, None) if m.symbol.is_some() => s += "(%rip)",
+        (None, None) => {}
+    }
+    s
+}
+
+fn suffix(size: Size) -> &'static str {
+    match size {
+        Size::Byte => "b",
+        Size::Word => "w",
+        Size::Dword => "l",
+        Size::Qword => "q",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::x86::{
+        instr::{Cond, Instr},
+        operand::{Memory, Operand},
+        reg::Reg,
+    };
+
+    use super::format_instr;
+
+    #[rstest(
+        input,
+        expect,
+        case(Instr::Push(Operand::imm(42)), "pushq $42"),
+        case(Instr::Pop(Operand::reg(Reg::Rdi)), "popq %rdi"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rax, 0)), "movq (%rax), %rax"),
+        case(Instr::Mov(Operand::mem(Reg::Rax, 0), Operand::reg(Reg::Rdi)), "movq %rdi, (%rax)"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rbp, -8)), "movq -8(%rbp), %rax"),
+        case(Instr::Mov(Operand::mem(Reg::Rbp, -8), Operand::imm(1)), "movq $1, -8(%rbp)"),
+        case(Instr::Lea(Operand::reg(Reg::Rax), Operand::Mem(Memory::base(Reg::Rdi, 16).with_index(Reg::Rsi, 8))), "leaq 16(%rdi,%rsi,8), %rax"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::Mem(Memory::symbol("x"))), "movq x(%rip), %rax"),
+        case(Instr::Sub(Operand::reg(Reg::Rax), Operand::reg(Reg::Rdi)), "subq %rdi, %rax"),
+        case(Instr::Idiv(Operand::reg(Reg::Rdi)), "idivq %rdi"),
+        case(Instr::Div(Operand::reg(Reg::Rdi)), "divq %rdi"),
+        case(Instr::Set(Cond::Le, Operand::reg8(Reg::Rax)), "setle %al"),
+        case(Instr::Movzx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), "movzbq %al, %rax"),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), "movsbq %al, %rax"),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg32(Reg::Rax)), "movslq %eax, %rax"),
+        case(Instr::Mov(Operand::reg32(Reg::Rax), Operand::reg32(Reg::Rax)), "movl %eax, %eax"),
+        case(Instr::Jcc(Cond::E, ".Lend0".to_string()), "je .Lend0"),
+        case(Instr::Call("foo".to_string()), "call foo"),
+        case(Instr::Cqo, "cqto"),
+        case(Instr::Ret, "ret"),
+    )]
+    fn test_format_instr(input: Instr, expect: &str) {
+        assert_eq!(expect, format_instr(&input));
+    }
+}
@@ -0,0 +1,9 @@
+/// Assembler directives.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Directive {
+    Globl(String),
+    Text,
+    Data,
+    Quad(i64),
+    Zero(u32),
+}
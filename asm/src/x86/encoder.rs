@@ -0,0 +1,662 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::Error,
+    object::{Object, RelocKind, RelocTarget, Relocation, SectionId, Symbol},
+    x86::{
+        directive::Directive,
+        instr::{Cond, Instr},
+        intel,
+        module::{AsmModule, Item},
+        operand::{Memory, Operand},
+        reg::{Reg, Size},
+    },
+};
+
+/// Assemble a module into machine code.
+///
+/// Branches to labels in the text section are resolved here, everything else
+/// is left to the linker as a relocation.
+pub fn assemble(module: &AsmModule) -> Result<Object, Error> {
+    let mut encoder = Encoder::default();
+    for item in module.items.iter() {
+        match item {
+            Item::Directive(d) => encoder.directive(d),
+            Item::Label(l) => encoder.label(l)?,
+            Item::Instr(i) => encoder.instr(i)?,
+        }
+    }
+    encoder.finish()
+}
+
+/// Encode a single instruction, mainly for tests and debugging.
+///
+/// Label references are left as zero displacements.
+pub fn encode(instr: &Instr) -> Result<Vec<u8>, Error> {
+    let mut encoder = Encoder::default();
+    encoder.instr(instr)?;
+    Ok(encoder.text)
+}
+
+// reference to a label from a 32 bit field in the text section
+#[derive(Debug)]
+struct Fixup {
+    offset: u64,
+    label: String,
+    kind: RelocKind,
+    // bytes of the instruction that follow the 32 bit field
+    trailing: i64,
+    addend: i64,
+}
+
+#[derive(Debug)]
+struct Encoder {
+    section: SectionId,
+    text: Vec<u8>,
+    data: Vec<u8>,
+    labels: HashMap<String, (SectionId, u64)>,
+    label_order: Vec<String>,
+    globals: HashSet<String>,
+    fixups: Vec<Fixup>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self {
+            section: SectionId::Text,
+            text: Vec::new(),
+            data: Vec::new(),
+            labels: HashMap::new(),
+            label_order: Vec::new(),
+            globals: HashSet::new(),
+            fixups: Vec::new(),
+        }
+    }
+}
+
+impl Encoder {
+    fn directive(&mut self, directive: &Directive) {
+        match directive {
+            Directive::Globl(s) => {
+                self.globals.insert(s.clone());
+            }
+            Directive::Text => self.section = SectionId::Text,
+            Directive::Data => self.section = SectionId::Data,
+            Directive::Quad(n) => {
+                let bytes = n.to_le_bytes();
+                self.buf().extend_from_slice(&bytes);
+            }
+            Directive::Zero(n) => {
+                let len = self.buf().len() + *n as usize;
+                self.buf().resize(len, 0);
+            }
+        }
+    }
+
+    fn label(&mut self, label: &str) -> Result<(), Error> {
+        let offset = self.buf().len() as u64;
+        if self
+            .labels
+            .insert(label.to_string(), (self.section, offset))
+            .is_some()
+        {
+            return Err(Error::DuplicateLabel(label.to_string()));
+        }
+        self.label_order.push(label.to_string());
+        Ok(())
+    }
+
+    fn instr(&mut self, instr: &Instr) -> Result<(), Error> {
+        if self.section != SectionId::Text {
+            return Err(Error::UnsupportedInstruction(format!(
+                "{} outside of .text",
+                intel::format_instr(instr)
+            )));
+        }
+        match instr {
+            Instr::Mov(dst, src) => match (dst, src) {
+                (Operand::Reg(_, Size::Qword), Operand::Imm(n)) if !fits_i32(*n) => {
+                    let Operand::Reg(r, _) = dst else {
+                        unreachable!()
+                    };
+                    self.rex(true, 0, 0, r.number(), false);
+                    self.text.push(0xb8 + (r.number() & 7));
+                    self.text.extend_from_slice(&n.to_le_bytes());
+                    Ok(())
+                }
+                (_, Operand::Imm(n)) => {
+                    self.check_qword(dst, instr)?;
+                    self.modrm(&[0xc7], 0, dst, true, Imm::I32(*n))
+                }
+                // 32 bit moves zero the upper half of the destination
+                (Operand::Reg(_, Size::Dword), Operand::Reg(src, Size::Dword)) => {
+                    self.modrm(&[0x89], src.number(), dst, false, Imm::None)
+                }
+                (_, Operand::Reg(src, _)) => {
+                    self.check_qword(dst, instr)?;
+                    self.check_qword(&Operand::reg(*src), instr)?;
+                    self.modrm(&[0x89], src.number(), dst, true, Imm::None)
+                }
+                (Operand::Reg(dst, Size::Qword), Operand::Mem(_)) => {
+                    self.check_qword(src, instr)?;
+                    self.modrm(&[0x8b], dst.number(), src, true, Imm::None)
+                }
+                _ => Err(unsupported(instr)),
+            },
+            Instr::Movzx(Operand::Reg(dst, Size::Qword), src) if src.size() == Some(Size::Byte) => {
+                self.modrm(&[0x0f, 0xb6], dst.number(), src, true, Imm::None)
+            }
+            Instr::Movsx(Operand::Reg(dst, Size::Qword), src) => match src.size() {
+                Some(Size::Byte) => self.modrm(&[0x0f, 0xbe], dst.number(), src, true, Imm::None),
+                Some(Size::Dword) => self.modrm(&[0x63], dst.number(), src, true, Imm::None),
+                _ => Err(unsupported(instr)),
+            },
+            Instr::Lea(Operand::Reg(dst, Size::Qword), src @ Operand::Mem(_)) => {
+                self.modrm(&[0x8d], dst.number(), src, true, Imm::None)
+            }
+            Instr::Push(op) => match op {
+                Operand::Reg(r, Size::Qword) => {
+                    self.rex(false, 0, 0, r.number(), false);
+                    self.text.push(0x50 + (r.number() & 7));
+                    Ok(())
+                }
+                Operand::Imm(n) if fits_i8(*n) => {
+                    self.text.push(0x6a);
+                    self.text.push(*n as u8);
+                    Ok(())
+                }
+                Operand::Imm(n) if fits_i32(*n) => {
+                    self.text.push(0x68);
+                    self.text.extend_from_slice(&(*n as i32).to_le_bytes());
+                    Ok(())
+                }
+                Operand::Imm(n) => Err(Error::ImmediateOutOfRange(*n)),
+                Operand::Mem(m) if m.size == Size::Qword => {
+                    self.modrm(&[0xff], 6, op, false, Imm::None)
+                }
+                _ => Err(unsupported(instr)),
+            },
+            Instr::Pop(op) => match op {
+                Operand::Reg(r, Size::Qword) => {
+                    self.rex(false, 0, 0, r.number(), false);
+                    self.text.push(0x58 + (r.number() & 7));
+                    Ok(())
+                }
+                Operand::Mem(m) if m.size == Size::Qword => {
+                    self.modrm(&[0x8f], 0, op, false, Imm::None)
+                }
+                _ => Err(unsupported(instr)),
+            },
+            Instr::Add(dst, src) => self.arith(instr, 0x01, 0x03, 0, dst, src),
+            Instr::Sub(dst, src) => self.arith(instr, 0x29, 0x2b, 5, dst, src),
+            Instr::Cmp(dst, src) => self.arith(instr, 0x39, 0x3b, 7, dst, src),
+            Instr::Imul(Operand::Reg(dst, Size::Qword), src) => match src {
+                Operand::Imm(n) if fits_i8(*n) => self.modrm(
+                    &[0x6b],
+                    dst.number(),
+                    &Operand::reg(*dst),
+                    true,
+                    Imm::I8(*n),
+                ),
+                Operand::Imm(n) => self.modrm(
+                    &[0x69],
+                    dst.number(),
+                    &Operand::reg(*dst),
+                    true,
+                    Imm::I32(*n),
+                ),
+                _ => {
+                    self.check_qword(src, instr)?;
+                    self.modrm(&[0x0f, 0xaf], dst.number(), src, true, Imm::None)
+                }
+            },
+            Instr::Cqo => {
+                self.text.extend_from_slice(&[0x48, 0x99]);
+                Ok(())
+            }
+            Instr::Idiv(op) => {
+                self.check_qword(op, instr)?;
+                self.modrm(&[0xf7], 7, op, true, Imm::None)
+            }
+            Instr::Div(op) => {
+                self.check_qword(op, instr)?;
+                self.modrm(&[0xf7], 6, op, true, Imm::None)
+            }
+            Instr::Set(cond, op) if op.size() == Some(Size::Byte) => {
+                self.modrm(&[0x0f, 0x90 + cond_code(cond)], 0, op, false, Imm::None)
+            }
+            Instr::Jmp(label) => {
+                self.text.push(0xe9);
+                self.rel32(label, RelocKind::Plt32);
+                Ok(())
+            }
+            Instr::Jcc(cond, label) => {
+                self.text.extend_from_slice(&[0x0f, 0x80 + cond_code(cond)]);
+                self.rel32(label, RelocKind::Plt32);
+                Ok(())
+            }
+            Instr::Call(label) => {
+                self.text.push(0xe8);
+                self.rel32(label, RelocKind::Plt32);
+                Ok(())
+            }
+            Instr::Ret => {
+                self.text.push(0xc3);
+                Ok(())
+            }
+            _ => Err(unsupported(instr)),
+        }
+    }
+
+    // add, sub and cmp share their encodings apart from the opcodes
+    fn arith(
+        &mut self,
+        instr: &Instr,
+        rm_reg: u8,
+        reg_rm: u8,
+        ext: u8,
+        dst: &Operand,
+        src: &Operand,
+    ) -> Result<(), Error> {
+        self.check_qword(dst, instr)?;
+        match src {
+            Operand::Imm(n) if fits_i8(*n) => self.modrm(&[0x83], ext, dst, true, Imm::I8(*n)),
+            Operand::Imm(n) => self.modrm(&[0x81], ext, dst, true, Imm::I32(*n)),
+            Operand::Reg(r, Size::Qword) => self.modrm(&[rm_reg], r.number(), dst, true, Imm::None),
+            Operand::Mem(m) if m.size == Size::Qword => match dst {
+                Operand::Reg(r, _) => self.modrm(&[reg_rm], r.number(), src, true, Imm::None),
+                _ => Err(unsupported(instr)),
+            },
+            _ => Err(unsupported(instr)),
+        }
+    }
+
+    // emit `REX? opcode ModRM SIB? disp? imm?` with `reg` in the ModRM.reg field
+    fn modrm(
+        &mut self,
+        opcode: &[u8],
+        reg: u8,
+        rm: &Operand,
+        w: bool,
+        imm: Imm,
+    ) -> Result<(), Error> {
+        match rm {
+            Operand::Reg(r, size) => {
+                // spl, bpl, sil and dil are only reachable with a REX prefix
+                let byte_reg = *size == Size::Byte && (4..8).contains(&r.number());
+                self.rex(w, reg, 0, r.number(), byte_reg);
+                self.text.extend_from_slice(opcode);
+                self.text.push(0xc0 | ((reg & 7) << 3) | (r.number() & 7));
+            }
+            Operand::Mem(m) => self.memory(opcode, reg, m, w, imm.len())?,
+            _ => return Err(Error::UnsupportedOperand(intel::format_operand(rm))),
+        }
+        match imm {
+            Imm::None => {}
+            Imm::I8(n) => self.text.push(n as u8),
+            Imm::I32(n) if fits_i32(n) => self.text.extend_from_slice(&(n as i32).to_le_bytes()),
+            Imm::I32(n) => return Err(Error::ImmediateOutOfRange(n)),
+        }
+        Ok(())
+    }
+
+    fn memory(
+        &mut self,
+        opcode: &[u8],
+        reg: u8,
+        m: &Memory,
+        w: bool,
+        trailing: i64,
+    ) -> Result<(), Error> {
+        let index = m.index.map(|(r, _)| r.number()).unwrap_or(0);
+        let base = m.base.map(|r| r.number()).unwrap_or(0);
+        self.rex(w, reg, index, base, false);
+        self.text.extend_from_slice(opcode);
+        let reg = (reg & 7) << 3;
+
+        if let Some(symbol) = &m.symbol {
+            if m.base.is_some() || m.index.is_some() {
+                return Err(Error::UnsupportedOperand(intel::format_operand(
+                    &Operand::Mem(m.clone()),
+                )));
+            }
+            // rip relative
+            self.text.push(reg | 0b101);
+            self.fixups.push(Fixup {
+                offset: self.text.len() as u64,
+                label: symbol.clone(),
+                kind: RelocKind::Pc32,
+                trailing,
+                addend: m.disp as i64,
+            });
+            self.text.extend_from_slice(&[0; 4]);
+            return Ok(());
+        }
+
+        let Some(base_reg) = m.base else {
+            // [index * scale + disp32] without a base
+            let (index, scale) = m.index.ok_or_else(|| {
+                Error::UnsupportedOperand(intel::format_operand(&Operand::Mem(m.clone())))
+            })?;
+            self.text.push(reg | 0b100);
+            self.text
+                .push(scale_bits(scale)? | ((index.number() & 7) << 3) | 0b101);
+            self.text.extend_from_slice(&m.disp.to_le_bytes());
+            return Ok(());
+        };
+
+        // rbp and r13 have no encoding without a displacement
+        let mode = if m.disp == 0 && base & 7 != 5 {
+            0b00
+        } else if fits_i8(m.disp as i64) {
+            0b01
+        } else {
+            0b10
+        };
+        // rsp and r12 as a base need a SIB byte
+        if m.index.is_some() || base & 7 == 4 {
+            self.text.push((mode << 6) | reg | 0b100);
+            let sib = match m.index {
+                Some((Reg::Rsp, _)) => {
+                    return Err(Error::UnsupportedOperand(intel::format_operand(
+                        &Operand::Mem(m.clone()),
+                    )))
+                }
+                Some((index, scale)) => scale_bits(scale)? | ((index.number() & 7) << 3),
+                None => 0b100 << 3,
+            };
+            self.text.push(sib | (base_reg.number() & 7));
+        } else {
+            self.text.push((mode << 6) | reg | (base & 7));
+        }
+        match mode {
+            0b01 => self.text.push(m.disp as u8),
+            0b10 => self.text.extend_from_slice(&m.disp.to_le_bytes()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn rex(&mut self, w: bool, reg: u8, index: u8, base: u8, force: bool) {
+        let rex = 0x40 | ((w as u8) << 3) | ((reg >> 3) << 2) | ((index >> 3) << 1) | (base >> 3);
+        if rex != 0x40 || force {
+            self.text.push(rex);
+        }
+    }
+
+    fn rel32(&mut self, label: &str, kind: RelocKind) {
+        self.fixups.push(Fixup {
+            offset: self.text.len() as u64,
+            label: label.to_string(),
+            kind,
+            trailing: 0,
+            addend: 0,
+        });
+        self.text.extend_from_slice(&[0; 4]);
+    }
+
+    fn check_qword(&self, op: &Operand, instr: &Instr) -> Result<(), Error> {
+        match op.size() {
+            Some(Size::Qword) => Ok(()),
+            _ => Err(unsupported(instr)),
+        }
+    }
+
+    fn buf(&mut self) -> &mut Vec<u8> {
+        match self.section {
+            SectionId::Text => &mut self.text,
+            SectionId::Data => &mut self.data,
+        }
+    }
+
+    fn finish(mut self) -> Result<Object, Error> {
+        let mut relocations = Vec::new();
+        let mut externals = Vec::new();
+        for fixup in self.fixups.iter() {
+            let addend = fixup.addend - 4 - fixup.trailing;
+            match self.labels.get(&fixup.label) {
+                Some((SectionId::Text, target)) => {
+                    let rel = *target as i64 + addend - fixup.offset as i64;
+                    let at = fixup.offset as usize;
+                    self.text[at..at + 4].copy_from_slice(&(rel as i32).to_le_bytes());
+                }
+                Some((section, target)) => relocations.push(Relocation {
+                    offset: fixup.offset,
+                    kind: RelocKind::Pc32,
+                    target: RelocTarget::Section(*section),
+                    addend: *target as i64 + addend,
+                }),
+                None => {
+                    if !externals.contains(&fixup.label) {
+                        externals.push(fixup.label.clone());
+                    }
+                    relocations.push(Relocation {
+                        offset: fixup.offset,
+                        kind: fixup.kind,
+                        target: RelocTarget::Symbol(fixup.label.clone()),
+                        addend,
+                    })
+                }
+            }
+        }
+
+        // local labels like .Lend0 do not become symbols
+        let mut symbols: Vec<Symbol> = self
+            .label_order
+            .iter()
+            .filter(|l| !l.starts_with(".L"))
+            .map(|l| {
+                let (section, offset) = self.labels[l];
+                Symbol {
+                    name: l.clone(),
+                    section: Some(section),
+                    offset,
+                    global: self.globals.contains(l),
+                }
+            })
+            .collect();
+        let mut globals: Vec<&String> = self.globals.iter().collect();
+        globals.sort();
+        for name in globals.into_iter().chain(externals.iter()) {
+            if !self.labels.contains_key(name) && !symbols.iter().any(|s| &s.name == name) {
+                symbols.push(Symbol {
+                    name: name.clone(),
+                    section: None,
+                    offset: 0,
+                    global: true,
+                });
+            }
+        }
+
+        Ok(Object {
+            text: self.text,
+            data: self.data,
+            symbols,
+            relocations,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Imm {
+    None,
+    I8(i64),
+    I32(i64),
+}
+
+impl Imm {
+    fn len(&self) -> i64 {
+        match self {
+            Imm::None => 0,
+            Imm::I8(_) => 1,
+            Imm::I32(_) => 4,
+        }
+    }
+}
+
+fn cond_code(cond: &Cond) -> u8 {
+    match cond {
+        Cond::B => 0x2,
+        Cond::Ae => 0x3,
+        Cond::E => 0x4,
+        Cond::Ne => 0x5,
+        Cond::Be => 0x6,
+        Cond::A => 0x7,
+        Cond::L => 0xc,
+        Cond::Ge => 0xd,
+        Cond::Le => 0xe,
+        Cond::G => 0xf,
+    }
+}
+
+fn scale_bits(scale: u8) -> Result<u8, Error> {
+    match scale {
+        1 => Ok(0),
+        2 => Ok(1 << 6),
+        4 => Ok(2 << 6),
+        8 => Ok(3 << 6),
+        _ => Err(Error::UnsupportedOperand(format!("scale {scale}"))),
+    }
+}
+
+fn fits_i8(n: i64) -> bool {
+    i8::try_from(n).is_ok()
+}
+
+fn fits_i32(n: i64) -> bool {
+    i32::try_from(n).is_ok()
+}
+
+fn unsupported(instr: &Instr) -> Error {
+    Error::UnsupportedInstruction(intel::format_instr(instr))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        object::{RelocKind, RelocTarget, SectionId},
+        x86::{
+            directive::Directive,
+            instr::{Cond, Instr},
+            module::AsmModule,
+            operand::{Memory, Operand},
+            reg::Reg,
+        },
+    };
+
+    use super::{assemble, encode};
+
+    #[rstest(
+        input,
+        expect,
+        case(Instr::Push(Operand::reg(Reg::Rbp)), vec![0x55]),
+        case(Instr::Push(Operand::reg(Reg::R12)), vec![0x41, 0x54]),
+        case(Instr::Push(Operand::imm(42)), vec![0x6a, 0x2a]),
+        case(Instr::Push(Operand::imm(1000)), vec![0x68, 0xe8, 0x03, 0x00, 0x00]),
+        case(Instr::Pop(Operand::reg(Reg::Rdi)), vec![0x5f]),
+        case(Instr::Pop(Operand::reg(Reg::R9)), vec![0x41, 0x59]),
+        case(Instr::Mov(Operand::reg(Reg::Rbp), Operand::reg(Reg::Rsp)), vec![0x48, 0x89, 0xe5]),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rax, 0)), vec![0x48, 0x8b, 0x00]),
+        case(Instr::Mov(Operand::mem(Reg::Rax, 0), Operand::reg(Reg::Rdi)), vec![0x48, 0x89, 0x38]),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rbp, -8)), vec![0x48, 0x8b, 0x45, 0xf8]),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rbp, 0)), vec![0x48, 0x8b, 0x45, 0x00]),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rsp, 8)), vec![0x48, 0x8b, 0x44, 0x24, 0x08]),
+        case(Instr::Mov(Operand::reg(Reg::R8), Operand::mem(Reg::R13, -512)), vec![0x4d, 0x8b, 0x85, 0x00, 0xfe, 0xff, 0xff]),
+        case(Instr::Mov(Operand::reg(Reg::Rsi), Operand::imm(2)), vec![0x48, 0xc7, 0xc6, 0x02, 0x00, 0x00, 0x00]),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::imm(0x1_0000_0000)), vec![0x48, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]),
+        case(Instr::Mov(Operand::mem(Reg::Rbp, -16), Operand::imm(1)), vec![0x48, 0xc7, 0x45, 0xf0, 0x01, 0x00, 0x00, 0x00]),
+        case(Instr::Lea(Operand::reg(Reg::Rax), Operand::Mem(Memory::base(Reg::Rdi, 16).with_index(Reg::Rsi, 8))), vec![0x48, 0x8d, 0x44, 0xf7, 0x10]),
+        case(Instr::Add(Operand::reg(Reg::Rax), Operand::reg(Reg::Rdi)), vec![0x48, 0x01, 0xf8]),
+        case(Instr::Sub(Operand::reg(Reg::Rsp), Operand::imm(208)), vec![0x48, 0x81, 0xec, 0xd0, 0x00, 0x00, 0x00]),
+        case(Instr::Sub(Operand::reg(Reg::Rax), Operand::imm(8)), vec![0x48, 0x83, 0xe8, 0x08]),
+        case(Instr::Add(Operand::reg(Reg::Rax), Operand::mem(Reg::Rbp, -8)), vec![0x48, 0x03, 0x45, 0xf8]),
+        case(Instr::Imul(Operand::reg(Reg::Rax), Operand::reg(Reg::Rdi)), vec![0x48, 0x0f, 0xaf, 0xc7]),
+        case(Instr::Imul(Operand::reg(Reg::Rax), Operand::imm(3)), vec![0x48, 0x6b, 0xc0, 0x03]),
+        case(Instr::Cmp(Operand::reg(Reg::Rax), Operand::imm(0)), vec![0x48, 0x83, 0xf8, 0x00]),
+        case(Instr::Cmp(Operand::reg(Reg::Rax), Operand::reg(Reg::Rdi)), vec![0x48, 0x39, 0xf8]),
+        case(Instr::Cqo, vec![0x48, 0x99]),
+        case(Instr::Idiv(Operand::reg(Reg::Rdi)), vec![0x48, 0xf7, 0xff]),
+        case(Instr::Div(Operand::reg(Reg::Rdi)), vec![0x48, 0xf7, 0xf7]),
+        case(Instr::Set(Cond::E, Operand::reg8(Reg::Rax)), vec![0x0f, 0x94, 0xc0]),
+        case(Instr::Set(Cond::L, Operand::reg8(Reg::Rdi)), vec![0x40, 0x0f, 0x9c, 0xc7]),
+        case(Instr::Movzx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), vec![0x48, 0x0f, 0xb6, 0xc0]),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), vec![0x48, 0x0f, 0xbe, 0xc0]),
+        case(Instr::Movsx(Operand::reg(Reg::R10), Operand::reg8(Reg::Rsi)), vec![0x4c, 0x0f, 0xbe, 0xd6]),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg32(Reg::Rax)), vec![0x48, 0x63, 0xc0]),
+        case(Instr::Movsx(Operand::reg(Reg::R11), Operand::reg32(Reg::R11)), vec![0x4d, 0x63, 0xdb]),
+        case(Instr::Mov(Operand::reg32(Reg::Rax), Operand::reg32(Reg::Rax)), vec![0x89, 0xc0]),
+        case(Instr::Mov(Operand::reg32(Reg::R10), Operand::reg32(Reg::R10)), vec![0x45, 0x89, 0xd2]),
+        case(Instr::Ret, vec![0xc3]),
+    )]
+    fn test_encode(input: Instr, expect: Vec<u8>) {
+        assert_eq!(expect, encode(&input).unwrap());
+    }
+
+    #[test]
+    fn test_assemble_branches_and_calls() {
+        let mut module = AsmModule::new();
+        module.directive(Directive::Globl("main".to_string()));
+        module.label("main");
+        module.label(".Lbegin0");
+        module.instr(Instr::Jcc(Cond::E, ".Lend0".to_string()));
+        module.instr(Instr::Call("foo".to_string()));
+        module.instr(Instr::Jmp(".Lbegin0".to_string()));
+        module.label(".Lend0");
+        module.instr(Instr::Ret);
+        let object = assemble(&module).unwrap();
+
+        assert_eq!(
+            vec![
+                0x0f, 0x84, 0x0a, 0x00, 0x00, 0x00, // je .Lend0
+                0xe8, 0x00, 0x00, 0x00, 0x00, // call foo
+                0xe9, 0xf0, 0xff, 0xff, 0xff, // jmp .Lbegin0
+                0xc3, // ret
+            ],
+            object.text
+        );
+        let main = object.symbol("main").unwrap();
+        assert!(main.global);
+        assert_eq!(Some(SectionId::Text), main.section);
+        assert_eq!(None, object.symbol("foo").unwrap().section);
+        assert!(object.symbol(".Lend0").is_none());
+        assert_eq!(1, object.relocations.len());
+        assert_eq!(7, object.relocations[0].offset);
+        assert_eq!(RelocKind::Plt32, object.relocations[0].kind);
+        assert_eq!(
+            RelocTarget::Symbol("foo".to_string()),
+            object.relocations[0].target
+        );
+        assert_eq!(-4, object.relocations[0].addend);
+    }
+
+    #[test]
+    fn test_assemble_rip_relative_data() {
+        let mut module = AsmModule::new();
+        module.directive(Directive::Data);
+        module.directive(Directive::Quad(1));
+        module.label(".Lx");
+        module.directive(Directive::Quad(2));
+        module.directive(Directive::Text);
+        module.instr(Instr::Mov(
+            Operand::Mem(Memory::symbol(".Lx")),
+            Operand::imm(3),
+        ));
+        let object = assemble(&module).unwrap();
+
+        assert_eq!(16, object.data.len());
+        assert_eq!(
+            vec![0x48, 0xc7, 0x05, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00],
+            object.text
+        );
+        assert_eq!(3, object.relocations[0].offset);
+        assert_eq!(RelocKind::Pc32, object.relocations[0].kind);
+        assert_eq!(
+            RelocTarget::Section(SectionId::Data),
+            object.relocations[0].target
+        );
+        // 8 bytes into .data, minus the displacement and the trailing immediate
+        assert_eq!(8 - 4 - 4, object.relocations[0].addend);
+    }
+}
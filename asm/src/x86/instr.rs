@@ -0,0 +1,126 @@
+use crate::x86::{operand::Operand, reg::Size};
+
+/// Condition codes used by `setcc` and `jcc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cond {
+    E,
+    Ne,
+    L,
+    Le,
+    G,
+    Ge,
+    B,
+    Be,
+    A,
+    Ae,
+}
+
+impl Cond {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Cond::E => "e",
+            Cond::Ne => "ne",
+            Cond::L => "l",
+            Cond::Le => "le",
+            Cond::G => "g",
+            Cond::Ge => "ge",
+            Cond::B => "b",
+            Cond::Be => "be",
+            Cond::A => "a",
+            Cond::Ae => "ae",
+        }
+    }
+
+    /// Condition that holds exactly when `self` does not.
+    pub fn negate(&self) -> Self {
+        match self {
+            Cond::E => Cond::Ne,
+            Cond::Ne => Cond::E,
+            Cond::L => Cond::Ge,
+            Cond::Le => Cond::G,
+            Cond::G => Cond::Le,
+            Cond::Ge => Cond::L,
+            Cond::B => Cond::Ae,
+            Cond::Be => Cond::A,
+            Cond::A => Cond::Be,
+            Cond::Ae => Cond::B,
+        }
+    }
+}
+
+/// x86-64 instructions emitted by the code generator.
+///
+/// Two operand forms are `(destination, source)` as in Intel syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Instr {
+    Mov(Operand, Operand),
+    Movzx(Operand, Operand),
+    /// `movsx` from a byte, `movsxd` from a dword.
+    Movsx(Operand, Operand),
+    Lea(Operand, Operand),
+    Push(Operand),
+    Pop(Operand),
+    Add(Operand, Operand),
+    Sub(Operand, Operand),
+    Imul(Operand, Operand),
+    Cqo,
+    Idiv(Operand),
+    Div(Operand),
+    Cmp(Operand, Operand),
+    Set(Cond, Operand),
+    Jmp(String),
+    Jcc(Cond, String),
+    Call(String),
+    Ret,
+}
+
+impl Instr {
+    pub fn mnemonic(&self) -> String {
+        match self {
+            Instr::Mov(_, _) => "mov".to_string(),
+            Instr::Movzx(_, _) => "movzx".to_string(),
+            Instr::Movsx(_, src) if src.size() == Some(Size::Dword) => "movsxd".to_string(),
+            Instr::Movsx(_, _) => "movsx".to_string(),
+            Instr::Lea(_, _) => "lea".to_string(),
+            Instr::Push(_) => "push".to_string(),
+            Instr::Pop(_) => "pop".to_string(),
+            Instr::Add(_, _) => "add".to_string(),
+            Instr::Sub(_, _) => "sub".to_string(),
+            Instr::Imul(_, _) => "imul".to_string(),
+            Instr::Cqo => "cqo".to_string(),
+            Instr::Idiv(_) => "idiv".to_string(),
+            Instr::Div(_) => "div".to_string(),
+            Instr::Cmp(_, _) => "cmp".to_string(),
+            Instr::Set(cond, _) => format!("set{}", cond.suffix()),
+            Instr::Jmp(_) => "jmp".to_string(),
+            Instr::Jcc(cond, _) => format!("j{}", cond.suffix()),
+            Instr::Call(_) => "call".to_string(),
+            Instr::Ret => "ret".to_string(),
+        }
+    }
+
+    /// Operands in Intel order, with branch targets as labels.
+    pub fn operands(&self) -> Vec<Operand> {
+        match self {
+            Instr::Mov(dst, src)
+            | Instr::Movzx(dst, src)
+            | Instr::Movsx(dst, src)
+            | Instr::Lea(dst, src)
+            | Instr::Add(dst, src)
+            | Instr::Sub(dst, src)
+            | Instr::Imul(dst, src)
+            | Instr::Cmp(dst, src) => vec![dst.clone(), src.clone()],
+            Instr::Push(op)
+            | Instr::Pop(op)
+            | Instr::Idiv(op)
+            | Instr::Div(op)
+            | Instr::Set(_, op) => {
+                vec![op.clone()]
+            }
+            Instr::Jmp(label) | Instr::Jcc(_, label) | Instr::Call(label) => {
+                vec![Operand::Label(label.clone())]
+            }
+            Instr::Cqo | Instr::Ret => vec![],
+        }
+    }
+}
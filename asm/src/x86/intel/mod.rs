@@ -1 +1,4 @@
 pub mod constants;
+mod printer;
+
+pub use printer::{format_instr, format_operand, write_module};
@@ -0,0 +1,130 @@
+use std::io::{self, Write};
+
+use crate::x86::{
+    directive::Directive,
+    instr::Instr,
+    module::{AsmModule, Item},
+    operand::{Memory, Operand},
+    reg::Size,
+};
+
+use super::constants;
+
+pub fn write_module<W: Write>(module: &AsmModule, w: &mut W) -> io::Result<()> {
+    writeln!(w, "{} {}", constants::INTEL_SYNTAX, constants::NOPREFIX)?;
+    for item in module.items.iter() {
+        match item {
+            Item::Directive(d) => writeln!(w, "{}", format_directive(d))?,
+            Item::Label(l) => writeln!(w, "{l}:")?,
+            Item::Instr(i) => writeln!(w, "\t{}", format_instr(i))?,
+        }
+    }
+    Ok(())
+}
+
+fn format_directive(directive: &Directive) -> String {
+    match directive {
+        Directive::Globl(s) => format!("{} {s}", constants::SEC_GLOBAL),
+        Directive::Text => ".text".to_string(),
+        Directive::Data => ".data".to_string(),
+        Directive::Quad(n) => format!(".quad {n}"),
+        Directive::Zero(n) => format!(".zero {n}"),
+    }
+}
+
+pub fn format_instr(instr: &Instr) -> String {
+    let operands = instr.operands();
+    if operands.is_empty() {
+        return instr.mnemonic();
+    }
+    // the width of a memory operand is implied by a register operand of the same size
+    let reg_size = operands.iter().find_map(|op| match op {
+        Operand::Reg(_, size) => Some(*size),
+        _ => None,
+    });
+    let operands: Vec<String> = operands
+        .iter()
+        .map(|op| match op {
+            Operand::Mem(m) if reg_size != Some(m.size) => {
+                format!("{} PTR {}", size_name(m.size), format_memory(m))
+            }
+            _ => format_operand(op),
+        })
+        .collect();
+    format!("{} {}", instr.mnemonic(), operands.join(", "))
+}
+
+pub fn format_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Reg(reg, size) => reg.name(*size).to_string(),
+        Operand::Imm(n) => n.to_string(),
+        Operand::Mem(m) => format_memory(m),
+        Operand::Label(l) => l.clone(),
+    }
+}
+
+fn format_memory(m: &Memory) -> String {
+    let mut s = match (&m.base, &m.symbol) {
+        (Some(base), _) => base.to_string(),
+        (None, Some(symbol)) => format!("rip+{symbol}"),
+        (None, None) => String::new(),
+    };
+    if let Some((index, scale)) = &m.index {
+        if !s.is_empty() {
+            s += "+";
+        }
+        s += &format!("{index}*{scale}");
+    }
+    if m.disp > 0 && !s.is_empty() {
+        s += &format!("+{}", m.disp);
+    } else if m.disp != 0 || s.is_empty() {
+        s += &m.disp.to_string();
+    }
+    format!("[{s}]")
+}
+
+fn size_name(size: Size) -> &'static str {
+    match size {
+        Size::Byte => "BYTE",
+        Size::Word => "WORD",
+        Size::Dword => "DWORD",
+        Size::Qword => "QWORD",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::x86::{
+        instr::{Cond, Instr},
+        operand::{Memory, Operand},
+        reg::Reg,
+    };
+
+    use super::format_instr;
+
+    #[rstest(
+        input,
+        expect,
+        case(Instr::Push(Operand::imm(42)), "push 42"),
+        case(Instr::Pop(Operand::reg(Reg::Rdi)), "pop rdi"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rax, 0)), "mov rax, [rax]"),
+        case(Instr::Mov(Operand::mem(Reg::Rax, 0), Operand::reg(Reg::Rdi)), "mov [rax], rdi"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::mem(Reg::Rbp, -8)), "mov rax, [rbp-8]"),
+        case(Instr::Mov(Operand::mem(Reg::Rbp, -8), Operand::imm(1)), "mov QWORD PTR [rbp-8], 1"),
+        case(Instr::Lea(Operand::reg(Reg::Rax), Operand::Mem(Memory::base(Reg::Rdi, 16).with_index(Reg::Rsi, 8))), "lea rax, [rdi+rsi*8+16]"),
+        case(Instr::Mov(Operand::reg(Reg::Rax), Operand::Mem(Memory::symbol("x"))), "mov rax, [rip+x]"),
+        case(Instr::Set(Cond::Le, Operand::reg8(Reg::Rax)), "setle al"),
+        case(Instr::Movzx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), "movzx rax, al"),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg8(Reg::Rax)), "movsx rax, al"),
+        case(Instr::Movsx(Operand::reg(Reg::Rax), Operand::reg32(Reg::Rax)), "movsxd rax, eax"),
+        case(Instr::Div(Operand::reg(Reg::Rdi)), "div rdi"),
+        case(Instr::Jcc(Cond::E, ".Lend0".to_string()), "je .Lend0"),
+        case(Instr::Call("foo".to_string()), "call foo"),
+        case(Instr::Cqo, "cqo"),
+    )]
+    fn test_format_instr(input: Instr, expect: &str) {
+        assert_eq!(expect, format_instr(&input));
+    }
+}
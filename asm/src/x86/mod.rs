@@ -1 +1,45 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use module::AsmModule;
+
+pub mod att;
+pub mod directive;
+pub mod encoder;
+pub mod instr;
 pub mod intel;
+pub mod module;
+pub mod operand;
+pub mod peephole;
+pub mod reg;
+
+/// Assembly syntax used to print an `AsmModule`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Syntax {
+    #[default]
+    Intel,
+    Att,
+}
+
+impl Syntax {
+    pub fn write_module<W: Write>(&self, module: &AsmModule, w: &mut W) -> io::Result<()> {
+        match self {
+            Syntax::Intel => intel::write_module(module, w),
+            Syntax::Att => att::write_module(module, w),
+        }
+    }
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "intel" => Ok(Syntax::Intel),
+            "att" => Ok(Syntax::Att),
+            _ => Err(format!("unknown assembler syntax: {s}")),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::x86::{directive::Directive, instr::Instr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Directive(Directive),
+    Label(String),
+    Instr(Instr),
+}
+
+/// A whole assembly file as a sequence of directives, labels and instructions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AsmModule {
+    pub items: Vec<Item>,
+}
+
+impl AsmModule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn directive(&mut self, directive: Directive) {
+        self.items.push(Item::Directive(directive));
+    }
+
+    pub fn label(&mut self, label: &str) {
+        self.items.push(Item::Label(label.to_string()));
+    }
+
+    pub fn instr(&mut self, instr: Instr) {
+        self.items.push(Item::Instr(instr));
+    }
+
+    pub fn instrs(&self) -> impl Iterator<Item = &Instr> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Instr(instr) => Some(instr),
+            _ => None,
+        })
+    }
+}
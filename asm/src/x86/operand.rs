@@ -0,0 +1,94 @@
+use crate::x86::reg::{Reg, Size};
+
+/// Memory reference of the form `[base + index * scale + disp]`.
+///
+/// When `symbol` is set and `base` is empty, the address is relative to `rip`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Memory {
+    pub size: Size,
+    pub base: Option<Reg>,
+    pub index: Option<(Reg, u8)>,
+    pub disp: i32,
+    pub symbol: Option<String>,
+}
+
+impl Memory {
+    pub fn base(base: Reg, disp: i32) -> Self {
+        Self {
+            size: Size::Qword,
+            base: Some(base),
+            index: None,
+            disp,
+            symbol: None,
+        }
+    }
+
+    pub fn symbol(symbol: &str) -> Self {
+        Self {
+            size: Size::Qword,
+            base: None,
+            index: None,
+            disp: 0,
+            symbol: Some(symbol.to_string()),
+        }
+    }
+
+    pub fn with_index(mut self, index: Reg, scale: u8) -> Self {
+        self.index = Some((index, scale));
+        self
+    }
+
+    pub fn with_size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Reg(Reg, Size),
+    Imm(i64),
+    Mem(Memory),
+    Label(String),
+}
+
+impl Operand {
+    /// 64-bit register operand.
+    pub fn reg(reg: Reg) -> Self {
+        Operand::Reg(reg, Size::Qword)
+    }
+
+    /// Lower 32 bits of a register, writing them clears the upper half.
+    pub fn reg32(reg: Reg) -> Self {
+        Operand::Reg(reg, Size::Dword)
+    }
+
+    /// Lowest byte of a register.
+    pub fn reg8(reg: Reg) -> Self {
+        Operand::Reg(reg, Size::Byte)
+    }
+
+    pub fn imm(n: i64) -> Self {
+        Operand::Imm(n)
+    }
+
+    pub fn mem(base: Reg, disp: i32) -> Self {
+        Operand::Mem(Memory::base(base, disp))
+    }
+
+    pub fn label(label: &str) -> Self {
+        Operand::Label(label.to_string())
+    }
+
+    pub fn size(&self) -> Option<Size> {
+        match self {
+            Operand::Reg(_, size) => Some(*size),
+            Operand::Mem(m) => Some(m.size),
+            _ => None,
+        }
+    }
+
+    pub fn is_reg(&self) -> bool {
+        matches!(self, Operand::Reg(_, _))
+    }
+}
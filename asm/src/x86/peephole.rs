@@ -0,0 +1,527 @@
+use crate::x86::{
+    instr::Instr,
+    module::{AsmModule, Item},
+    operand::Operand,
+    reg::{Reg, Size},
+};
+
+/// Rewrite short instruction sequences into cheaper equivalent ones.
+///
+/// Patterns never span labels, directives or branches, so every rewrite only
+/// sees straight line code. Rewriting is repeated until nothing changes.
+pub fn optimize(module: &mut AsmModule) {
+    let items = &mut module.items;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut i = 0;
+        while i < items.len() {
+            if dead_move(items, i)
+                || forward_push(items, i)
+                || address_with_lea(items, i)
+                || fold_address(items, i)
+                || forward_move(items, i)
+            {
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+// something an instruction reads or writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Loc {
+    Reg(Reg),
+    Flags,
+    Memory,
+}
+
+#[derive(Debug, Default)]
+struct Effects {
+    reads: Vec<Loc>,
+    writes: Vec<Loc>,
+}
+
+impl Effects {
+    fn touches(&self, loc: Loc) -> bool {
+        self.reads.contains(&loc) || self.writes.contains(&loc)
+    }
+}
+
+// registers used to form the address of a memory operand
+fn address(op: &Operand) -> Vec<Loc> {
+    match op {
+        Operand::Mem(m) => m
+            .base
+            .into_iter()
+            .chain(m.index.map(|(r, _)| r))
+            .map(Loc::Reg)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// everything read to get the value of an operand
+fn value(op: &Operand) -> Vec<Loc> {
+    match op {
+        Operand::Reg(r, _) => vec![Loc::Reg(*r)],
+        Operand::Mem(_) => [address(op), vec![Loc::Memory]].concat(),
+        _ => vec![],
+    }
+}
+
+// everything read and written by storing into an operand, writes to part of a
+// register keep the rest of it
+fn store(op: &Operand) -> (Vec<Loc>, Vec<Loc>) {
+    match op {
+        Operand::Reg(r, Size::Qword | Size::Dword) => (vec![], vec![Loc::Reg(*r)]),
+        Operand::Reg(r, _) => (vec![Loc::Reg(*r)], vec![Loc::Reg(*r)]),
+        Operand::Mem(_) => (address(op), vec![Loc::Memory]),
+        _ => (vec![], vec![]),
+    }
+}
+
+// effects of an instruction, `None` for the ones transferring control
+fn effects(instr: &Instr) -> Option<Effects> {
+    let mut e = Effects::default();
+    match instr {
+        Instr::Mov(dst, src) | Instr::Movzx(dst, src) | Instr::Movsx(dst, src) => {
+            let (reads, writes) = store(dst);
+            e.reads = [value(src), reads].concat();
+            e.writes = writes;
+        }
+        Instr::Lea(dst, src) => {
+            let (reads, writes) = store(dst);
+            e.reads = [address(src), reads].concat();
+            e.writes = writes;
+        }
+        Instr::Add(dst, src) | Instr::Sub(dst, src) | Instr::Imul(dst, src) => {
+            e.reads = [value(dst), value(src)].concat();
+            e.writes = [store(dst).1, vec![Loc::Flags]].concat();
+        }
+        Instr::Cmp(lhs, rhs) => {
+            e.reads = [value(lhs), value(rhs)].concat();
+            e.writes = vec![Loc::Flags];
+        }
+        Instr::Push(op) => {
+            e.reads = [value(op), vec![Loc::Reg(Reg::Rsp)]].concat();
+            e.writes = vec![Loc::Reg(Reg::Rsp), Loc::Memory];
+        }
+        Instr::Pop(op) => {
+            let (reads, writes) = store(op);
+            e.reads = [reads, vec![Loc::Reg(Reg::Rsp), Loc::Memory]].concat();
+            e.writes = [writes, vec![Loc::Reg(Reg::Rsp)]].concat();
+        }
+        Instr::Cqo => {
+            e.reads = vec![Loc::Reg(Reg::Rax)];
+            e.writes = vec![Loc::Reg(Reg::Rdx)];
+        }
+        Instr::Idiv(op) | Instr::Div(op) => {
+            e.reads = [value(op), vec![Loc::Reg(Reg::Rax), Loc::Reg(Reg::Rdx)]].concat();
+            e.writes = vec![Loc::Reg(Reg::Rax), Loc::Reg(Reg::Rdx), Loc::Flags];
+        }
+        Instr::Set(_, op) => {
+            let (reads, writes) = store(op);
+            e.reads = [reads, vec![Loc::Flags]].concat();
+            e.writes = writes;
+        }
+        Instr::Jmp(_) | Instr::Jcc(_, _) | Instr::Call(_) | Instr::Ret => return None,
+    }
+    Some(e)
+}
+
+// what is still needed after returning to the caller
+fn live_at_return(loc: Loc) -> bool {
+    match loc {
+        Loc::Reg(r) => matches!(
+            r,
+            Reg::Rax | Reg::Rsp | Reg::Rbp | Reg::Rbx | Reg::R12 | Reg::R13 | Reg::R14 | Reg::R15
+        ),
+        Loc::Flags => false,
+        Loc::Memory => true,
+    }
+}
+
+// whether the value in `loc` after items[i] is never read, anything leaving
+// straight line code other than a return counts as a read
+fn is_dead_after(items: &[Item], i: usize, loc: Loc) -> bool {
+    for item in items[i + 1..].iter() {
+        let Item::Instr(instr) = item else {
+            return false;
+        };
+        if *instr == Instr::Ret {
+            return !live_at_return(loc);
+        }
+        let Some(e) = effects(instr) else {
+            return false;
+        };
+        if e.reads.contains(&loc) {
+            return false;
+        }
+        if e.writes.contains(&loc) {
+            return true;
+        }
+    }
+    false
+}
+
+fn instr(items: &[Item], i: usize) -> Option<&Instr> {
+    match items.get(i) {
+        Some(Item::Instr(instr)) => Some(instr),
+        _ => None,
+    }
+}
+
+fn qword(op: &Operand) -> Option<Reg> {
+    match op {
+        Operand::Reg(r, Size::Qword) => Some(*r),
+        _ => None,
+    }
+}
+
+fn fits_i32(op: &Operand) -> bool {
+    match op {
+        Operand::Imm(n) => i32::try_from(*n).is_ok(),
+        _ => true,
+    }
+}
+
+// `mov rax, rax` or a register load whose value is never read
+fn dead_move(items: &mut Vec<Item>, i: usize) -> bool {
+    let dead = match instr(items, i) {
+        Some(
+            Instr::Mov(dst, src)
+            | Instr::Movzx(dst, src)
+            | Instr::Movsx(dst, src)
+            | Instr::Lea(dst, src),
+        ) => qword(dst).is_some_and(|r| dst == src || is_dead_after(items, i, Loc::Reg(r))),
+        _ => false,
+    };
+    if dead {
+        items.remove(i);
+    }
+    dead
+}
+
+// `push a; ...; pop b` into `...; mov b, a` when the instructions in between
+// leave the stack, `a` and `b` alone
+fn forward_push(items: &mut Vec<Item>, i: usize) -> bool {
+    let Some(Instr::Push(src)) = instr(items, i) else {
+        return false;
+    };
+    let src = src.clone();
+    if src == Operand::reg(Reg::Rsp) {
+        return false;
+    }
+    let mut between = vec![];
+    let mut j = i + 1;
+    let dst = loop {
+        let Some(next) = instr(items, j) else {
+            return false;
+        };
+        if let Instr::Pop(dst) = next {
+            break dst.clone();
+        }
+        match effects(next) {
+            Some(e) if !e.touches(Loc::Reg(Reg::Rsp)) => between.push(e),
+            _ => return false,
+        }
+        j += 1;
+    };
+    if dst == Operand::reg(Reg::Rsp) || matches!((&src, &dst), (Operand::Mem(_), Operand::Mem(_))) {
+        return false;
+    }
+    let (dst_reads, dst_writes) = store(&dst);
+    let dst_address = address(&dst);
+    let safe = between.iter().all(|e| {
+        value(&src).iter().all(|loc| !e.writes.contains(loc))
+            && dst_writes
+                .iter()
+                .chain(dst_reads.iter())
+                .all(|loc| !e.touches(*loc))
+            && dst_address.iter().all(|loc| !e.writes.contains(loc))
+    });
+    if !safe {
+        return false;
+    }
+    items[j] = Item::Instr(Instr::Mov(dst, src));
+    items.remove(i);
+    true
+}
+
+// `mov rax, rbp; sub rax, 8` into `lea rax, [rbp-8]` when the flags are unused
+fn address_with_lea(items: &mut Vec<Item>, i: usize) -> bool {
+    let (Some(Instr::Mov(dst, base)), Some(Instr::Sub(sub, Operand::Imm(n)))) =
+        (instr(items, i), instr(items, i + 1))
+    else {
+        return false;
+    };
+    let (Some(r), Some(base)) = (qword(dst), qword(base)) else {
+        return false;
+    };
+    let Ok(disp) = i32::try_from(-n) else {
+        return false;
+    };
+    if qword(sub) != Some(r) || r == Reg::Rsp || !is_dead_after(items, i + 1, Loc::Flags) {
+        return false;
+    }
+    items[i] = Item::Instr(Instr::Lea(Operand::reg(r), Operand::mem(base, disp)));
+    items.remove(i + 1);
+    true
+}
+
+// `lea rax, [rbp-8]; ...; mov rdi, [rax]` into `...; mov rdi, [rbp-8]` when
+// rax is not needed afterwards
+fn fold_address(items: &mut Vec<Item>, i: usize) -> bool {
+    let Some(Instr::Lea(dst, addr @ Operand::Mem(mem))) = instr(items, i) else {
+        return false;
+    };
+    let Some(r) = qword(dst) else {
+        return false;
+    };
+    let (addr, mem) = (address(addr), mem.clone());
+    if addr.contains(&Loc::Reg(r)) {
+        return false;
+    }
+    let mut j = i + 1;
+    let user = loop {
+        let Some(next) = instr(items, j) else {
+            return false;
+        };
+        let Some(e) = effects(next) else {
+            return false;
+        };
+        if e.touches(Loc::Reg(r)) {
+            break next;
+        }
+        if addr.iter().any(|loc| e.writes.contains(loc)) {
+            return false;
+        }
+        j += 1;
+    };
+    let replace = |op: &Operand| match op {
+        Operand::Mem(m) if m.base == Some(r) && m.index.is_none() && m.disp == 0 => {
+            let mut mem = mem.clone();
+            mem.size = m.size;
+            Operand::Mem(mem)
+        }
+        op => op.clone(),
+    };
+    let rewritten = match user {
+        Instr::Mov(d, s) => Instr::Mov(replace(d), replace(s)),
+        Instr::Add(d, s) => Instr::Add(replace(d), replace(s)),
+        Instr::Sub(d, s) => Instr::Sub(replace(d), replace(s)),
+        Instr::Imul(d, s) => Instr::Imul(d.clone(), replace(s)),
+        Instr::Cmp(d, s) => Instr::Cmp(replace(d), replace(s)),
+        Instr::Push(op) => Instr::Push(replace(op)),
+        _ => return false,
+    };
+    let Some(e) = effects(&rewritten) else {
+        return false;
+    };
+    if e.reads.contains(&Loc::Reg(r))
+        || !(e.writes.contains(&Loc::Reg(r)) || is_dead_after(items, j, Loc::Reg(r)))
+    {
+        return false;
+    }
+    items[j] = Item::Instr(rewritten);
+    items.remove(i);
+    true
+}
+
+// `mov rdi, 5; ...; add rax, rdi` into `...; add rax, 5` when rdi is not
+// needed afterwards and nothing in between changes the source
+fn forward_move(items: &mut Vec<Item>, i: usize) -> bool {
+    let Some(Instr::Mov(tmp, src)) = instr(items, i) else {
+        return false;
+    };
+    let Some(r) = qword(tmp) else {
+        return false;
+    };
+    let sources = value(src);
+    let mut j = i + 1;
+    let next = loop {
+        let Some(next) = instr(items, j) else {
+            return false;
+        };
+        let Some(e) = effects(next) else {
+            return false;
+        };
+        if e.touches(Loc::Reg(r)) {
+            break next;
+        }
+        if sources.iter().any(|loc| e.writes.contains(loc)) {
+            return false;
+        }
+        j += 1;
+    };
+    if src.size().is_some_and(|size| size != Size::Qword) {
+        return false;
+    }
+    let (user, rhs) = match next {
+        Instr::Mov(d, s) | Instr::Add(d, s) | Instr::Sub(d, s) | Instr::Cmp(d, s) => (d, s),
+        Instr::Imul(d, s) if d.is_reg() => (d, s),
+        _ => return false,
+    };
+    if *rhs != *tmp || user.size() != Some(Size::Qword) || value(user).contains(&Loc::Reg(r)) {
+        return false;
+    }
+    let legal = match src {
+        Operand::Mem(_) => user.is_reg(),
+        Operand::Imm(_) => fits_i32(src) || matches!(next, Instr::Mov(_, _)) && user.is_reg(),
+        _ => true,
+    };
+    if !legal || !is_dead_after(items, j, Loc::Reg(r)) {
+        return false;
+    }
+    let src = src.clone();
+    let user = user.clone();
+    items[j] = Item::Instr(match next {
+        Instr::Mov(_, _) => Instr::Mov(user, src),
+        Instr::Add(_, _) => Instr::Add(user, src),
+        Instr::Sub(_, _) => Instr::Sub(user, src),
+        Instr::Cmp(_, _) => Instr::Cmp(user, src),
+        Instr::Imul(_, _) => Instr::Imul(user, src),
+        _ => unreachable!(),
+    });
+    items.remove(i);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::x86::{
+        instr::{Cond, Instr},
+        intel,
+        module::AsmModule,
+        operand::Operand,
+        reg::Reg,
+    };
+
+    use super::optimize;
+
+    fn reg(r: Reg) -> Operand {
+        Operand::reg(r)
+    }
+
+    fn optimized(instrs: Vec<Instr>) -> Vec<String> {
+        let mut module = AsmModule::new();
+        module.label("main");
+        for instr in instrs {
+            module.instr(instr);
+        }
+        optimize(&mut module);
+        module.instrs().map(intel::format_instr).collect()
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case(
+            vec![
+                Instr::Push(reg(Reg::Rax)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Ret,
+            ],
+            vec!["ret"]
+        ),
+        case(
+            vec![
+                Instr::Push(Operand::imm(5)),
+                Instr::Pop(reg(Reg::Rdi)),
+                Instr::Call("foo".to_string()),
+                Instr::Ret,
+            ],
+            vec!["mov rdi, 5", "call foo", "ret"]
+        ),
+        case(
+            vec![
+                Instr::Mov(reg(Reg::Rax), reg(Reg::Rbp)),
+                Instr::Sub(reg(Reg::Rax), Operand::imm(8)),
+                Instr::Push(reg(Reg::Rax)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Mov(reg(Reg::Rax), Operand::mem(Reg::Rax, 0)),
+                Instr::Ret,
+            ],
+            vec!["mov rax, [rbp-8]", "ret"]
+        ),
+        // a = 3
+        case(
+            vec![
+                Instr::Mov(reg(Reg::Rax), reg(Reg::Rbp)),
+                Instr::Sub(reg(Reg::Rax), Operand::imm(8)),
+                Instr::Push(reg(Reg::Rax)),
+                Instr::Push(Operand::imm(3)),
+                Instr::Pop(reg(Reg::Rdi)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Mov(Operand::mem(Reg::Rax, 0), reg(Reg::Rdi)),
+                Instr::Push(reg(Reg::Rdi)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Ret,
+            ],
+            vec!["mov rdi, 3", "mov [rbp-8], rdi", "mov rax, rdi", "ret"]
+        ),
+        case(
+            vec![
+                Instr::Push(Operand::mem(Reg::Rbp, -8)),
+                Instr::Push(Operand::imm(5)),
+                Instr::Pop(reg(Reg::Rdi)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Add(reg(Reg::Rax), reg(Reg::Rdi)),
+                Instr::Ret,
+            ],
+            vec!["mov rax, [rbp-8]", "add rax, 5", "ret"]
+        ),
+        // the flags of sub are read by the jump
+        case(
+            vec![
+                Instr::Mov(reg(Reg::Rax), reg(Reg::Rbp)),
+                Instr::Sub(reg(Reg::Rax), Operand::imm(8)),
+                Instr::Jcc(Cond::E, ".L0".to_string()),
+                Instr::Mov(reg(Reg::Rax), Operand::mem(Reg::Rax, 0)),
+                Instr::Ret,
+            ],
+            vec!["mov rax, rbp", "sub rax, 8", "je .L0", "mov rax, [rax]", "ret"]
+        ),
+        // rdi is written between the push and the pop and read by the call
+        case(
+            vec![
+                Instr::Push(reg(Reg::Rdi)),
+                Instr::Mov(reg(Reg::Rdi), Operand::imm(1)),
+                Instr::Pop(reg(Reg::Rax)),
+                Instr::Add(reg(Reg::Rax), reg(Reg::Rdi)),
+                Instr::Call("foo".to_string()),
+                Instr::Ret,
+            ],
+            vec!["push rdi", "mov rdi, 1", "pop rax", "add rax, rdi", "call foo", "ret"]
+        ),
+        // immediates wider than 32 bits only fit mov
+        case(
+            vec![
+                Instr::Mov(reg(Reg::Rdi), Operand::imm(1 << 40)),
+                Instr::Add(reg(Reg::Rax), reg(Reg::Rdi)),
+                Instr::Ret,
+            ],
+            vec!["mov rdi, 1099511627776", "add rax, rdi", "ret"]
+        ),
+    )]
+    fn test_optimize(input: Vec<Instr>, expect: Vec<&str>) {
+        assert_eq!(expect, optimized(input));
+    }
+
+    #[test]
+    fn test_optimize_stops_at_labels() {
+        let mut module = AsmModule::new();
+        module.instr(Instr::Push(reg(Reg::Rax)));
+        module.label(".L0");
+        module.instr(Instr::Pop(reg(Reg::Rax)));
+        module.instr(Instr::Ret);
+        let expect = module.clone();
+        optimize(&mut module);
+        assert_eq!(expect, module);
+    }
+}
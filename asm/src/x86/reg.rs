@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+/// General purpose registers of x86-64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reg {
+    Rax,
+    Rcx,
+    Rdx,
+    Rbx,
+    Rsp,
+    Rbp,
+    Rsi,
+    Rdi,
+    R8,
+    R9,
+    R10,
+    R11,
+    R12,
+    R13,
+    R14,
+    R15,
+}
+
+/// Width of an operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+}
+
+impl Reg {
+    /// Number of the register used by the instruction encoding.
+    pub fn number(&self) -> u8 {
+        match self {
+            Reg::Rax => 0,
+            Reg::Rcx => 1,
+            Reg::Rdx => 2,
+            Reg::Rbx => 3,
+            Reg::Rsp => 4,
+            Reg::Rbp => 5,
+            Reg::Rsi => 6,
+            Reg::Rdi => 7,
+            Reg::R8 => 8,
+            Reg::R9 => 9,
+            Reg::R10 => 10,
+            Reg::R11 => 11,
+            Reg::R12 => 12,
+            Reg::R13 => 13,
+            Reg::R14 => 14,
+            Reg::R15 => 15,
+        }
+    }
+
+    pub fn name(&self, size: Size) -> &'static str {
+        const NAMES: [[&str; 4]; 16] = [
+            ["al", "ax", "eax", "rax"],
+            ["cl", "cx", "ecx", "rcx"],
+            ["dl", "dx", "edx", "rdx"],
+            ["bl", "bx", "ebx", "rbx"],
+            ["spl", "sp", "esp", "rsp"],
+            ["bpl", "bp", "ebp", "rbp"],
+            ["sil", "si", "esi", "rsi"],
+            ["dil", "di", "edi", "rdi"],
+            ["r8b", "r8w", "r8d", "r8"],
+            ["r9b", "r9w", "r9d", "r9"],
+            ["r10b", "r10w", "r10d", "r10"],
+            ["r11b", "r11w", "r11d", "r11"],
+            ["r12b", "r12w", "r12d", "r12"],
+            ["r13b", "r13w", "r13d", "r13"],
+            ["r14b", "r14w", "r14d", "r14"],
+            ["r15b", "r15w", "r15d", "r15"],
+        ];
+        let width = match size {
+            Size::Byte => 0,
+            Size::Word => 1,
+            Size::Dword => 2,
+            Size::Qword => 3,
+        };
+        NAMES[self.number() as usize][width]
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name(Size::Qword))
+    }
+}
+
+impl Size {
+    pub fn bytes(&self) -> u32 {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Dword => 4,
+            Size::Qword => 8,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("too many arguments for {0}")]
+    TooManyArguments(String),
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use ir::{
+    cfg::Dominators,
+    function::{Block, Function},
+    instr::{BlockId, CmpOp, Inst, Operand, Slot, Terminator, Value},
+    regalloc::{self, Allocation},
+};
+
+/// Where the values of a function live while generating its code.
+///
+/// The offsets are for targets that address the frame from the stack pointer,
+/// which stays put between the prologue and the epilogue. Slots come first,
+/// then spill slots and then the saved registers, 8 bytes each.
+#[derive(Debug)]
+pub struct Frame<R> {
+    pub name: String,
+    pub alloc: Allocation<R>,
+    pub slots: u32,
+    /// comparisons only feeding the branch right after them
+    pub fused: HashMap<Value, (CmpOp, Operand, Operand)>,
+}
+
+impl<R: Copy + Eq> Frame<R> {
+    /// Allocate the values to the registers, or keep them all on the stack
+    /// without `regalloc`.
+    pub fn new(func: &Function, caller_saved: &[R], callee_saved: &[R], regalloc: bool) -> Self {
+        let alloc = if regalloc {
+            regalloc::linear_scan(func, caller_saved, callee_saved)
+        } else {
+            regalloc::linear_scan(func, &[], &[])
+        };
+        Self {
+            name: func.name.clone(),
+            alloc,
+            slots: func.slots.len() as u32,
+            fused: fused_compares(func),
+        }
+    }
+
+    pub fn slot(&self, slot: Slot) -> i64 {
+        8 * slot.0 as i64
+    }
+
+    pub fn spill(&self, i: u32) -> i64 {
+        8 * (self.slots + i) as i64
+    }
+
+    pub fn saved(&self, i: usize) -> i64 {
+        8 * (self.slots + self.alloc.spill_slots) as i64 + 8 * i as i64
+    }
+
+    /// Bytes below the frame record, keeping the stack 16 byte aligned.
+    pub fn size(&self) -> i64 {
+        let size = self.saved(self.alloc.callee_saved.len());
+        size + (16 - size % 16) % 16
+    }
+
+    pub fn label(&self, block: BlockId) -> String {
+        format!(".L{}.{}", self.name, block.0)
+    }
+}
+
+/// Blocks that can be reached from the entry, in order.
+pub fn reachable_blocks(func: &Function) -> Vec<&Block> {
+    let doms = Dominators::new(func);
+    func.blocks
+        .iter()
+        .filter(|b| doms.is_reachable(b.id))
+        .collect()
+}
+
+// comparisons that are the last instruction of their block and only used by
+// its branch are emitted together with the jump
+fn fused_compares(func: &Function) -> HashMap<Value, (CmpOp, Operand, Operand)> {
+    let mut uses: HashMap<Value, usize> = HashMap::new();
+    for block in func.blocks.iter() {
+        let insts = block.insts.iter().flat_map(|i| i.uses());
+        for v in insts.chain(block.term.uses()) {
+            *uses.entry(v).or_default() += 1;
+        }
+    }
+    let mut fused = HashMap::new();
+    for block in func.blocks.iter() {
+        if let (
+            Some(Inst::Cmp { dst, op, lhs, rhs }),
+            Terminator::Branch {
+                cond: Operand::Value(cond),
+                ..
+            },
+        ) = (block.insts.last(), &block.term)
+        {
+            if dst == cond && uses.get(dst) == Some(&1) {
+                fused.insert(*dst, (*op, *lhs, *rhs));
+            }
+        }
+    }
+    fused
+}
@@ -0,0 +1,432 @@
+use ir::{
+    function::{Function, Module},
+    instr::{BinOp, BlockId, CmpOp, Inst, Operand, Slot, Terminator, Value},
+};
+
+pub use error::Error;
+pub use frame::{reachable_blocks, Frame};
+pub use load_store::LoadStore;
+
+mod error;
+mod frame;
+mod load_store;
+
+/// Code generation for one target, driven over the IR by `generate_module`.
+///
+/// Instructions read their operands from and write their results to where the
+/// frame says the values live, arguments are passed in
+/// `argument_registers` and the result is returned in the first of them.
+pub trait Backend {
+    type Reg: Copy + Eq + 'static;
+    /// What `finish` hands back, assembly text or a structured module.
+    type Output;
+
+    fn argument_registers(&self) -> &'static [Self::Reg];
+
+    /// Registers a call may change, values live across calls never get them.
+    fn caller_saved(&self) -> &'static [Self::Reg];
+
+    /// Registers a function has to preserve when it uses them.
+    fn callee_saved(&self) -> &'static [Self::Reg];
+
+    /// Define the function symbol, set up the frame, save the callee saved
+    /// registers and move the parameters out of the argument registers.
+    fn prologue(&mut self, frame: &Frame<Self::Reg>, params: &[Value]);
+
+    /// Restore the callee saved registers and the frame of the caller.
+    fn epilogue(&mut self, frame: &Frame<Self::Reg>);
+
+    fn label(&mut self, label: &str);
+
+    fn load(&mut self, frame: &Frame<Self::Reg>, dst: Value, slot: Slot);
+
+    fn store(&mut self, frame: &Frame<Self::Reg>, slot: Slot, src: &Operand);
+
+    fn copy(&mut self, frame: &Frame<Self::Reg>, dst: Value, src: &Operand);
+
+    /// Sign or zero extend the low `bits` of src to 64 bits.
+    fn extend(
+        &mut self,
+        frame: &Frame<Self::Reg>,
+        dst: Value,
+        src: &Operand,
+        bits: u32,
+        signed: bool,
+    );
+
+    fn binary(
+        &mut self,
+        frame: &Frame<Self::Reg>,
+        op: BinOp,
+        dst: Value,
+        lhs: &Operand,
+        rhs: &Operand,
+    );
+
+    /// Set dst to 1 when the comparison holds and to 0 otherwise.
+    fn compare(
+        &mut self,
+        frame: &Frame<Self::Reg>,
+        op: CmpOp,
+        dst: Value,
+        lhs: &Operand,
+        rhs: &Operand,
+    );
+
+    /// Jump to the label when the comparison holds.
+    fn compare_and_branch(
+        &mut self,
+        frame: &Frame<Self::Reg>,
+        op: CmpOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        target: &str,
+    );
+
+    fn jump(&mut self, target: &str);
+
+    /// Call with at most `argument_registers` arguments.
+    fn call(&mut self, frame: &Frame<Self::Reg>, dst: Value, func: &str, args: &[Operand]);
+
+    /// Return the value, running the epilogue.
+    fn ret(&mut self, frame: &Frame<Self::Reg>, value: &Operand);
+
+    /// Run the epilogue and jump to the function, which returns to our caller.
+    fn tail_call(&mut self, frame: &Frame<Self::Reg>, func: &str, args: &[Operand]);
+
+    /// Define a global initialized with 64 bit values.
+    fn data(&mut self, name: &str, values: &[i64]);
+
+    /// Take the code generated so far.
+    fn finish(&mut self) -> Self::Output;
+}
+
+/// Generate the functions of the module, keeping values in registers with
+/// `regalloc` and on the stack otherwise.
+pub fn generate_module<B: Backend>(
+    backend: &mut B,
+    module: &Module,
+    regalloc: bool,
+) -> Result<(), Error> {
+    for func in module.functions.iter() {
+        generate_function(backend, func, regalloc)?;
+    }
+    Ok(())
+}
+
+fn generate_function<B: Backend>(
+    backend: &mut B,
+    func: &Function,
+    regalloc: bool,
+) -> Result<(), Error> {
+    let frame = Frame::new(
+        func,
+        backend.caller_saved(),
+        backend.callee_saved(),
+        regalloc,
+    );
+    backend.prologue(&frame, &func.params);
+
+    let blocks = reachable_blocks(func);
+    for (i, block) in blocks.iter().enumerate() {
+        backend.label(&frame.label(block.id));
+        for inst in block.insts.iter() {
+            generate_inst(backend, &frame, inst)?;
+        }
+        let next = blocks.get(i + 1).map(|b| b.id);
+        generate_term(backend, &frame, &block.term, next)?;
+    }
+    Ok(())
+}
+
+fn generate_inst<B: Backend>(
+    backend: &mut B,
+    frame: &Frame<B::Reg>,
+    inst: &Inst,
+) -> Result<(), Error> {
+    match inst {
+        Inst::Binary { dst, op, lhs, rhs } => backend.binary(frame, *op, *dst, lhs, rhs),
+        Inst::Cmp { dst, op, lhs, rhs } => {
+            // fused comparisons are generated with their branch
+            if !frame.fused.contains_key(dst) {
+                backend.compare(frame, *op, *dst, lhs, rhs);
+            }
+        }
+        Inst::Extend {
+            dst,
+            src,
+            bits,
+            signed,
+        } => backend.extend(frame, *dst, src, *bits, *signed),
+        Inst::Zext { dst, src } | Inst::Copy { dst, src } => backend.copy(frame, *dst, src),
+        Inst::Load { dst, slot } => backend.load(frame, *dst, *slot),
+        Inst::Store { slot, src } => backend.store(frame, *slot, src),
+        Inst::Call { dst, func, args } => {
+            check_arguments(backend, func, args)?;
+            backend.call(frame, *dst, func, args);
+        }
+    }
+    Ok(())
+}
+
+fn generate_term<B: Backend>(
+    backend: &mut B,
+    frame: &Frame<B::Reg>,
+    term: &Terminator,
+    next: Option<BlockId>,
+) -> Result<(), Error> {
+    match term {
+        Terminator::Jump(target) => {
+            if Some(*target) != next {
+                backend.jump(&frame.label(*target));
+            }
+        }
+        Terminator::Branch {
+            cond,
+            then,
+            otherwise,
+        } => {
+            let fused = cond.value().and_then(|v| frame.fused.get(&v));
+            let (op, lhs, rhs) = match fused {
+                Some((op, lhs, rhs)) => (*op, *lhs, *rhs),
+                None => (CmpOp::Ne, *cond, Operand::Const(0)),
+            };
+            // fall through to the block placed next
+            if Some(*then) == next {
+                backend.compare_and_branch(
+                    frame,
+                    op.negate(),
+                    &lhs,
+                    &rhs,
+                    &frame.label(*otherwise),
+                );
+            } else {
+                backend.compare_and_branch(frame, op, &lhs, &rhs, &frame.label(*then));
+                if Some(*otherwise) != next {
+                    backend.jump(&frame.label(*otherwise));
+                }
+            }
+        }
+        Terminator::Return(v) => backend.ret(frame, v),
+        Terminator::TailCall { func, args } => {
+            check_arguments(backend, func, args)?;
+            backend.tail_call(frame, func, args);
+        }
+    }
+    Ok(())
+}
+
+fn check_arguments<B: Backend>(backend: &B, func: &str, args: &[Operand]) -> Result<(), Error> {
+    if args.len() > backend.argument_registers().len() {
+        return Err(Error::TooManyArguments(func.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ir::{
+        function::{Block, Function, Module},
+        instr::{BinOp, BlockId, CmpOp, Inst, Operand, Slot, Terminator, Type, Value},
+    };
+
+    use super::{generate_module, Backend, Error, Frame};
+
+    // records the calls made by the driver
+    #[derive(Debug, Default)]
+    struct Recorder {
+        out: Vec<String>,
+    }
+
+    impl Backend for Recorder {
+        type Reg = u8;
+        type Output = Vec<String>;
+
+        fn argument_registers(&self) -> &'static [u8] {
+            &[0, 1]
+        }
+
+        fn caller_saved(&self) -> &'static [u8] {
+            &[2]
+        }
+
+        fn callee_saved(&self) -> &'static [u8] {
+            &[3]
+        }
+
+        fn prologue(&mut self, frame: &Frame<u8>, _: &[Value]) {
+            self.out.push(format!("prologue {}", frame.name));
+        }
+
+        fn epilogue(&mut self, _: &Frame<u8>) {
+            self.out.push("epilogue".to_string());
+        }
+
+        fn label(&mut self, label: &str) {
+            self.out.push(format!("{label}:"));
+        }
+
+        fn load(&mut self, _: &Frame<u8>, dst: Value, slot: Slot) {
+            self.out.push(format!("{dst} = load {}", slot.0));
+        }
+
+        fn store(&mut self, _: &Frame<u8>, slot: Slot, src: &Operand) {
+            self.out.push(format!("store {}, {src}", slot.0));
+        }
+
+        fn copy(&mut self, _: &Frame<u8>, dst: Value, src: &Operand) {
+            self.out.push(format!("{dst} = {src}"));
+        }
+
+        fn extend(&mut self, _: &Frame<u8>, dst: Value, src: &Operand, bits: u32, _: bool) {
+            self.out.push(format!("{dst} = extend.{bits} {src}"));
+        }
+
+        fn binary(&mut self, _: &Frame<u8>, op: BinOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+            self.out.push(format!("{dst} = {op} {lhs}, {rhs}"));
+        }
+
+        fn compare(&mut self, _: &Frame<u8>, op: CmpOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+            self.out.push(format!("{dst} = {op} {lhs}, {rhs}"));
+        }
+
+        fn compare_and_branch(
+            &mut self,
+            _: &Frame<u8>,
+            op: CmpOp,
+            lhs: &Operand,
+            rhs: &Operand,
+            target: &str,
+        ) {
+            self.out.push(format!("b{op} {lhs}, {rhs}, {target}"));
+        }
+
+        fn jump(&mut self, target: &str) {
+            self.out.push(format!("jump {target}"));
+        }
+
+        fn call(&mut self, _: &Frame<u8>, dst: Value, func: &str, args: &[Operand]) {
+            self.out.push(format!("{dst} = call {func} {}", args.len()));
+        }
+
+        fn ret(&mut self, frame: &Frame<u8>, value: &Operand) {
+            self.epilogue(frame);
+            self.out.push(format!("ret {value}"));
+        }
+
+        fn tail_call(&mut self, frame: &Frame<u8>, func: &str, _: &[Operand]) {
+            self.epilogue(frame);
+            self.out.push(format!("tail {func}"));
+        }
+
+        fn data(&mut self, name: &str, values: &[i64]) {
+            self.out.push(format!("data {name} {values:?}"));
+        }
+
+        fn finish(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.out)
+        }
+    }
+
+    fn module(insts: Vec<Inst>, values: Vec<Type>, term: Terminator) -> Module {
+        let mut func = Function::new("main");
+        func.values = values;
+        func.blocks = vec![
+            Block {
+                id: BlockId(0),
+                insts,
+                term,
+            },
+            Block {
+                id: BlockId(1),
+                insts: vec![],
+                term: Terminator::Return(Operand::Const(1)),
+            },
+            Block {
+                id: BlockId(2),
+                insts: vec![],
+                term: Terminator::Return(Operand::Const(2)),
+            },
+        ];
+        Module {
+            functions: vec![func],
+        }
+    }
+
+    #[test]
+    fn test_generate_fused_branch() {
+        // the comparison is only used by the branch and falls through to then
+        let module = module(
+            vec![Inst::Cmp {
+                dst: Value(0),
+                op: CmpOp::Lt,
+                lhs: Operand::Const(1),
+                rhs: Operand::Const(2),
+            }],
+            vec![Type::I1],
+            Terminator::Branch {
+                cond: Operand::Value(Value(0)),
+                then: BlockId(1),
+                otherwise: BlockId(2),
+            },
+        );
+        let mut recorder = Recorder::default();
+        generate_module(&mut recorder, &module, true).unwrap();
+        assert_eq!(
+            vec![
+                "prologue main",
+                ".Lmain.0:",
+                "bge 1, 2, .Lmain.2",
+                ".Lmain.1:",
+                "epilogue",
+                "ret 1",
+                ".Lmain.2:",
+                "epilogue",
+                "ret 2",
+            ],
+            recorder.finish()
+        );
+    }
+
+    #[test]
+    fn test_generate_branch() {
+        // a value that is not a comparison is tested against zero
+        let module = module(
+            vec![Inst::Copy {
+                dst: Value(0),
+                src: Operand::Const(3),
+            }],
+            vec![Type::I64],
+            Terminator::Branch {
+                cond: Operand::Value(Value(0)),
+                then: BlockId(2),
+                otherwise: BlockId(1),
+            },
+        );
+        let mut recorder = Recorder::default();
+        generate_module(&mut recorder, &module, true).unwrap();
+        let out = recorder.finish();
+        assert_eq!(
+            vec![".Lmain.0:", "%0 = 3", "bne %0, 0, .Lmain.2"],
+            out[1..4]
+        );
+    }
+
+    #[test]
+    fn test_generate_too_many_arguments() {
+        let module = module(
+            vec![Inst::Call {
+                dst: Value(0),
+                func: "foo".to_string(),
+                args: vec![Operand::Const(1); 3],
+            }],
+            vec![Type::I64],
+            Terminator::Jump(BlockId(1)),
+        );
+        let mut recorder = Recorder::default();
+        assert!(matches!(
+            generate_module(&mut recorder, &module, true),
+            Err(Error::TooManyArguments(f)) if f == "foo"
+        ));
+    }
+}
@@ -0,0 +1,71 @@
+use ir::{
+    instr::{Operand, Value},
+    regalloc::Location,
+};
+
+use crate::{Backend, Frame};
+
+/// Moving operands between registers and where the frame keeps their values,
+/// for targets whose instructions only take registers.
+pub trait LoadStore: Backend {
+    /// Register a value is computed in before being stored to the stack.
+    const SCRATCH: Self::Reg;
+
+    /// The register holding the constant, loading it into `tmp` when it has none.
+    fn constant(&mut self, n: i64, tmp: Self::Reg) -> Self::Reg;
+
+    /// Load the register from the frame offset.
+    fn load_offset(&mut self, dst: Self::Reg, offset: i64);
+
+    /// Store the register to the frame offset.
+    fn store_offset(&mut self, offset: i64, src: Self::Reg);
+
+    fn move_reg(&mut self, dst: Self::Reg, src: Self::Reg);
+
+    /// The register holding the operand, loading it into `tmp` when it has none.
+    fn read(&mut self, frame: &Frame<Self::Reg>, op: &Operand, tmp: Self::Reg) -> Self::Reg {
+        match op {
+            Operand::Const(n) => self.constant(*n, tmp),
+            Operand::Value(v) => match frame.alloc.location(*v) {
+                Location::Reg(reg) => reg,
+                Location::Stack(i) => {
+                    self.load_offset(tmp, frame.spill(i));
+                    tmp
+                }
+            },
+        }
+    }
+
+    fn move_to(&mut self, frame: &Frame<Self::Reg>, dst: Self::Reg, op: &Operand) {
+        let src = self.read(frame, op, dst);
+        if src != dst {
+            self.move_reg(dst, src);
+        }
+    }
+
+    /// Store the register into the location of the value.
+    fn write(&mut self, frame: &Frame<Self::Reg>, value: Value, src: Self::Reg) {
+        match frame.alloc.location(value) {
+            Location::Reg(reg) if reg == src => {}
+            Location::Reg(reg) => self.move_reg(reg, src),
+            Location::Stack(i) => self.store_offset(frame.spill(i), src),
+        }
+    }
+
+    /// Move the arguments of a call into the argument registers.
+    fn arguments(&mut self, frame: &Frame<Self::Reg>, args: &[Operand]) {
+        // arguments never live in argument registers so they can be set up in order
+        for (arg, reg) in args.iter().zip(self.argument_registers().iter()) {
+            self.move_to(frame, *reg, arg);
+        }
+    }
+
+    /// The register the value is computed in before being written to its
+    /// location.
+    fn dst_reg(&self, frame: &Frame<Self::Reg>, value: Value) -> Self::Reg {
+        match frame.alloc.location(value) {
+            Location::Reg(reg) => reg,
+            Location::Stack(_) => Self::SCRATCH,
+        }
+    }
+}
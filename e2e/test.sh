@@ -4,7 +4,8 @@ assert() {
   expected="$1"
   input="$2"
 
-  e2e/teruc "$input" > tmp.s
+  echo "$input" > tmp.c
+  e2e/teruc -S -o tmp.s tmp.c
   cc -o tmp tmp.s
   ./tmp
   actual="$?"
@@ -21,11 +22,65 @@ assert() {
   fi
 }
 
+assert_att() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp.c
+  e2e/teruc -masm=att -S -o tmp.s tmp.c
+  cc -o tmp tmp.s
+  ./tmp
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (att)"
+  else
+    echo "$input => $expected expected, but got $actual (att)"
+    exit 1
+  fi
+}
+
+assert_obj() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp.c
+  e2e/teruc -c -o tmp.o tmp.c
+  cc -o tmp tmp.o
+  ./tmp
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (obj)"
+  else
+    echo "$input => $expected expected, but got $actual (obj)"
+    exit 1
+  fi
+}
+
+assert_obj_with_output() {
+  expected="$1"
+  link_target="$2"
+  input="$3"
+  echo "$input" > tmp.c
+  e2e/teruc -c -o tmp.o tmp.c
+  cc -c e2e/$link_target.c -o "$link_target".o
+  cc "$link_target".o tmp.o -o tmp
+  output=$(./tmp)
+  if [ "$output" == "$expected" ]; then
+    echo "$input => $output (obj)"
+  else
+    echo "$input => $expected" is expected, but got "$output (obj)"
+    exit 1
+  fi
+}
+
 assert_with_output() {
   expected="$1"
   link_target="$2"
   input="$3"
-  e2e/teruc "$input" > tmp.s
+  echo "$input" > tmp.c
+  e2e/teruc -S -o tmp.s tmp.c
   cc -c e2e/$link_target.c -o "$link_target".o
   cc "$link_target".o tmp.s -o tmp
   output=$(./tmp)
@@ -37,6 +92,211 @@ assert_with_output() {
   fi
 }
 
+assert_link() {
+  expected="$1"
+  input="$2"
+  shift 2
+
+  echo "$input" > tmp.c
+  e2e/teruc "$@" -o tmp tmp.c
+  ./tmp
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (link $*)"
+  else
+    echo "$input => $expected expected, but got $actual (link $*)"
+    exit 1
+  fi
+}
+
+assert_link_with_output() {
+  expected="$1"
+  link_target="$2"
+  input="$3"
+  shift 3
+  echo "$input" > tmp.c
+  cc -c e2e/$link_target.c -o "$link_target".o
+  e2e/teruc "$@" -o tmp tmp.c "$link_target".o
+  output=$(./tmp)
+  if [ "$output" == "$expected" ]; then
+    echo "$input => $output (link $*)"
+  else
+    echo "$input => $expected" is expected, but got "$output (link)"
+    exit 1
+  fi
+}
+
+assert_peephole() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp.c
+  for flags in "" "-fno-ir" "-fno-ir -fno-regalloc"; do
+    for peephole in -fpeephole -fno-peephole; do
+      e2e/teruc $flags $peephole -o tmp tmp.c
+      ./tmp
+      actual="$?"
+      if [ "$actual" != "$expected" ]; then
+        echo "$input => $expected expected, but got $actual (peephole $flags $peephole)"
+        exit 1
+      fi
+    done
+  done
+  echo "$input => $actual (peephole)"
+}
+
+# cross compile, running the program only when a cross toolchain and qemu are installed
+assert_target() {
+  expected="$1"
+  target="$2"
+  input="$3"
+  shift 3
+
+  echo "$input" > tmp.c
+  e2e/teruc --target "$target" "$@" -S -o tmp.s tmp.c || exit 1
+  if ! command -v "$target-gcc" > /dev/null || ! command -v "qemu-${target%%-*}" > /dev/null; then
+    echo "$input => compiled only ($target $*)"
+    return
+  fi
+  "$target-gcc" -static -o tmp tmp.s
+  "qemu-${target%%-*}" ./tmp
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual ($target $*)"
+  else
+    echo "$input => $expected expected, but got $actual ($target $*)"
+    exit 1
+  fi
+}
+
+# compile to a wasm module, running main with node when wat2wasm is installed
+assert_wasm() {
+  expected="$1"
+  input="$2"
+  shift 2
+
+  echo "$input" > tmp.c
+  e2e/teruc --target wasm32 "$@" -S -o tmp.wat tmp.c || exit 1
+  if ! command -v wat2wasm > /dev/null || ! command -v node > /dev/null; then
+    echo "$input => compiled only (wasm32 $*)"
+    return
+  fi
+  wat2wasm tmp.wat -o tmp.wasm || exit 1
+  actual=$(node -e '
+    const m = new WebAssembly.Module(require("fs").readFileSync("tmp.wasm"));
+    const { main } = new WebAssembly.Instance(m, { env: { ret3: () => 3n } }).exports;
+    console.log(Number(BigInt.asUintN(8, main())));
+  ')
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (wasm32 $*)"
+  else
+    echo "$input => $expected expected, but got $actual (wasm32 $*)"
+    exit 1
+  fi
+}
+
+assert_llvm() {
+  expected="$1"
+  input="$2"
+  shift 2
+
+  echo "$input" > tmp.c
+  e2e/teruc --emit-llvm "$@" -S -o tmp.ll tmp.c || exit 1
+  if ! command -v lli > /dev/null; then
+    echo "$input => compiled only (llvm $*)"
+    return
+  fi
+  # `ptr` needs opaque pointers to be enabled before LLVM 15
+  flags=""
+  if [ "$(lli --version | sed -n 's/.*version \([0-9]*\).*/\1/p')" -lt 15 ]; then
+    flags="-opaque-pointers"
+  fi
+  lli $flags tmp.ll
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (llvm $*)"
+  else
+    echo "$input => $expected expected, but got $actual (llvm $*)"
+    exit 1
+  fi
+}
+
+assert_run() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp.c
+  e2e/teruc run tmp.c
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (run)"
+  else
+    echo "$input => $expected expected, but got $actual (run)"
+    exit 1
+  fi
+}
+
+assert_run_output() {
+  expected="$1"
+  input="$2"
+
+  echo "$input" > tmp.c
+  output=$(e2e/teruc run tmp.c)
+  if [ "$output" == "$expected" ]; then
+    echo "$input => $output (run)"
+  else
+    echo "$input => $expected" is expected, but got "$output (run)"
+    exit 1
+  fi
+}
+
+assert_jit() {
+  expected="$1"
+  input="$2"
+  shift 2
+
+  echo "$input" > tmp.c
+  e2e/teruc --jit "$@" tmp.c
+  actual="$?"
+
+  if [ "$actual" = "$expected" ]; then
+    echo "$input => $actual (jit $*)"
+  else
+    echo "$input => $expected expected, but got $actual (jit $*)"
+    exit 1
+  fi
+}
+
+assert_dump_ast() {
+  format="$1"
+  expected="$2"
+  input="$3"
+
+  echo "$input" > tmp.c
+  output=$(e2e/teruc --dump-ast="$format" tmp.c)
+  if [ "$output" == "$expected" ]; then
+    echo "$input => $output (dump-ast $format)"
+  else
+    echo "$input => $expected" is expected, but got "$output (dump-ast $format)"
+    exit 1
+  fi
+}
+
+assert_fail() {
+  input="$1"
+  echo "$input" > tmp.c
+  if e2e/teruc -S -o tmp.s tmp.c 2> tmp.err; then
+    echo "$input => expected failure, but succeeded"
+    exit 1
+  fi
+  echo "$input => $(cat tmp.err)"
+}
+
 assert 0 '0;'
 assert 42 '42;'
 
@@ -57,6 +317,199 @@ assert 1 'a = 1; if (a == 0) return 0; else return 1;'
 assert 1 'a = 1; if (a == 0) return 0; else if (a == 1) return 1; else return 2;'
 assert 10 'a = 0; while (a != 10) a = a + 1; return a;'
 assert 10 'b = 0; for(a = 0; a < 10; a = a + 1) b = b + 1; return b;'
-assert_with_output "hello from foo" foo "foo();"
-assert_with_output "3" add "add(1, 2);"
+assert_att 47 '5+6*7;'
+assert_att 4 '(3+5)/2;'
+assert_att 1 'a = 1; if (a == 0) return 0; else if (a == 1) return 1; else return 2;'
+assert_att 10 'b = 0; for(a = 0; a < 10; a = a + 1) b = b + 1; return b;'
+assert_with_output "hello from foo" foo "int foo(); foo();"
+assert_with_output "3" add "int add(int a, int b); add(1, 2);"
+assert_obj 47 '5+6*7;'
+assert_obj 4 '(3+5)/2;'
+assert_obj 1 'a = 1; if (a == 0) return 0; else if (a == 1) return 1; else return 2;'
+assert_obj 10 'b = 0; for(a = 0; a < 10; a = a + 1) b = b + 1; return b;'
+assert_obj_with_output "3" add "int add(int a, int b); add(1, 2);"
+assert_link 47 '5+6*7;'
+assert_link 47 '5+6*7;' -fno-regalloc
+assert_link 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -fno-regalloc
+assert 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);'
+# deep enough to run out of scratch registers
+deep=a
+for i in 1 2 3 4 5 6 7; do deep="($deep+$deep)"; done
+assert 128 "a = 1; return $deep;"
+assert_with_output "3" add "int add(int a, int b); a = 2; b = a + add(1, 2) * a;"
+assert_link 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -fno-ir
+assert_link 128 "a = 1; return $deep;" -fno-ir
+assert_link 128 "a = 1; return $deep;" -fno-regalloc
+assert_link 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;'
+assert_link 1 'a = 5; return (a > 3) == (3 <= a);'
+assert_link 47 '5+6*7;' -fno-fold
+assert_link 1 'a = 0 - 9223372036854775807 - 1; return a < 0;' -fno-ir
+assert_link 255 'return 0 - 9223372036854775807 - 2;'
+assert_link 3 'a = 3; return a * 1 + 0 - (a - a);'
+assert_link 1 'a = 1; if (0) a = 2; return a; a = 3;' -Wunreachable-code
+assert_link 2 'a = 1; while (0) a = 5; if (a) return 2; else return 3; return 4;' -fno-ir
+assert_link 7 'a = 7; a + 1; a;' -fno-dce
+assert_link 3 'return VALUE;' -DVALUE=3
+assert_link 1 '#ifdef VALUE
+return 2;
+#else
+return 1;
+#endif' -DVALUE -UVALUE
+assert_link 10 'b = 0; for(a = 0; a < 10; a = a + 1) b = b + 1; return b;' -fno-integrated-as
+assert_link_with_output "3" add "int add(int a, int b); add(1, 2);"
+assert_link_with_output "3" add "int add(int a, int b); a = 2; return add(2, 1);" -O2
+assert_link_with_output "5" add "int add(int, int); a = 2; if (a == 2) return add(2, 3); return add(1, 1);" -O2
+assert_peephole 47 '5+6*7;'
+assert_peephole 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);'
+assert_peephole 128 "a = 1; return $deep;"
+assert_peephole 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;'
+assert_peephole 16 'a = 3; b = a + 5; if (b > 7) return b * 2; return 1;'
+assert_peephole 3 'a = 1; b = a; c = b + a * 2; return c;'
+assert_link 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_link 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O2
+assert_link 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;' -O0 -fregalloc
+assert_link 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;' -O2 -fno-peephole -fdump-dce
+# the syntax tree passes dump the program as C
+echo 'a = 2 * 3; if (0) a = 1; return a;' > tmp.c
+output=$(e2e/teruc -fdump-dce -S -o tmp.s tmp.c 2>&1)
+expected='a = 6;
+return (int)a;'
+if [ "$output" != "$expected" ]; then
+  echo "-fdump-dce => $expected is expected, but got $output"
+  exit 1
+fi
+echo "-fdump-dce => $output"
+assert_link 240 'b = 0; n = 3; for (i = 0; i < 10; i = i + 1) b = b + i * 4 + n * 2; return b;' -O2
+assert_link 240 'b = 0; n = 3; for (i = 0; i < 10; i = i + 1) b = b + i * 4 + n * 2; return b;' -O2 -fno-loops
+assert_link 14 'a = 1; if (a) while (a < 9) { a = a * 3; a = a - 1; } return a;' -O2
+assert_link 65 's = 0; for (i = 0; i < 5; i = i + 1) for (j = 0; j < 5; j = j + 1) s = s + i * 2 - j; return s + 15;' -O2
+assert_link 1 'unsigned a = 0; return a - 1 > 5;'
+assert_link 1 'unsigned a = 0; return a - 1 > 5;' -O0
+assert_link 1 'unsigned a = 0; return a - 1 > 5;' -O2
+assert_link 1 'unsigned a = 0; return a - 1 > 5;' -fno-ir
+assert_link 0 'int a = 0 - 1; unsigned b = 1; return a < b;'
+assert_link 0 'int a = 0 - 1; unsigned b = 1; return a < b;' -fno-ir
+assert_link 1 'char c = 200; return c < 0;' -O0
+assert_link 1 'char c = 200; return c < 0;' -O2
+assert_link 127 'unsigned long a = 0 - 1; return a / 2 / 72057594037927936;'
+assert_link 127 'unsigned long a = 0 - 1; return a / 2 / 72057594037927936;' -fno-ir
+assert_link 1 'unsigned char c = 0 - 1; long n = c; return n == 255;' -O0
+assert_link 66 'int d = 0 - 7; unsigned u = d; return u / 65536 / 1000 + 1;'
+assert_target 47 aarch64-linux-gnu '5+6*7;'
+assert_target 14 aarch64-linux-gnu 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_target 55 aarch64-linux-gnu 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;' -O2
+assert_target 1 aarch64-linux-gnu 'unsigned a = 0; return a - 1 > 5;'
+assert_target 47 riscv64-linux-gnu '5+6*7;'
+assert_target 14 riscv64-linux-gnu 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_target 55 riscv64-linux-gnu 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;' -O2
+assert_target 1 riscv64-linux-gnu 'char c = 200; return c < 0;'
+assert_wasm 47 '5+6*7;'
+assert_wasm 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_wasm 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;'
+assert_wasm 45 'b = 0; for (a = 0; a < 10; a = a + 1) { if (a == 5) b = b + 5; else b = b + a; } return b;'
+assert_wasm 1 'char c = 200; return c < 0;'
+assert_wasm 5 'int ret3(); return ret3() + 2;'
+assert_llvm 47 '5+6*7;'
+assert_llvm 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_llvm 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;'
+assert_llvm 45 'b = 0; for (a = 0; a < 10; a = a + 1) { if (a == 5) b = b + 5; else b = b + a; } return b;'
+assert_llvm 1 'char c = 200; return c < 0;'
+assert_llvm 3 'int putchar(int); putchar(97); putchar(10); return 3;'
+assert_run 47 '5+6*7;'
+assert_run 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;'
+assert_run 1 'unsigned a = 0; return a - 1 > 5;'
+assert_run 3 'int exit(int); exit(3); return 4;'
+assert_run_output 'aaa' 'int putchar(int); for (a = 0; a < 3; a = a + 1) putchar(97); putchar(10);'
+assert_jit 47 '5+6*7;'
+assert_jit 14 'a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);' -O0
+assert_jit 55 'a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;' -O2
+assert_jit 3 'int exit(int); exit(3); return 4;'
+assert_dump_ast sexpr '(Return :span 1:1-1:14
+  (Add :span 1:8-1:13
+    (Num 1 :span 1:8-1:9)
+    (Num 2 :span 1:12-1:13)))' 'return 1 + 2;'
+assert_dump_ast sexpr '(Declaration :type "char" :span 1:1-1:12
+  (LocalVar c 8 :span 1:6-1:7)
+  (Num 1 :span 1:10-1:11))' 'char c = 1;'
+assert_dump_ast json '[
+  {
+    "kind": "Num",
+    "value": 42,
+    "span": {"start": {"line": 1, "column": 1}, "end": {"line": 1, "column": 3}}
+  }
+]' '42;'
+echo '1;' > tmp2.c
+if e2e/teruc --dump-ast=json tmp.c tmp2.c > /dev/null 2> tmp.err; then
+  echo "--dump-ast=json tmp.c tmp2.c => expected failure, but succeeded"
+  exit 1
+fi
+echo "--dump-ast=json tmp.c tmp2.c => $(cat tmp.err)"
+# without a return, main exits with the value of the last statement when it is
+# an expression or a block ending in one, and 0 otherwise, in every mode
+assert 0 'long a = 3; if (a) a + 4;'
+assert_link 0 'long a = 3; if (a) a + 4;' -O1
+assert_link 0 'long a = 3; if (a) a + 4;' -O2
+assert_run 0 'long a = 3; if (a) a + 4;'
+assert_jit 0 'long a = 3; if (a) a + 4;' -O0
+assert_jit 0 'long a = 3; if (a) a + 4;'
+assert_llvm 0 'long a = 3; if (a) a + 4;'
+assert_wasm 0 'long a = 3; if (a) a + 4;'
+assert_target 0 aarch64-linux-gnu 'long a = 3; if (a) a + 4;'
+assert_target 0 riscv64-linux-gnu 'long a = 3; if (a) a + 4;'
+assert 7 'long a = 3; { a + 4; }'
+assert_link 7 'long a = 3; { a + 4; }' -O1
+assert_link 7 'long a = 3; { a + 4; }' -O2
+assert_run 7 'long a = 3; { a + 4; }'
+assert_jit 7 'long a = 3; { a + 4; }' -O0
+assert_jit 7 'long a = 3; { a + 4; }'
+assert_llvm 7 'long a = 3; { a + 4; }'
+assert_wasm 7 'long a = 3; { a + 4; }'
+assert_target 7 aarch64-linux-gnu 'long a = 3; { a + 4; }'
+assert_target 7 riscv64-linux-gnu 'long a = 3; { a + 4; }'
+# the step of a for still runs when dce removes the body, or when there is none
+assert 3 'for (i = 0; i < 3; i = i + 1) 5; return i;'
+assert_link 3 'for (i = 0; i < 3; i = i + 1) 5; return i;' -O1
+assert_link 3 'for (i = 0; i < 3; i = i + 1) 5; return i;' -O2
+assert_run 3 'for (i = 0; i < 3; i = i + 1) 5; return i;'
+assert_jit 3 'for (i = 0; i < 3; i = i + 1) 5; return i;'
+assert_llvm 3 'for (i = 0; i < 3; i = i + 1) 5; return i;'
+assert 6 'for (a = 0; a < 5;) a = a + 2; return a;'
+assert_link 6 'for (a = 0; a < 5;) a = a + 2; return a;' -O1
+assert_link 6 'for (a = 0; a < 5;) a = a + 2; return a;' -O2
+assert_run 6 'for (a = 0; a < 5;) a = a + 2; return a;'
+assert_jit 6 'for (a = 0; a < 5;) a = a + 2; return a;'
+assert_llvm 6 'for (a = 0; a < 5;) a = a + 2; return a;'
+# constant conditions removed by dce at the end leave the exit status 0
+assert 0 'a = 1; if (1) a = 5;'
+assert_link 0 'a = 1; if (1) a = 5;' -O1
+assert_link 0 'a = 1; if (1) a = 5;' -O2
+assert_run 0 'a = 1; if (1) a = 5;'
+assert_jit 0 'a = 1; if (1) a = 5;'
+assert_llvm 0 'a = 1; if (1) a = 5;'
+assert 0 'a = 6; if (0) a = 2;'
+assert_link 0 'a = 6; if (0) a = 2;' -O1
+assert_link 0 'a = 6; if (0) a = 2;' -O2
+assert_run 0 'a = 6; if (0) a = 2;'
+assert_jit 0 'a = 6; if (0) a = 2;'
+assert_llvm 0 'a = 6; if (0) a = 2;'
+assert 0 'a = 3; while (0) a = 2;'
+assert_link 0 'a = 3; while (0) a = 2;' -O1
+assert_link 0 'a = 3; while (0) a = 2;' -O2
+assert_run 0 'a = 3; while (0) a = 2;'
+assert_jit 0 'a = 3; while (0) a = 2;'
+assert_llvm 0 'a = 3; while (0) a = 2;'
+assert 0 'for (a = 4; 0; a = a + 1) a;'
+assert_link 0 'for (a = 4; 0; a = a + 1) a;' -O1
+assert_link 0 'for (a = 4; 0; a = a + 1) a;' -O2
+assert_run 0 'for (a = 4; 0; a = a + 1) a;'
+assert_jit 0 'for (a = 4; 0; a = a + 1) a;'
+assert_llvm 0 'for (a = 4; 0; a = a + 1) a;'
+
+assert_fail 'return 1'
+assert_fail '1 +;'
+assert_fail 'retrun_val = 1; return retrun_va1;'
+assert_fail 'a = a + 1;'
+assert_fail 'foo();'
+assert_fail 'void f(); a = f();'
+assert_fail 'int add(int, int); return add(1);'
 echo OK
@@ -6,4 +6,6 @@ pub enum Error {
     InvalidNode,
     #[error("left value of assignment must be identifier")]
     LeftValueMustBeIdentifier,
+    #[error(transparent)]
+    Backend(#[from] backend::Error),
 }
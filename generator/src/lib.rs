@@ -1,30 +1,99 @@
-use error::Error;
-use parser::ast::{Node, NodeKind};
+use asm::x86::{
+    directive::Directive,
+    instr::{Cond, Instr},
+    module::AsmModule,
+    operand::Operand,
+    reg::Reg,
+};
+pub use error::Error;
+use parser::ast::{Node, NodeKind, Type};
 
 mod error;
+mod regalloc;
+mod select;
 
-const REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+const REGISTERS: [Reg; 6] = [Reg::Rdi, Reg::Rsi, Reg::Rdx, Reg::Rcx, Reg::R8, Reg::R9];
 
 #[derive(Debug, Default)]
 pub struct Generator {
-    end_labels: u32,
-    else_labels: u32,
-    begin_labels: u32,
+    labels: u32,
+    verbose: bool,
+    regalloc: bool,
+    // registers pushed by the register allocator and not yet popped
+    pushed: usize,
+    module: AsmModule,
 }
 
 impl Generator {
+    pub fn new(verbose: bool) -> Self {
+        Self {
+            verbose,
+            ..Default::default()
+        }
+    }
+
+    /// Evaluate expressions in registers instead of on the stack.
+    pub fn with_regalloc(mut self, regalloc: bool) -> Self {
+        self.regalloc = regalloc;
+        self
+    }
+
+    /// Generate the program as the body of `main`, which returns the value of
+    /// the last statement when it does not return, see `ir::lower::lower`.
+    pub fn generate_program(&mut self, nodes: &[Node]) -> Result<AsmModule, Error> {
+        self.module.directive(Directive::Globl("main".to_string()));
+        self.module.label("main");
+
+        // allocate local area
+        self.emit(Instr::Push(Operand::reg(Reg::Rbp)));
+        self.emit(Instr::Mov(Operand::reg(Reg::Rbp), Operand::reg(Reg::Rsp)));
+        self.emit(Instr::Sub(Operand::reg(Reg::Rsp), Operand::imm(208))); // 8 * 26
+
+        if let Some((last, nodes)) = nodes.split_last() {
+            for node in nodes.iter() {
+                self.generate_stmt(node)?;
+            }
+            self.generate_last(last)?;
+        } else {
+            self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::imm(0)));
+        }
+
+        self.emit(Instr::Mov(Operand::reg(Reg::Rsp), Operand::reg(Reg::Rbp)));
+        self.emit(Instr::Pop(Operand::reg(Reg::Rbp)));
+        self.emit(Instr::Ret);
+
+        Ok(std::mem::take(&mut self.module))
+    }
+
     pub fn generate(&mut self, node: &Node) -> Result<(), Error> {
-        eprintln!("generating node => {:?}\n", node);
+        if self.verbose {
+            eprintln!("generating node => {:?}\n", node);
+        }
+        if self.regalloc && regalloc::is_expr(node) {
+            self.generate_expr(node)?;
+            self.emit(Instr::Push(Operand::reg(Reg::Rax)));
+            return Ok(());
+        }
         match &node.kind {
             NodeKind::Num(n) => {
-                println!("\tpush {n}");
+                let n = *n as i64;
+                if i32::try_from(n).is_ok() {
+                    self.emit(Instr::Push(Operand::imm(n)));
+                } else {
+                    // push only takes a 32 bit immediate
+                    self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::imm(n)));
+                    self.emit(Instr::Push(Operand::reg(Reg::Rax)));
+                }
                 return Ok(());
             }
             NodeKind::LocalVar(_, _) => {
                 self.generate_local_val(node)?;
-                println!("\tpop rax");
-                println!("\tmov rax, [rax]");
-                println!("\tpush rax");
+                self.emit(Instr::Pop(Operand::reg(Reg::Rax)));
+                self.emit(Instr::Mov(
+                    Operand::reg(Reg::Rax),
+                    Operand::mem(Reg::Rax, 0),
+                ));
+                self.emit(Instr::Push(Operand::reg(Reg::Rax)));
                 return Ok(());
             }
             NodeKind::Assignment => {
@@ -38,53 +107,51 @@ impl Generator {
                 } else {
                     return Err(Error::InvalidNode);
                 }
-                println!("\tpop rdi");
-                println!("\tpop rax");
-                println!("\tmov [rax], rdi");
-                println!("\tpush rdi");
+                self.emit(Instr::Pop(Operand::reg(Reg::Rdi)));
+                self.emit(Instr::Pop(Operand::reg(Reg::Rax)));
+                self.emit(Instr::Mov(
+                    Operand::mem(Reg::Rax, 0),
+                    Operand::reg(Reg::Rdi),
+                ));
+                self.emit(Instr::Push(Operand::reg(Reg::Rdi)));
                 return Ok(());
             }
             NodeKind::Return => {
                 if let Some(lhs) = &node.lhs {
-                    self.generate(lhs)?;
+                    self.generate_value(lhs)?;
                 } else {
                     return Err(Error::InvalidNode);
                 }
-                println!("\tpop rax");
-                println!("\tmov rsp, rbp");
-                println!("\tpop rbp");
-                println!("\tret");
+                self.emit(Instr::Mov(Operand::reg(Reg::Rsp), Operand::reg(Reg::Rbp)));
+                self.emit(Instr::Pop(Operand::reg(Reg::Rbp)));
+                self.emit(Instr::Ret);
                 return Ok(());
             }
             NodeKind::If => {
                 if let Some(lhs) = &node.lhs {
-                    self.generate(lhs)?;
+                    self.generate_value(lhs)?;
                 } else {
                     return Err(Error::InvalidNode);
                 }
-                println!("\tpop rax");
-                println!("\tcmp rax, 0");
+                let id = self.new_label_id();
+                self.emit(Instr::Cmp(Operand::reg(Reg::Rax), Operand::imm(0)));
                 if let Some(rhs) = &node.rhs {
                     if rhs.kind.eq(&NodeKind::Else) {
-                        println!("\tje .Lelse{}", self.else_labels);
+                        self.emit(Instr::Jcc(Cond::E, format!(".Lelse{id}")));
                         // gen some code
                         if let Some(lhs) = &rhs.lhs {
-                            self.generate(lhs)?;
-                            println!("\tjmp .Lend{}", self.end_labels);
+                            self.generate_body(lhs)?;
+                            self.emit(Instr::Jmp(format!(".Lend{id}")));
                         }
+                        self.module.label(&format!(".Lelse{id}"));
                         if let Some(rhs) = &rhs.rhs {
-                            println!(".Lelse{}:", self.else_labels);
-                            self.else_labels += 1;
-                            self.generate(rhs)?;
+                            self.generate_body(rhs)?;
                         }
-                        // TODO: handle duplicate .LendX when handling "else if"
-                        println!(".Lend{}:", self.end_labels);
-                        self.end_labels += 1;
+                        self.module.label(&format!(".Lend{id}"));
                     } else {
-                        println!("\tje .Lend{}", self.end_labels);
-                        self.generate(rhs)?;
-                        println!(".Lend{}:", self.end_labels);
-                        self.end_labels += 1;
+                        self.emit(Instr::Jcc(Cond::E, format!(".Lend{id}")));
+                        self.generate_body(rhs)?;
+                        self.module.label(&format!(".Lend{id}"));
                     }
                 } else {
                     return Err(Error::InvalidNode);
@@ -92,69 +159,60 @@ impl Generator {
                 return Ok(());
             }
             NodeKind::While => {
-                println!(".Lbegin{}:", self.begin_labels);
+                let id = self.new_label_id();
+                self.module.label(&format!(".Lbegin{id}"));
                 if let Some(lhs) = &node.lhs {
-                    self.generate(lhs)?;
+                    self.generate_value(lhs)?;
                 } else {
                     return Err(Error::InvalidNode);
                 }
-                println!("\tpop rax");
-                println!("\tcmp rax, 0");
-                println!("\tje .Lend{}", self.end_labels);
+                self.emit(Instr::Cmp(Operand::reg(Reg::Rax), Operand::imm(0)));
+                self.emit(Instr::Jcc(Cond::E, format!(".Lend{id}")));
                 if let Some(rhs) = &node.rhs {
-                    self.generate(rhs)?;
+                    self.generate_body(rhs)?;
                 } else {
                     return Err(Error::InvalidNode);
                 }
-                println!("\tjmp .Lbegin{}", self.begin_labels);
-                self.begin_labels += 1;
-                println!(".Lend{}:", self.end_labels);
-                self.end_labels += 1;
+                self.emit(Instr::Jmp(format!(".Lbegin{id}")));
+                self.module.label(&format!(".Lend{id}"));
                 return Ok(());
             }
             NodeKind::For => {
-                if let Some(lhs) = &node.lhs {
-                    self.generate(lhs)?;
-                } else {
-                    return Err(Error::InvalidNode);
-                }
-                println!(".Lbegin{}", self.begin_labels);
-                if let Some(rhs) = &node.rhs {
-                    if rhs.kind.ne(&NodeKind::If) {
-                        return Err(Error::InvalidNode);
-                    }
-                    if let Some(lhs) = &rhs.lhs {
-                        self.generate(lhs)?;
-                    } else {
-                        return Err(Error::InvalidNode);
-                    }
-                    println!("\tpop rax");
-                    println!("\tcmp rax, 0");
-                    println!("\tje .Lend{}", self.end_labels);
-                    if let Some(rhs) = &rhs.rhs {
-                        self.generate(rhs)?;
-                    }
-                    println!("\tjmp .Lbegin{}", self.begin_labels);
-                    self.begin_labels += 1;
-                }
-                println!(".Lend{}", self.end_labels);
-                self.end_labels += 1;
+                let parts = node.for_parts().ok_or(Error::InvalidNode)?;
+                self.generate_body(parts.init.ok_or(Error::InvalidNode)?)?;
+                let id = self.new_label_id();
+                self.module.label(&format!(".Lbegin{id}"));
+                self.generate_value(parts.cond.ok_or(Error::InvalidNode)?)?;
+                self.emit(Instr::Cmp(Operand::reg(Reg::Rax), Operand::imm(0)));
+                self.emit(Instr::Jcc(Cond::E, format!(".Lend{id}")));
+                self.generate_body(parts.iteration)?;
+                self.emit(Instr::Jmp(format!(".Lbegin{id}")));
+                self.module.label(&format!(".Lend{id}"));
                 return Ok(());
             }
             NodeKind::Block(nodes) => {
                 for node in nodes.iter() {
-                    self.generate(node)?;
-                    println!("\tpop rax");
+                    self.generate_stmt(node)?;
                 }
                 return Ok(());
             }
+            NodeKind::Cast => {
+                self.generate_value(node.lhs.as_ref().ok_or(Error::InvalidNode)?)?;
+                self.convert(Reg::Rax, node.ty);
+                self.emit(Instr::Push(Operand::reg(Reg::Rax)));
+                return Ok(());
+            }
             NodeKind::Func(f, args) => {
                 for (i, arg) in args.iter().rev().enumerate() {
                     if let Some(n) = arg.num() {
-                        println!("\tmov {}, {n}", REGISTERS[args.len() - 1 - i])
+                        self.emit(Instr::Mov(
+                            Operand::reg(REGISTERS[args.len() - 1 - i]),
+                            Operand::imm(n as i64),
+                        ));
                     }
                 }
-                println!("\tcall {}", f);
+                self.emit(Instr::Call(f.clone()));
+                self.emit(Instr::Push(Operand::reg(Reg::Rax)));
                 return Ok(());
             }
             _ => {}
@@ -171,54 +229,455 @@ impl Generator {
             return Err(Error::InvalidNode);
         }
 
-        println!("\tpop rdi");
-        println!("\tpop rax");
+        self.emit(Instr::Pop(Operand::reg(Reg::Rdi)));
+        self.emit(Instr::Pop(Operand::reg(Reg::Rax)));
 
+        let rax = Operand::reg(Reg::Rax);
+        let rdi = Operand::reg(Reg::Rdi);
         match node.kind {
-            NodeKind::Add => println!("\tadd rax, rdi"),
-            NodeKind::Sub => println!("\tsub rax, rdi"),
-            NodeKind::Mul => println!("\timul rax, rdi"),
-            NodeKind::Div => {
-                println!("\tcqo");
-                println!("\tidiv rax, rdi");
+            NodeKind::Add => self.emit(Instr::Add(rax, rdi)),
+            NodeKind::Sub => self.emit(Instr::Sub(rax, rdi)),
+            NodeKind::Mul => self.emit(Instr::Imul(rax, rdi)),
+            NodeKind::Div => self.divide(node, rdi),
+            _ => {
+                let cond = condition(node).ok_or(Error::InvalidNode)?;
+                self.compare(cond);
             }
-            NodeKind::Equal => {
-                println!("\tcmp rax, rdi");
-                println!("\tsete al");
-                println!("\tmovzb rax, al");
-            }
-            NodeKind::NotEqual => {
-                println!("\tcmp rax, rdi");
-                println!("\tsetne al");
-                println!("\tmovzb rax, al");
-            }
-            NodeKind::LessThan => {
-                println!("\tcmp rax, rdi");
-                println!("\tsetl al");
-                println!("\tmovzb rax, al");
-            }
-            NodeKind::LessThanOrEqual => {
-                println!("\tcmp rax, rdi");
-                println!("\tsetle al");
-                println!("\tmovzb rax, al");
+        }
+
+        self.emit(Instr::Push(Operand::reg(Reg::Rax)));
+
+        Ok(())
+    }
+
+    // generate a statement, leaving the value of an expression in rax
+    fn generate_stmt(&mut self, node: &Node) -> Result<(), Error> {
+        if let NodeKind::Prototype(_, _) = node.kind {
+            // declarations take no code
+            return Ok(());
+        }
+        if !self.regalloc {
+            self.generate(node)?;
+            self.emit(Instr::Pop(Operand::reg(Reg::Rax)));
+        } else if regalloc::is_expr(node) {
+            self.generate_expr(node)?;
+        } else {
+            self.generate(node)?;
+        }
+        Ok(())
+    }
+
+    // generate the last statement of main, leaving in rax the value of an
+    // expression or of a block ending in one and 0 after anything else
+    fn generate_last(&mut self, node: &Node) -> Result<(), Error> {
+        match &node.kind {
+            NodeKind::Block(nodes) => match nodes.split_last() {
+                Some((last, nodes)) => {
+                    for node in nodes.iter() {
+                        self.generate_stmt(node)?;
+                    }
+                    self.generate_last(last)
+                }
+                None => {
+                    self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::imm(0)));
+                    Ok(())
+                }
+            },
+            NodeKind::Return => self.generate_stmt(node),
+            NodeKind::If | NodeKind::While | NodeKind::For | NodeKind::Prototype(_, _) => {
+                self.generate_stmt(node)?;
+                self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::imm(0)));
+                Ok(())
             }
-            _ => return Err(Error::InvalidNode),
+            _ => self.generate_stmt(node),
         }
+    }
 
-        println!("\tpush rax");
+    // generate the body of a control statement
+    fn generate_body(&mut self, node: &Node) -> Result<(), Error> {
+        if self.regalloc {
+            self.generate_stmt(node)
+        } else {
+            self.generate(node)
+        }
+    }
 
+    // generate an expression and move its value into rax
+    fn generate_value(&mut self, node: &Node) -> Result<(), Error> {
+        if self.regalloc {
+            return self.generate_expr(node);
+        }
+        self.generate(node)?;
+        self.emit(Instr::Pop(Operand::reg(Reg::Rax)));
         Ok(())
     }
 
-    fn generate_local_val(&self, node: &Node) -> Result<(), Error> {
-        eprintln!("generate local ver => {:?}\n", node);
+    fn generate_local_val(&mut self, node: &Node) -> Result<(), Error> {
+        if self.verbose {
+            eprintln!("generate local ver => {:?}\n", node);
+        }
         if let NodeKind::LocalVar(_s, offset) = &node.kind {
-            println!("\tmov rax, rbp");
-            println!("\tsub rax, {offset}");
-            println!("\tpush rax");
+            self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::reg(Reg::Rbp)));
+            self.emit(Instr::Sub(
+                Operand::reg(Reg::Rax),
+                Operand::imm(*offset as i64),
+            ));
+            self.emit(Instr::Push(Operand::reg(Reg::Rax)));
             Ok(())
         } else {
             Err(Error::LeftValueMustBeIdentifier)
         }
     }
+
+    // compare rax with rdi and set the result to rax
+    fn compare(&mut self, cond: Cond) {
+        self.emit(Instr::Cmp(Operand::reg(Reg::Rax), Operand::reg(Reg::Rdi)));
+        self.emit(Instr::Set(cond, Operand::reg8(Reg::Rax)));
+        self.emit(Instr::Movzx(
+            Operand::reg(Reg::Rax),
+            Operand::reg8(Reg::Rax),
+        ));
+    }
+
+    // rax = rax / src, signed unless the operands are unsigned
+    fn divide(&mut self, node: &Node, src: Operand) {
+        if node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned()) {
+            self.emit(Instr::Mov(Operand::reg(Reg::Rdx), Operand::imm(0)));
+            self.emit(Instr::Div(src));
+        } else {
+            self.emit(Instr::Cqo);
+            self.emit(Instr::Idiv(src));
+        }
+    }
+
+    // convert the 64 bit value in the register to the type
+    fn convert(&mut self, reg: Reg, ty: Option<Type>) {
+        let r = Operand::reg(reg);
+        match ty {
+            Some(Type::Char) => self.emit(Instr::Movsx(r, Operand::reg8(reg))),
+            Some(Type::UChar) => self.emit(Instr::Movzx(r, Operand::reg8(reg))),
+            Some(Type::Int) => self.emit(Instr::Movsx(r, Operand::reg32(reg))),
+            Some(Type::UInt) => self.emit(Instr::Mov(Operand::reg32(reg), Operand::reg32(reg))),
+            _ => {}
+        }
+    }
+
+    fn emit(&mut self, instr: Instr) {
+        self.module.instr(instr);
+    }
+
+    fn new_label_id(&mut self) -> u32 {
+        let id = self.labels;
+        self.labels += 1;
+        id
+    }
+}
+
+// condition of a comparison, unsigned when its operands are
+fn condition(node: &Node) -> Option<Cond> {
+    let unsigned = node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned());
+    match (&node.kind, unsigned) {
+        (NodeKind::Equal, _) => Some(Cond::E),
+        (NodeKind::NotEqual, _) => Some(Cond::Ne),
+        (NodeKind::LessThan, false) => Some(Cond::L),
+        (NodeKind::LessThan, true) => Some(Cond::B),
+        (NodeKind::LessThanOrEqual, false) => Some(Cond::Le),
+        (NodeKind::LessThanOrEqual, true) => Some(Cond::Be),
+        (NodeKind::GreaterThan, false) => Some(Cond::G),
+        (NodeKind::GreaterThan, true) => Some(Cond::A),
+        (NodeKind::GreaterThanOrEqual, false) => Some(Cond::Ge),
+        (NodeKind::GreaterThanOrEqual, true) => Some(Cond::Ae),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use asm::x86::intel;
+    use parser::ast::{Node, NodeKind, Type};
+    use rstest::rstest;
+
+    use super::{regalloc::SCRATCH, Generator};
+
+    #[rstest(
+        input,
+        expect,
+        case(Node::new_num(42), vec!["push 42"]),
+        case(
+            Node::new(NodeKind::Add, Some(Box::new(Node::new_num(1))), Some(Box::new(Node::new_num(2)))),
+            vec!["push 1", "push 2", "pop rdi", "pop rax", "add rax, rdi", "push rax"]
+        ),
+        case(
+            Node::new(NodeKind::Assignment, Some(Box::new(Node::new_local_var("a".to_string(), 8))), Some(Box::new(Node::new_num(1)))),
+            vec!["mov rax, rbp", "sub rax, 8", "push rax", "push 1", "pop rdi", "pop rax", "mov [rax], rdi", "push rdi"]
+        ),
+        case(
+            Node::new(NodeKind::Func("add".to_string(), vec![Node::new_num(1), Node::new_num(2)]), None, None),
+            vec!["mov rsi, 2", "mov rdi, 1", "call add", "push rax"]
+        ),
+    )]
+    fn test_generator_generate(input: Node, expect: Vec<&str>) {
+        let mut generator = Generator::default();
+        generator.generate(&input).unwrap();
+        let instrs: Vec<String> = generator.module.instrs().map(intel::format_instr).collect();
+        assert_eq!(expect, instrs);
+    }
+
+    fn binary(kind: NodeKind, lhs: Node, rhs: Node) -> Node {
+        Node::new(kind, Some(Box::new(lhs)), Some(Box::new(rhs)))
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case(Node::new_num(42), vec!["mov r10, 42", "mov rax, r10"]),
+        case(
+            binary(NodeKind::Add, Node::new_num(1), Node::new_num(2)),
+            vec!["mov r10, 1", "add r10, 2", "mov rax, r10"]
+        ),
+        case(
+            binary(NodeKind::Sub, Node::new_num(1), Node::new_local_var("a".to_string(), 8)),
+            vec!["mov r10, 1", "sub r10, [rbp-8]", "mov rax, r10"]
+        ),
+        case(
+            binary(
+                NodeKind::Mul,
+                Node::new_num(2),
+                binary(NodeKind::Add, Node::new_num(3), Node::new_num(4)),
+            ),
+            vec!["mov r10, 2", "mov r11, 3", "add r11, 4", "imul r10, r11", "mov rax, r10"]
+        ),
+        case(
+            // the right operand needs more registers and is evaluated first
+            binary(
+                NodeKind::Sub,
+                Node::new_num(2),
+                binary(
+                    NodeKind::Mul,
+                    binary(NodeKind::Add, Node::new_num(3), Node::new_num(4)),
+                    binary(NodeKind::Add, Node::new_num(5), Node::new_num(6)),
+                ),
+            ),
+            vec![
+                "mov r10, 3", "add r10, 4", "mov r11, 5", "add r11, 6", "imul r10, r11",
+                "mov r11, 2", "sub r11, r10", "mov r10, r11", "mov rax, r10",
+            ]
+        ),
+        case(
+            binary(NodeKind::Div, Node::new_num(6), Node::new_num(3)),
+            vec!["mov r10, 6", "mov rdi, 3", "mov rax, r10", "cqo", "idiv rdi", "mov r10, rax", "mov rax, r10"]
+        ),
+        case(
+            binary(NodeKind::Div, Node::new_num(6).with_ty(Type::ULong), Node::new_num(3).with_ty(Type::ULong)),
+            vec!["mov r10, 6", "mov rdi, 3", "mov rax, r10", "mov rdx, 0", "div rdi", "mov r10, rax", "mov rax, r10"]
+        ),
+        case(
+            binary(NodeKind::LessThan, Node::new_num(1).with_ty(Type::UInt), Node::new_num(2).with_ty(Type::UInt)),
+            vec!["mov r10, 1", "cmp r10, 2", "setb al", "movzx r10, al", "mov rax, r10"]
+        ),
+        case(
+            Node::new(NodeKind::Cast, Some(Box::new(Node::new_local_var("a".to_string(), 8))), None).with_ty(Type::Char),
+            vec!["mov r10, [rbp-8]", "movsx r10, r10b", "mov rax, r10"]
+        ),
+        case(
+            Node::new(NodeKind::Assignment, Some(Box::new(Node::new_local_var("a".to_string(), 8))), Some(Box::new(Node::new_num(1)))),
+            vec!["mov r10, 1", "mov [rbp-8], r10", "mov rax, r10"]
+        ),
+    )]
+    fn test_generator_generate_regalloc(input: Node, expect: Vec<&str>) {
+        let mut generator = Generator::default().with_regalloc(true);
+        generator.generate_expr(&input).unwrap();
+        let instrs: Vec<String> = generator.module.instrs().map(intel::format_instr).collect();
+        assert_eq!(expect, instrs);
+    }
+
+    #[test]
+    fn test_generator_regalloc_spill() {
+        // a balanced tree deeper than the scratch registers
+        let mut node = Node::new_local_var("a".to_string(), 8);
+        for _ in 0..SCRATCH.len() + 1 {
+            node = binary(NodeKind::Add, node.clone(), node);
+        }
+        let mut generator = Generator::default().with_regalloc(true);
+        generator.generate_expr(&node).unwrap();
+        let instrs: Vec<String> = generator.module.instrs().map(intel::format_instr).collect();
+        let pushes = instrs.iter().filter(|i| i.starts_with("push")).count();
+        let pops = instrs.iter().filter(|i| i.starts_with("pop")).count();
+        assert!(pushes > 0);
+        assert_eq!(pushes, pops);
+        assert_eq!(0, generator.pushed);
+    }
+
+    #[test]
+    fn test_generator_generate_module() {
+        use ir::{
+            function::{Block, Function, Module},
+            instr::{BinOp, BlockId, CmpOp, Inst, Operand, Terminator, Type, Value},
+        };
+
+        // while (a < 10) a = a + 1; return a;
+        let mut func = Function::new("main");
+        let a = func.new_slot("a");
+        func.values = vec![Type::I64, Type::I1, Type::I64, Type::I64, Type::I64];
+        func.blocks = vec![
+            Block {
+                id: BlockId(0),
+                insts: vec![
+                    Inst::Load {
+                        dst: Value(0),
+                        slot: a,
+                    },
+                    Inst::Cmp {
+                        dst: Value(1),
+                        op: CmpOp::Lt,
+                        lhs: Operand::Value(Value(0)),
+                        rhs: Operand::Const(10),
+                    },
+                ],
+                term: Terminator::Branch {
+                    cond: Operand::Value(Value(1)),
+                    then: BlockId(1),
+                    otherwise: BlockId(2),
+                },
+            },
+            Block {
+                id: BlockId(1),
+                insts: vec![
+                    Inst::Load {
+                        dst: Value(2),
+                        slot: a,
+                    },
+                    Inst::Binary {
+                        dst: Value(3),
+                        op: BinOp::Add,
+                        lhs: Operand::Value(Value(2)),
+                        rhs: Operand::Const(1),
+                    },
+                    Inst::Store {
+                        slot: a,
+                        src: Operand::Value(Value(3)),
+                    },
+                ],
+                term: Terminator::Jump(BlockId(0)),
+            },
+            Block {
+                id: BlockId(2),
+                insts: vec![Inst::Load {
+                    dst: Value(4),
+                    slot: a,
+                }],
+                term: Terminator::Return(Operand::Value(Value(4))),
+            },
+        ];
+        let module = Module {
+            functions: vec![func],
+        };
+
+        let mut generator = Generator::default().with_regalloc(true);
+        let asm = generator.generate_module(&module).unwrap();
+        let instrs: Vec<String> = asm.instrs().map(intel::format_instr).collect();
+        assert_eq!(
+            vec![
+                "push rbp",
+                "mov rbp, rsp",
+                "sub rsp, 16",
+                "mov r10, [rbp-8]",
+                "cmp r10, 10",
+                "jge .Lmain.2",
+                "mov r10, [rbp-8]",
+                "mov r11, r10",
+                "add r11, 1",
+                "mov [rbp-8], r11",
+                "jmp .Lmain.0",
+                "mov r10, [rbp-8]",
+                "mov rax, r10",
+                "mov rsp, rbp",
+                "pop rbp",
+                "ret",
+            ],
+            instrs
+        );
+    }
+
+    #[test]
+    fn test_generator_generate_tail_call() {
+        use ir::{
+            function::{Block, Function, Module},
+            instr::{BlockId, Inst, Operand, Terminator, Type, Value},
+        };
+
+        // a = 2; return foo(a, 1); with the load kept across the frame teardown
+        let mut func = Function::new("main");
+        let a = func.new_slot("a");
+        func.values = vec![Type::I64];
+        func.blocks = vec![Block {
+            id: BlockId(0),
+            insts: vec![
+                Inst::Store {
+                    slot: a,
+                    src: Operand::Const(2),
+                },
+                Inst::Load {
+                    dst: Value(0),
+                    slot: a,
+                },
+            ],
+            term: Terminator::TailCall {
+                func: "foo".to_string(),
+                args: vec![Operand::Value(Value(0)), Operand::Const(1)],
+            },
+        }];
+        let module = Module {
+            functions: vec![func],
+        };
+
+        let mut generator = Generator::default().with_regalloc(true);
+        let asm = generator.generate_module(&module).unwrap();
+        let instrs: Vec<String> = asm.instrs().map(intel::format_instr).collect();
+        assert_eq!(
+            vec![
+                "push rbp",
+                "mov rbp, rsp",
+                "sub rsp, 16",
+                "mov QWORD PTR [rbp-8], 2",
+                "mov r10, [rbp-8]",
+                "mov rdi, r10",
+                "mov rsi, 1",
+                "mov rsp, rbp",
+                "pop rbp",
+                "jmp foo",
+            ],
+            instrs
+        );
+    }
+
+    #[test]
+    fn test_generator_generate_program() {
+        let mut generator = Generator::default();
+        let module = generator.generate_program(&[Node::new_num(0)]).unwrap();
+        let mut out = Vec::new();
+        intel::write_module(&module, &mut out).unwrap();
+        let asm = String::from_utf8(out).unwrap();
+        assert!(asm.starts_with(".intel_syntax noprefix\n.globl main\nmain:\n"));
+        assert!(asm.ends_with("\tmov rsp, rbp\n\tpop rbp\n\tret\n"));
+    }
+
+    // main returns the value of a last expression, even in a block, and 0
+    // after any other statement
+    #[rstest(
+        input,
+        expect,
+        case(vec![], vec!["mov rax, 0"]),
+        case(vec![Node::new(NodeKind::Block(vec![Node::new_num(4)]), None, None)], vec!["push 4", "pop rax"]),
+        case(vec![Node::new(NodeKind::Block(vec![]), None, None)], vec!["mov rax, 0"]),
+        case(vec![Node::new(NodeKind::If, Some(Box::new(Node::new_num(1))), Some(Box::new(Node::new_num(4))))], vec!["pop rax", "mov rax, 0"]),
+        case(vec![Node::new(NodeKind::Prototype("f".to_string(), None), None, None)], vec!["sub rsp, 208", "mov rax, 0"])
+    )]
+    fn test_generator_generate_program_value(input: Vec<Node>, expect: Vec<&str>) {
+        let mut generator = Generator::default();
+        let module = generator.generate_program(&input).unwrap();
+        let instrs: Vec<String> = module.instrs().map(intel::format_instr).collect();
+        let epilogue = instrs.len() - 3;
+        assert_eq!(expect, instrs[epilogue - expect.len()..epilogue]);
+    }
 }
@@ -0,0 +1,221 @@
+use asm::x86::{
+    instr::{Cond, Instr},
+    operand::Operand,
+    reg::Reg,
+};
+use parser::ast::{Node, NodeKind};
+
+use crate::{condition, Error, Generator, REGISTERS};
+
+// scratch registers for expressions, rax, rdx and rdi are kept free for
+// division, comparison results and spilled operands
+pub(crate) const SCRATCH: [Reg; 6] = [Reg::R10, Reg::R11, Reg::Rsi, Reg::Rcx, Reg::R8, Reg::R9];
+
+// operand reloaded from the stack when the scratch registers run out
+const SPILL: Reg = Reg::Rdi;
+
+/// Whether the node is an expression the register allocator can evaluate.
+pub(crate) fn is_expr(node: &Node) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::Num(_)
+            | NodeKind::LocalVar(_, _)
+            | NodeKind::Assignment
+            | NodeKind::Func(_, _)
+            | NodeKind::Cast
+            | NodeKind::Add
+            | NodeKind::Sub
+            | NodeKind::Mul
+            | NodeKind::Div
+            | NodeKind::Equal
+            | NodeKind::NotEqual
+            | NodeKind::LessThan
+            | NodeKind::GreaterThan
+            | NodeKind::LessThanOrEqual
+            | NodeKind::GreaterThanOrEqual
+    )
+}
+
+/// Sethi-Ullman number of the node, the registers needed to evaluate it
+/// without spilling.
+pub(crate) fn need(node: &Node) -> usize {
+    match (&node.kind, &node.lhs, &node.rhs) {
+        (NodeKind::Assignment, _, Some(rhs)) => need(rhs),
+        (NodeKind::Cast, Some(lhs), _) => need(lhs),
+        (_, Some(lhs), Some(rhs)) => {
+            let l = need(lhs);
+            let r = if operand(rhs).is_some() { 0 } else { need(rhs) };
+            if l == r {
+                l + 1
+            } else {
+                l.max(r)
+            }
+        }
+        _ => 1,
+    }
+}
+
+// leaves that can be used directly as the source operand of an instruction
+fn operand(node: &Node) -> Option<Operand> {
+    match node.kind {
+        NodeKind::Num(n) if i32::try_from(n).is_ok() => Some(Operand::imm(n as i64)),
+        NodeKind::LocalVar(_, offset) => Some(local(offset)),
+        _ => None,
+    }
+}
+
+fn local(offset: u32) -> Operand {
+    Operand::mem(Reg::Rbp, -(offset as i32))
+}
+
+impl Generator {
+    /// Evaluate an expression into rax.
+    pub(crate) fn generate_expr(&mut self, node: &Node) -> Result<(), Error> {
+        self.generate_reg(node, 0)?;
+        self.emit(Instr::Mov(Operand::reg(Reg::Rax), Operand::reg(SCRATCH[0])));
+        Ok(())
+    }
+
+    // evaluate the node into SCRATCH[dst] using only SCRATCH[dst..]
+    fn generate_reg(&mut self, node: &Node, dst: usize) -> Result<(), Error> {
+        if self.verbose {
+            eprintln!("generating node into {} => {:?}\n", SCRATCH[dst], node);
+        }
+        let reg = SCRATCH[dst];
+        match &node.kind {
+            NodeKind::Num(n) => {
+                self.emit(Instr::Mov(Operand::reg(reg), Operand::imm(*n as i64)));
+                return Ok(());
+            }
+            NodeKind::LocalVar(_, offset) => {
+                self.emit(Instr::Mov(Operand::reg(reg), local(*offset)));
+                return Ok(());
+            }
+            NodeKind::Assignment => {
+                let Some(NodeKind::LocalVar(_, offset)) = node.lhs.as_ref().map(|n| &n.kind) else {
+                    return Err(Error::LeftValueMustBeIdentifier);
+                };
+                let rhs = node.rhs.as_ref().ok_or(Error::InvalidNode)?;
+                self.generate_reg(rhs, dst)?;
+                self.emit(Instr::Mov(local(*offset), Operand::reg(reg)));
+                return Ok(());
+            }
+            NodeKind::Func(f, args) => {
+                self.generate_call(f, args, dst);
+                return Ok(());
+            }
+            NodeKind::Cast => {
+                self.generate_reg(node.lhs.as_ref().ok_or(Error::InvalidNode)?, dst)?;
+                self.convert(reg, node.ty);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let (Some(lhs), Some(rhs)) = (&node.lhs, &node.rhs) else {
+            return Err(Error::InvalidNode);
+        };
+        if let Some(src) = operand(rhs) {
+            self.generate_reg(lhs, dst)?;
+            return self.binary(node, reg, src);
+        }
+        if dst + 1 == SCRATCH.len() {
+            // out of registers, keep the right operand on the stack
+            self.generate_reg(rhs, dst)?;
+            self.push(reg);
+            self.generate_reg(lhs, dst)?;
+            self.pop(SPILL);
+            return self.binary(node, reg, Operand::reg(SPILL));
+        }
+        // evaluate the subtree needing more registers first
+        if need(lhs) >= need(rhs) {
+            self.generate_reg(lhs, dst)?;
+            self.generate_reg(rhs, dst + 1)?;
+            self.binary(node, reg, Operand::reg(SCRATCH[dst + 1]))
+        } else {
+            self.generate_reg(rhs, dst)?;
+            self.generate_reg(lhs, dst + 1)?;
+            self.binary(node, SCRATCH[dst + 1], Operand::reg(reg))?;
+            self.emit(Instr::Mov(
+                Operand::reg(reg),
+                Operand::reg(SCRATCH[dst + 1]),
+            ));
+            Ok(())
+        }
+    }
+
+    // dst = dst op src
+    fn binary(&mut self, node: &Node, dst: Reg, src: Operand) -> Result<(), Error> {
+        let d = Operand::reg(dst);
+        match node.kind {
+            NodeKind::Add => self.emit(Instr::Add(d, src)),
+            NodeKind::Sub => self.emit(Instr::Sub(d, src)),
+            NodeKind::Mul => self.emit(Instr::Imul(d, src)),
+            NodeKind::Div => {
+                // div and idiv take no immediate
+                let src = if let Operand::Imm(_) = src {
+                    self.emit(Instr::Mov(Operand::reg(SPILL), src));
+                    Operand::reg(SPILL)
+                } else {
+                    src
+                };
+                self.emit(Instr::Mov(Operand::reg(Reg::Rax), d.clone()));
+                self.divide(node, src);
+                self.emit(Instr::Mov(d, Operand::reg(Reg::Rax)));
+            }
+            _ => {
+                let cond = condition(node).ok_or(Error::InvalidNode)?;
+                self.compare_reg(cond, dst, src);
+            }
+        }
+        Ok(())
+    }
+
+    fn compare_reg(&mut self, cond: Cond, dst: Reg, src: Operand) {
+        self.emit(Instr::Cmp(Operand::reg(dst), src));
+        self.emit(Instr::Set(cond, Operand::reg8(Reg::Rax)));
+        self.emit(Instr::Movzx(Operand::reg(dst), Operand::reg8(Reg::Rax)));
+    }
+
+    // the scratch registers below dst are live and saved around the call
+    fn generate_call(&mut self, f: &str, args: &[Node], dst: usize) {
+        let live = &SCRATCH[..dst];
+        for reg in live.iter() {
+            self.push(*reg);
+        }
+        // keep the stack 16 byte aligned at the call
+        let pad = self.pushed % 2 == 1;
+        if pad {
+            self.emit(Instr::Sub(Operand::reg(Reg::Rsp), Operand::imm(8)));
+        }
+        for (i, arg) in args.iter().enumerate() {
+            if let Some(n) = arg.num() {
+                self.emit(Instr::Mov(
+                    Operand::reg(REGISTERS[i]),
+                    Operand::imm(n as i64),
+                ));
+            }
+        }
+        self.emit(Instr::Call(f.to_string()));
+        if pad {
+            self.emit(Instr::Add(Operand::reg(Reg::Rsp), Operand::imm(8)));
+        }
+        self.emit(Instr::Mov(
+            Operand::reg(SCRATCH[dst]),
+            Operand::reg(Reg::Rax),
+        ));
+        for reg in live.iter().rev() {
+            self.pop(*reg);
+        }
+    }
+
+    fn push(&mut self, reg: Reg) {
+        self.emit(Instr::Push(Operand::reg(reg)));
+        self.pushed += 1;
+    }
+
+    fn pop(&mut self, reg: Reg) {
+        self.emit(Instr::Pop(Operand::reg(reg)));
+        self.pushed -= 1;
+    }
+}
@@ -0,0 +1,326 @@
+use asm::x86::{
+    directive::Directive,
+    instr::{Cond, Instr},
+    module::AsmModule,
+    operand::Operand,
+    reg::Reg,
+};
+use backend::{Backend, Frame};
+use ir::{
+    function::Module,
+    instr::{self, BinOp, CmpOp, Slot, Value},
+    regalloc::Location,
+};
+
+use crate::{Error, Generator, REGISTERS};
+
+// rax, rdx and rdi stay free as temporaries and the argument registers are
+// only written when setting up calls
+const CALLER_SAVED: [Reg; 2] = [Reg::R10, Reg::R11];
+const CALLEE_SAVED: [Reg; 5] = [Reg::Rbx, Reg::R12, Reg::R13, Reg::R14, Reg::R15];
+
+// the frame is addressed from rbp, the callee saved registers are pushed right
+// below it followed by the slots and the spill slots
+fn saved(frame: &Frame<Reg>) -> i32 {
+    frame.alloc.callee_saved.len() as i32 * 8
+}
+
+fn slot(frame: &Frame<Reg>, slot: Slot) -> Operand {
+    Operand::mem(Reg::Rbp, -saved(frame) - 8 * (slot.0 as i32 + 1))
+}
+
+fn location(frame: &Frame<Reg>, value: Value) -> Operand {
+    match frame.alloc.location(value) {
+        Location::Reg(reg) => Operand::reg(reg),
+        Location::Stack(i) => {
+            Operand::mem(Reg::Rbp, -saved(frame) - 8 * ((frame.slots + i) as i32 + 1))
+        }
+    }
+}
+
+fn operand(frame: &Frame<Reg>, op: &instr::Operand) -> Operand {
+    match op {
+        instr::Operand::Const(n) => Operand::imm(*n),
+        instr::Operand::Value(v) => location(frame, *v),
+    }
+}
+
+// bytes below the saved registers, keeping rsp 16 byte aligned
+fn size(frame: &Frame<Reg>) -> i64 {
+    let size = 8 * (frame.slots + frame.alloc.spill_slots) as i64;
+    let total = size + saved(frame) as i64;
+    size + (16 - total % 16) % 16
+}
+
+impl Generator {
+    /// Generate the functions of an IR module.
+    pub fn generate_module(&mut self, module: &Module) -> Result<AsmModule, Error> {
+        backend::generate_module(self, module, self.regalloc)?;
+        Ok(self.finish())
+    }
+
+    fn arguments(&mut self, frame: &Frame<Reg>, args: &[instr::Operand]) {
+        // arguments never live in argument registers so they can be set up in order
+        for (arg, reg) in args.iter().zip(REGISTERS.iter()) {
+            self.move_to(Operand::reg(*reg), operand(frame, arg));
+        }
+    }
+
+    // set the flags from comparing lhs with rhs
+    fn compare_operands(&mut self, frame: &Frame<Reg>, lhs: &instr::Operand, rhs: &instr::Operand) {
+        let rhs = self.source(operand(frame, rhs));
+        let lhs = match operand(frame, lhs) {
+            lhs @ Operand::Reg(_, _) => lhs,
+            lhs @ Operand::Mem(_) if !matches!(rhs, Operand::Mem(_)) => lhs,
+            lhs => {
+                self.move_to(Operand::reg(Reg::Rax), lhs);
+                Operand::reg(Reg::Rax)
+            }
+        };
+        self.emit(Instr::Cmp(lhs, rhs));
+    }
+
+    // immediates wider than 32 bits only fit mov, load them into rdi
+    fn source(&mut self, src: Operand) -> Operand {
+        match src {
+            Operand::Imm(n) if i32::try_from(n).is_err() => {
+                self.emit(Instr::Mov(Operand::reg(Reg::Rdi), src));
+                Operand::reg(Reg::Rdi)
+            }
+            src => src,
+        }
+    }
+
+    // mov that goes through rax when both sides are in memory
+    fn move_to(&mut self, dst: Operand, src: Operand) {
+        if dst == src {
+            return;
+        }
+        let direct = dst.is_reg()
+            || match src {
+                Operand::Reg(_, _) => true,
+                Operand::Imm(n) => i32::try_from(n).is_ok(),
+                _ => false,
+            };
+        if direct {
+            self.emit(Instr::Mov(dst, src));
+        } else {
+            self.emit(Instr::Mov(Operand::reg(Reg::Rax), src));
+            self.emit(Instr::Mov(dst, Operand::reg(Reg::Rax)));
+        }
+    }
+}
+
+impl Backend for Generator {
+    type Reg = Reg;
+    type Output = AsmModule;
+
+    fn argument_registers(&self) -> &'static [Reg] {
+        &REGISTERS
+    }
+
+    fn caller_saved(&self) -> &'static [Reg] {
+        &CALLER_SAVED
+    }
+
+    fn callee_saved(&self) -> &'static [Reg] {
+        &CALLEE_SAVED
+    }
+
+    fn prologue(&mut self, frame: &Frame<Reg>, params: &[Value]) {
+        self.module.directive(Directive::Globl(frame.name.clone()));
+        self.module.label(&frame.name);
+        self.emit(Instr::Push(Operand::reg(Reg::Rbp)));
+        self.emit(Instr::Mov(Operand::reg(Reg::Rbp), Operand::reg(Reg::Rsp)));
+        for reg in frame.alloc.callee_saved.iter() {
+            self.emit(Instr::Push(Operand::reg(*reg)));
+        }
+        if size(frame) > 0 {
+            self.emit(Instr::Sub(
+                Operand::reg(Reg::Rsp),
+                Operand::imm(size(frame)),
+            ));
+        }
+        for (param, reg) in params.iter().zip(REGISTERS.iter()) {
+            self.move_to(location(frame, *param), Operand::reg(*reg));
+        }
+    }
+
+    fn epilogue(&mut self, frame: &Frame<Reg>) {
+        if saved(frame) > 0 {
+            self.emit(Instr::Lea(
+                Operand::reg(Reg::Rsp),
+                Operand::mem(Reg::Rbp, -saved(frame)),
+            ));
+        } else {
+            self.emit(Instr::Mov(Operand::reg(Reg::Rsp), Operand::reg(Reg::Rbp)));
+        }
+        for reg in frame.alloc.callee_saved.iter().rev() {
+            self.emit(Instr::Pop(Operand::reg(*reg)));
+        }
+        self.emit(Instr::Pop(Operand::reg(Reg::Rbp)));
+    }
+
+    fn label(&mut self, label: &str) {
+        self.module.label(label);
+    }
+
+    fn load(&mut self, frame: &Frame<Reg>, dst: Value, src: Slot) {
+        self.move_to(location(frame, dst), slot(frame, src));
+    }
+
+    fn store(&mut self, frame: &Frame<Reg>, dst: Slot, src: &instr::Operand) {
+        self.move_to(slot(frame, dst), operand(frame, src));
+    }
+
+    fn copy(&mut self, frame: &Frame<Reg>, dst: Value, src: &instr::Operand) {
+        self.move_to(location(frame, dst), operand(frame, src));
+    }
+
+    fn extend(
+        &mut self,
+        frame: &Frame<Reg>,
+        dst: Value,
+        src: &instr::Operand,
+        bits: u32,
+        signed: bool,
+    ) {
+        self.move_to(Operand::reg(Reg::Rax), operand(frame, src));
+        let rax = Operand::reg(Reg::Rax);
+        match (bits, signed) {
+            (8, true) => self.emit(Instr::Movsx(rax, Operand::reg8(Reg::Rax))),
+            (8, false) => self.emit(Instr::Movzx(rax, Operand::reg8(Reg::Rax))),
+            (32, true) => self.emit(Instr::Movsx(rax, Operand::reg32(Reg::Rax))),
+            (32, false) => self.emit(Instr::Mov(
+                Operand::reg32(Reg::Rax),
+                Operand::reg32(Reg::Rax),
+            )),
+            _ => {}
+        }
+        self.move_to(location(frame, dst), Operand::reg(Reg::Rax));
+    }
+
+    fn binary(
+        &mut self,
+        frame: &Frame<Reg>,
+        op: BinOp,
+        dst: Value,
+        lhs: &instr::Operand,
+        rhs: &instr::Operand,
+    ) {
+        if let BinOp::Div | BinOp::UDiv = op {
+            self.move_to(Operand::reg(Reg::Rax), operand(frame, lhs));
+            let divisor = match operand(frame, rhs) {
+                imm @ Operand::Imm(_) => {
+                    self.move_to(Operand::reg(Reg::Rdi), imm);
+                    Operand::reg(Reg::Rdi)
+                }
+                divisor => divisor,
+            };
+            if op == BinOp::UDiv {
+                self.emit(Instr::Mov(Operand::reg(Reg::Rdx), Operand::imm(0)));
+                self.emit(Instr::Div(divisor));
+            } else {
+                self.emit(Instr::Cqo);
+                self.emit(Instr::Idiv(divisor));
+            }
+            self.move_to(location(frame, dst), Operand::reg(Reg::Rax));
+            return;
+        }
+        let d = location(frame, dst);
+        let rhs = self.source(operand(frame, rhs));
+        let acc = if d.is_reg() && d != rhs {
+            d.clone()
+        } else {
+            Operand::reg(Reg::Rax)
+        };
+        self.move_to(acc.clone(), operand(frame, lhs));
+        match op {
+            BinOp::Add => self.emit(Instr::Add(acc.clone(), rhs)),
+            BinOp::Sub => self.emit(Instr::Sub(acc.clone(), rhs)),
+            BinOp::Mul => self.emit(Instr::Imul(acc.clone(), rhs)),
+            BinOp::Div | BinOp::UDiv => unreachable!(),
+        }
+        self.move_to(d, acc);
+    }
+
+    fn compare(
+        &mut self,
+        frame: &Frame<Reg>,
+        op: CmpOp,
+        dst: Value,
+        lhs: &instr::Operand,
+        rhs: &instr::Operand,
+    ) {
+        self.compare_operands(frame, lhs, rhs);
+        self.emit(Instr::Set(cond(op), Operand::reg8(Reg::Rax)));
+        self.emit(Instr::Movzx(
+            Operand::reg(Reg::Rax),
+            Operand::reg8(Reg::Rax),
+        ));
+        self.move_to(location(frame, dst), Operand::reg(Reg::Rax));
+    }
+
+    fn compare_and_branch(
+        &mut self,
+        frame: &Frame<Reg>,
+        op: CmpOp,
+        lhs: &instr::Operand,
+        rhs: &instr::Operand,
+        target: &str,
+    ) {
+        self.compare_operands(frame, lhs, rhs);
+        self.emit(Instr::Jcc(cond(op), target.to_string()));
+    }
+
+    fn jump(&mut self, target: &str) {
+        self.emit(Instr::Jmp(target.to_string()));
+    }
+
+    fn call(&mut self, frame: &Frame<Reg>, dst: Value, func: &str, args: &[instr::Operand]) {
+        self.arguments(frame, args);
+        self.emit(Instr::Call(func.to_string()));
+        self.move_to(location(frame, dst), Operand::reg(Reg::Rax));
+    }
+
+    fn ret(&mut self, frame: &Frame<Reg>, value: &instr::Operand) {
+        self.move_to(Operand::reg(Reg::Rax), operand(frame, value));
+        self.epilogue(frame);
+        self.emit(Instr::Ret);
+    }
+
+    fn tail_call(&mut self, frame: &Frame<Reg>, func: &str, args: &[instr::Operand]) {
+        self.arguments(frame, args);
+        self.epilogue(frame);
+        self.emit(Instr::Jmp(func.to_string()));
+    }
+
+    fn data(&mut self, name: &str, values: &[i64]) {
+        self.module.directive(Directive::Data);
+        self.module.directive(Directive::Globl(name.to_string()));
+        self.module.label(name);
+        for value in values.iter() {
+            self.module.directive(Directive::Quad(*value));
+        }
+        self.module.directive(Directive::Text);
+    }
+
+    fn finish(&mut self) -> AsmModule {
+        std::mem::take(&mut self.module)
+    }
+}
+
+fn cond(op: CmpOp) -> Cond {
+    match op {
+        CmpOp::Eq => Cond::E,
+        CmpOp::Ne => Cond::Ne,
+        CmpOp::Lt => Cond::L,
+        CmpOp::Le => Cond::Le,
+        CmpOp::Gt => Cond::G,
+        CmpOp::Ge => Cond::Ge,
+        CmpOp::Ult => Cond::B,
+        CmpOp::Ule => Cond::Be,
+        CmpOp::Ugt => Cond::A,
+        CmpOp::Uge => Cond::Ae,
+    }
+}
@@ -0,0 +1,33 @@
+use std::{env, fs, path::PathBuf};
+
+use parser::{ast::Node, parser::Parser};
+use tokenizer::Tokenizer;
+
+/// Compare what `generate` makes of the checked tree of `golden/<name>.c` with
+/// `golden/<name>.<ext>`, both under `manifest_dir`.
+///
+/// `UPDATE_GOLDEN=1` rewrites the golden file with the output instead, to be
+/// reviewed in the diff.
+pub fn check(
+    manifest_dir: &str,
+    name: &str,
+    ext: &str,
+    generate: impl FnOnce(Vec<Node>) -> String,
+) {
+    let dir = PathBuf::from(manifest_dir).join("golden");
+    let src = fs::read_to_string(dir.join(format!("{name}.c"))).unwrap();
+    let out = generate(checked(&src));
+    let golden = dir.join(format!("{name}.{ext}"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &out).unwrap();
+    }
+    assert_eq!(fs::read_to_string(golden).unwrap(), out);
+}
+
+/// The checked tree of `src`, which the generators take.
+pub fn checked(src: &str) -> Vec<Node> {
+    let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+    let mut parser = Parser::with_locations(tokens);
+    parser.parse().unwrap();
+    sema::types::check(parser.nodes).unwrap()
+}
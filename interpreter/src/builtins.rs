@@ -0,0 +1,28 @@
+use std::io::Write;
+
+use crate::{error::Error, Interpreter, Stop};
+
+impl<W: Write> Interpreter<W> {
+    // the libc functions a program can call
+    pub(crate) fn builtin(&mut self, name: &str, args: &[u64]) -> Result<u64, Stop> {
+        let arity = match name {
+            "putchar" | "malloc" | "free" | "exit" => 1,
+            _ => return Err(Error::UndefinedFunction(name.to_string()).into()),
+        };
+        if args.len() != arity {
+            return Err(Error::ArgumentCount(name.to_string(), arity, args.len()).into());
+        }
+        let value = match name {
+            "putchar" => {
+                self.out.write_all(&[args[0] as u8]).map_err(Error::from)?;
+                args[0] as u8 as u64
+            }
+            "malloc" => self.memory.malloc(args[0]),
+            // freed memory is never reused
+            "free" => 0,
+            "exit" => return Err(Stop::Exit(args[0])),
+            _ => unreachable!(),
+        };
+        Ok(value)
+    }
+}
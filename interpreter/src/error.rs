@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid node")]
+    InvalidNode,
+    #[error("left value of assignment must be identifier")]
+    LeftValueMustBeIdentifier,
+    #[error("call to undefined function '{0}'")]
+    UndefinedFunction(String),
+    #[error("'{0}' takes {1} arguments, but {2} were given")]
+    ArgumentCount(String, usize, usize),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("invalid memory access at {0:#x}")]
+    InvalidAddress(u64),
+    #[error("stack overflow")]
+    StackOverflow,
+    #[error("failed to write output: {0}")]
+    Io(#[from] std::io::Error),
+}
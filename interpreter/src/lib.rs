@@ -0,0 +1,293 @@
+use std::{io::Write, rc::Rc};
+
+pub use error::Error;
+pub use memory::Memory;
+use parser::ast::{Node, NodeKind};
+
+mod builtins;
+mod error;
+mod memory;
+
+// deeper recursion of main reports a stack overflow before the host stack runs out
+const MAX_CALL_DEPTH: usize = 256;
+
+// what stops the statements being executed
+#[derive(Debug)]
+enum Stop {
+    Return(u64),
+    Exit(u64),
+    Error(Error),
+}
+
+impl From<Error> for Stop {
+    fn from(e: Error) -> Self {
+        Stop::Error(e)
+    }
+}
+
+/// Executes a checked program directly from its syntax tree.
+///
+/// Values are kept in 64 bits like in compiled code. Variables live in a
+/// frame on the simulated stack at their offset below the frame end, so every
+/// access goes through memory like the code of `-O0` does.
+///
+/// Of the C library it provides `putchar`, `malloc`, `free` and `exit`.
+/// There is no `printf`, as without string literals a program has no format
+/// string to pass it.
+#[derive(Debug)]
+pub struct Interpreter<W> {
+    memory: Memory,
+    out: W,
+    program: Rc<[Node]>,
+    frame_size: u64,
+    // end of the frame of the running call
+    bp: u64,
+    depth: usize,
+}
+
+impl<W: Write> Interpreter<W> {
+    /// Interpreter writing the output of the program to `out`.
+    pub fn new(out: W) -> Self {
+        Self {
+            memory: Memory::new(),
+            out,
+            program: Rc::from([]),
+            frame_size: 0,
+            bp: 0,
+            depth: 0,
+        }
+    }
+
+    /// Run the program as the body of `main` and return its value, the value of
+    /// the last statement when it does not return, see `ir::lower::lower`, or
+    /// the status passed to `exit`.
+    pub fn run(&mut self, nodes: &[Node]) -> Result<u64, Error> {
+        self.program = Rc::from(nodes);
+        let offset = nodes.iter().map(max_offset).max().unwrap_or(0);
+        self.frame_size = (offset as u64).div_ceil(16) * 16;
+        let result = match self.main() {
+            Ok(value) => Ok(value),
+            Err(Stop::Exit(status)) => Ok(status),
+            Err(Stop::Error(e)) => Err(e),
+            Err(Stop::Return(_)) => unreachable!("main catches its return"),
+        };
+        self.out.flush()?;
+        result
+    }
+
+    fn main(&mut self) -> Result<u64, Stop> {
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(Error::StackOverflow.into());
+        }
+        let caller = self.bp;
+        self.bp = self.memory.push_frame(self.frame_size)?;
+        self.depth += 1;
+
+        let program = Rc::clone(&self.program);
+        let mut result = Ok(0);
+        for node in program.iter() {
+            match self.stmt(node) {
+                Ok(value) => result = Ok(value.unwrap_or(0)),
+                Err(Stop::Return(value)) => {
+                    result = Ok(value);
+                    break;
+                }
+                Err(stop) => {
+                    result = Err(stop);
+                    break;
+                }
+            }
+        }
+
+        self.depth -= 1;
+        self.memory.pop_frame(self.frame_size);
+        self.bp = caller;
+        result
+    }
+
+    // execute a statement, returning the value of expression statements
+    fn stmt(&mut self, node: &Node) -> Result<Option<u64>, Stop> {
+        match &node.kind {
+            NodeKind::Return => {
+                let value = self.expr(child(&node.lhs)?)?;
+                return Err(Stop::Return(value));
+            }
+            NodeKind::If => {
+                let cond = self.expr(child(&node.lhs)?)?;
+                let body = child(&node.rhs)?;
+                if body.kind == NodeKind::Else {
+                    let branch = if cond != 0 { &body.lhs } else { &body.rhs };
+                    if let Some(branch) = branch {
+                        self.stmt(branch)?;
+                    }
+                } else if cond != 0 {
+                    self.stmt(body)?;
+                }
+            }
+            NodeKind::While => self.run_loop(node.lhs.as_deref(), child(&node.rhs)?)?,
+            NodeKind::For => {
+                let parts = node.for_parts().ok_or(Error::InvalidNode)?;
+                if let Some(init) = parts.init {
+                    self.stmt(init)?;
+                }
+                self.run_loop(parts.cond, parts.iteration)?;
+            }
+            NodeKind::Block(nodes) => {
+                let mut last = None;
+                for node in nodes.iter() {
+                    last = self.stmt(node)?;
+                }
+                return Ok(last);
+            }
+            NodeKind::Prototype(_, _) => {}
+            _ => return self.expr(node).map(Some),
+        }
+        Ok(None)
+    }
+
+    fn run_loop(&mut self, cond: Option<&Node>, body: &Node) -> Result<(), Stop> {
+        while match cond {
+            Some(cond) => self.expr(cond)? != 0,
+            None => true,
+        } {
+            self.stmt(body)?;
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, node: &Node) -> Result<u64, Stop> {
+        let value = match &node.kind {
+            NodeKind::Num(n) => *n,
+            NodeKind::LocalVar(_, offset) => self.memory.load(self.bp - *offset as u64)?,
+            NodeKind::Assignment => {
+                let Some(NodeKind::LocalVar(_, offset)) = node.lhs.as_ref().map(|n| &n.kind) else {
+                    return Err(Error::LeftValueMustBeIdentifier.into());
+                };
+                let value = self.expr(child(&node.rhs)?)?;
+                self.memory.store(self.bp - *offset as u64, value)?;
+                value
+            }
+            NodeKind::Cast => {
+                let value = self.expr(child(&node.lhs)?)?;
+                node.ty.map_or(value, |ty| ty.convert(value))
+            }
+            NodeKind::Func(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if name == "main" {
+                    return self.main();
+                }
+                self.builtin(name, &args)?
+            }
+            kind => {
+                let lhs = self.expr(child(&node.lhs)?)?;
+                let rhs = self.expr(child(&node.rhs)?)?;
+                // comparisons are unsigned when their operands are
+                let unsigned = node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned());
+                let (l, r) = (lhs as i64, rhs as i64);
+                match kind {
+                    NodeKind::Add => lhs.wrapping_add(rhs),
+                    NodeKind::Sub => lhs.wrapping_sub(rhs),
+                    NodeKind::Mul => lhs.wrapping_mul(rhs),
+                    NodeKind::Div if rhs == 0 => return Err(Error::DivisionByZero.into()),
+                    NodeKind::Div if node.is_unsigned() => lhs / rhs,
+                    NodeKind::Div => l.wrapping_div(r) as u64,
+                    NodeKind::Equal => (lhs == rhs) as u64,
+                    NodeKind::NotEqual => (lhs != rhs) as u64,
+                    NodeKind::LessThan if unsigned => (lhs < rhs) as u64,
+                    NodeKind::LessThan => (l < r) as u64,
+                    NodeKind::LessThanOrEqual if unsigned => (lhs <= rhs) as u64,
+                    NodeKind::LessThanOrEqual => (l <= r) as u64,
+                    NodeKind::GreaterThan if unsigned => (lhs > rhs) as u64,
+                    NodeKind::GreaterThan => (l > r) as u64,
+                    NodeKind::GreaterThanOrEqual if unsigned => (lhs >= rhs) as u64,
+                    NodeKind::GreaterThanOrEqual => (l >= r) as u64,
+                    _ => return Err(Error::InvalidNode.into()),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+// the frame has to hold the variable furthest from its end
+fn max_offset(node: &Node) -> u32 {
+    let own = match &node.kind {
+        NodeKind::LocalVar(_, offset) => *offset,
+        NodeKind::Block(nodes) | NodeKind::Func(_, nodes) => {
+            nodes.iter().map(max_offset).max().unwrap_or(0)
+        }
+        _ => 0,
+    };
+    [&node.lhs, &node.rhs]
+        .into_iter()
+        .flatten()
+        .map(|n| max_offset(n))
+        .fold(own, u32::max)
+}
+
+fn child(child: &Option<Box<Node>>) -> Result<&Node, Error> {
+    child.as_deref().ok_or(Error::InvalidNode)
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::{Error, Interpreter};
+
+    fn run(src: &str) -> (Result<u64, Error>, String) {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        let nodes = sema::types::check(parser.nodes).unwrap();
+        let mut out = Vec::new();
+        let result = Interpreter::new(&mut out).run(&nodes);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("return 42;", 42),
+        case("5+6*7;", 47),
+        case("a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);", 14),
+        case("a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;", 55),
+        case("b = 0; for (a = 0; a < 10; a = a + 1) b = b + a; return b;", 45),
+        case("a = 5; if (a > 3) return a * 2; else return 0 - 1;", 10),
+        case("a = 1; if (a > 3) 2; else { 3; 4; }", 0),
+        case("{ 3; 4; }", 4),
+        case("0 - 7 / 2;", (-3i64) as u64),
+        case("unsigned a = 0; return a - 1 > 5;", 1),
+        case("char c = 200; return c < 0;", 1),
+        case("int d = 0 - 7; unsigned u = d; return u / 65536 / 1000 + 1;", 66),
+        case("unsigned long a = 0 - 1; return a / 2 / 72057594037927936;", 127),
+        case("exit(3); return 4;", 3)
+    )]
+    fn test_run(input: &str, expect: u64) {
+        assert_eq!(expect, run(input).0.unwrap());
+    }
+
+    #[test]
+    fn test_run_output() {
+        let (result, out) = run("for (a = 0; a < 3; a = a + 1) putchar(97); putchar(10);");
+        assert_eq!(10, result.unwrap());
+        assert_eq!("aaa\n", out);
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("1 / 0;", "division by zero"),
+        case("foo(1);", "call to undefined function 'foo'"),
+        case("putchar(1, 2);", "'putchar' takes 1 arguments, but 2 were given"),
+        case("main();", "stack overflow")
+    )]
+    fn test_run_error(input: &str, expect: &str) {
+        assert_eq!(expect, run(input).0.unwrap_err().to_string());
+    }
+}
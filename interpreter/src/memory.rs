@@ -0,0 +1,118 @@
+use crate::error::Error;
+
+/// Bytes of simulated memory.
+pub const MEMORY_SIZE: u64 = 1 << 20;
+
+/// Lowest valid address, anything below it is treated like a null pointer.
+pub const HEAP_START: u64 = 0x1000;
+
+/// Memory of the program, the heap grows up from `HEAP_START` and the stack
+/// grows down from the end.
+#[derive(Debug)]
+pub struct Memory {
+    bytes: Vec<u8>,
+    // end of the heap, malloc never reuses what is freed
+    brk: u64,
+    sp: u64,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self {
+            bytes: vec![0; MEMORY_SIZE as usize],
+            brk: HEAP_START,
+            sp: MEMORY_SIZE,
+        }
+    }
+
+    /// Load the 64 bit little endian value at the address.
+    pub fn load(&self, addr: u64) -> Result<u64, Error> {
+        let range = self.range(addr, 8)?;
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.bytes[range]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn store(&mut self, addr: u64, value: u64) -> Result<(), Error> {
+        let range = self.range(addr, 8)?;
+        self.bytes[range].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /// Allocate 16 byte aligned bytes on the heap, 0 when they do not fit.
+    pub fn malloc(&mut self, size: u64) -> u64 {
+        let addr = self.brk;
+        match size
+            .checked_add(15)
+            .and_then(|size| addr.checked_add(size / 16 * 16))
+            .filter(|end| *end <= self.sp)
+        {
+            Some(end) => {
+                self.brk = end;
+                addr
+            }
+            None => 0,
+        }
+    }
+
+    /// Allocate a frame of `size` bytes on the stack, returning its end.
+    pub fn push_frame(&mut self, size: u64) -> Result<u64, Error> {
+        let bp = self.sp;
+        match self.sp.checked_sub(size).filter(|sp| *sp >= self.brk) {
+            Some(sp) => self.sp = sp,
+            None => return Err(Error::StackOverflow),
+        }
+        Ok(bp)
+    }
+
+    pub fn pop_frame(&mut self, size: u64) {
+        self.sp += size;
+    }
+
+    fn range(&self, addr: u64, size: u64) -> Result<std::ops::Range<usize>, Error> {
+        match addr.checked_add(size) {
+            Some(end) if addr >= HEAP_START && end <= MEMORY_SIZE => {
+                Ok(addr as usize..end as usize)
+            }
+            _ => Err(Error::InvalidAddress(addr)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Memory, HEAP_START, MEMORY_SIZE};
+    use crate::error::Error;
+
+    #[test]
+    fn test_memory_load_store() {
+        let mut memory = Memory::new();
+        let p = memory.malloc(10);
+        let q = memory.malloc(1);
+        assert_eq!(HEAP_START, p);
+        assert_eq!(HEAP_START + 16, q);
+        memory.store(p, 0x0102030405060708).unwrap();
+        assert_eq!(0x0102030405060708, memory.load(p).unwrap());
+        // little endian, the byte after the value is still 0
+        assert_eq!(0x01020304050607, memory.load(p + 1).unwrap());
+        assert!(matches!(memory.load(0), Err(Error::InvalidAddress(0))));
+        assert!(memory.load(MEMORY_SIZE - 4).is_err());
+    }
+
+    #[test]
+    fn test_memory_exhausted() {
+        let mut memory = Memory::new();
+        assert_eq!(0, memory.malloc(MEMORY_SIZE));
+        assert_eq!(0, memory.malloc(u64::MAX));
+        let bp = memory.push_frame(MEMORY_SIZE - HEAP_START).unwrap();
+        assert_eq!(MEMORY_SIZE, bp);
+        assert_eq!(0, memory.malloc(1));
+        assert!(matches!(memory.push_frame(16), Err(Error::StackOverflow)));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{function::Function, instr::BlockId};
+
+/// Blocks reachable from the entry in reverse postorder.
+pub fn reverse_postorder(func: &Function) -> Vec<BlockId> {
+    let mut visited = vec![false; func.blocks.len()];
+    let mut order = Vec::new();
+    if func.blocks.is_empty() {
+        return order;
+    }
+    // iterative dfs keeping the index of the next successor to visit
+    let mut stack = vec![(BlockId(0), 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.pop() {
+        let succs = func.block(block).term.successors();
+        if let Some(succ) = succs.get(next) {
+            stack.push((block, next + 1));
+            if !visited[succ.0 as usize] {
+                visited[succ.0 as usize] = true;
+                stack.push((*succ, 0));
+            }
+        } else {
+            order.push(block);
+        }
+    }
+    order.reverse();
+    order
+}
+
+/// Dominator tree of a function.
+#[derive(Debug, Clone)]
+pub struct Dominators {
+    idom: Vec<Option<BlockId>>,
+}
+
+impl Dominators {
+    // "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and Kennedy
+    pub fn new(func: &Function) -> Self {
+        let rpo = reverse_postorder(func);
+        let mut index = vec![usize::MAX; func.blocks.len()];
+        for (i, block) in rpo.iter().enumerate() {
+            index[block.0 as usize] = i;
+        }
+        let preds = func.predecessors();
+        let mut idom: Vec<Option<BlockId>> = vec![None; func.blocks.len()];
+        if let Some(entry) = rpo.first() {
+            idom[entry.0 as usize] = Some(*entry);
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in rpo.iter().skip(1) {
+                let mut new_idom: Option<BlockId> = None;
+                for pred in preds[block.0 as usize].iter() {
+                    if idom[pred.0 as usize].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => *pred,
+                        Some(other) => intersect(&idom, &index, *pred, other),
+                    });
+                }
+                if new_idom.is_some() && idom[block.0 as usize] != new_idom {
+                    idom[block.0 as usize] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        Self { idom }
+    }
+
+    /// Immediate dominator, the entry is its own.
+    pub fn idom(&self, block: BlockId) -> Option<BlockId> {
+        self.idom[block.0 as usize]
+    }
+
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.idom(block).is_some()
+    }
+
+    /// Whether every path from the entry to `b` goes through `a`.
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        let mut block = b;
+        loop {
+            if block == a {
+                return true;
+            }
+            match self.idom(block) {
+                Some(idom) if idom != block => block = idom,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// A natural loop, the header and the blocks that reach a back edge to it
+/// without going through the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: BlockId,
+    /// sorted by id, the header included
+    pub blocks: Vec<BlockId>,
+}
+
+impl Loop {
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+}
+
+/// Loops of the reachable blocks, back edges to the same header form a single
+/// loop. Inner loops come before the loops containing them.
+pub fn natural_loops(func: &Function, doms: &Dominators) -> Vec<Loop> {
+    let preds = func.predecessors();
+    let mut loops: Vec<Loop> = Vec::new();
+    for block in func.blocks.iter().filter(|b| doms.is_reachable(b.id)) {
+        for header in block.term.successors() {
+            if !doms.dominates(header, block.id) {
+                continue;
+            }
+            let mut blocks = vec![header];
+            let mut stack = vec![block.id];
+            while let Some(b) = stack.pop() {
+                if blocks.contains(&b) || !doms.is_reachable(b) {
+                    continue;
+                }
+                blocks.push(b);
+                stack.extend(preds[b.0 as usize].iter());
+            }
+            match loops.iter_mut().find(|l| l.header == header) {
+                Some(l) => {
+                    blocks.retain(|b| !l.blocks.contains(b));
+                    l.blocks.extend(blocks);
+                    l.blocks.sort();
+                }
+                None => {
+                    blocks.sort();
+                    loops.push(Loop { header, blocks });
+                }
+            }
+        }
+    }
+    // a loop inside another has fewer blocks
+    loops.sort_by_key(|l| l.blocks.len());
+    loops
+}
+
+fn intersect(idom: &[Option<BlockId>], index: &[usize], a: BlockId, b: BlockId) -> BlockId {
+    let (mut a, mut b) = (a, b);
+    while a != b {
+        while index[a.0 as usize] > index[b.0 as usize] {
+            a = idom[a.0 as usize].unwrap_or(a);
+        }
+        while index[b.0 as usize] > index[a.0 as usize] {
+            b = idom[b.0 as usize].unwrap_or(b);
+        }
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{natural_loops, reverse_postorder, Dominators, Loop};
+    use crate::{
+        function::{Block, Function},
+        instr::{BlockId, Operand, Terminator},
+    };
+
+    // bb0 -> bb1 -> (bb2 | bb3) -> bb1, bb3 -> bb4, bb5 unreachable
+    fn function() -> Function {
+        let terms = vec![
+            Terminator::Jump(BlockId(1)),
+            Terminator::Branch {
+                cond: Operand::Const(1),
+                then: BlockId(2),
+                otherwise: BlockId(3),
+            },
+            Terminator::Jump(BlockId(1)),
+            Terminator::Jump(BlockId(4)),
+            Terminator::Return(Operand::Const(0)),
+            Terminator::Jump(BlockId(4)),
+        ];
+        let mut func = Function::new("f");
+        func.blocks = terms
+            .into_iter()
+            .enumerate()
+            .map(|(i, term)| Block {
+                id: BlockId(i as u32),
+                insts: vec![],
+                term,
+            })
+            .collect();
+        func
+    }
+
+    #[test]
+    fn test_reverse_postorder() {
+        let order = reverse_postorder(&function());
+        assert_eq!(
+            vec![BlockId(0), BlockId(1), BlockId(3), BlockId(4), BlockId(2)],
+            order
+        );
+    }
+
+    #[test]
+    fn test_dominators() {
+        let doms = Dominators::new(&function());
+        assert_eq!(Some(BlockId(0)), doms.idom(BlockId(1)));
+        assert_eq!(Some(BlockId(1)), doms.idom(BlockId(2)));
+        assert_eq!(Some(BlockId(3)), doms.idom(BlockId(4)));
+        assert!(doms.dominates(BlockId(1), BlockId(4)));
+        assert!(!doms.dominates(BlockId(2), BlockId(3)));
+        assert!(!doms.is_reachable(BlockId(5)));
+    }
+
+    #[test]
+    fn test_natural_loops() {
+        let mut func = function();
+        // bb4 -> bb0 makes a loop around the one of bb1 and bb2
+        func.blocks[4].term = Terminator::Branch {
+            cond: Operand::Const(1),
+            then: BlockId(0),
+            otherwise: BlockId(5),
+        };
+        func.blocks[5].term = Terminator::Return(Operand::Const(0));
+        let doms = Dominators::new(&func);
+        assert_eq!(
+            vec![
+                Loop {
+                    header: BlockId(1),
+                    blocks: vec![BlockId(1), BlockId(2)],
+                },
+                Loop {
+                    header: BlockId(0),
+                    blocks: vec![BlockId(0), BlockId(1), BlockId(2), BlockId(3), BlockId(4)],
+                },
+            ],
+            natural_loops(&func, &doms)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{
+    function::{Block, Function, Module},
+    instr::{BinOp, BlockId, CmpOp, Inst, Operand, Slot, Terminator, Type, Value},
+};
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Type::I1 => write!(f, "i1"),
+            Type::I64 => write!(f, "i64"),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl Display for Slot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl Display for BlockId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Operand::Value(v) => write!(f, "{v}"),
+            Operand::Const(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            BinOp::Add => write!(f, "add"),
+            BinOp::Sub => write!(f, "sub"),
+            BinOp::Mul => write!(f, "mul"),
+            BinOp::Div => write!(f, "div"),
+            BinOp::UDiv => write!(f, "udiv"),
+        }
+    }
+}
+
+impl Display for CmpOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            CmpOp::Eq => write!(f, "eq"),
+            CmpOp::Ne => write!(f, "ne"),
+            CmpOp::Lt => write!(f, "lt"),
+            CmpOp::Le => write!(f, "le"),
+            CmpOp::Gt => write!(f, "gt"),
+            CmpOp::Ge => write!(f, "ge"),
+            CmpOp::Ult => write!(f, "ult"),
+            CmpOp::Ule => write!(f, "ule"),
+            CmpOp::Ugt => write!(f, "ugt"),
+            CmpOp::Uge => write!(f, "uge"),
+        }
+    }
+}
+
+impl Display for Terminator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jmp {target}"),
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            } => write!(f, "br {cond}, {then}, {otherwise}"),
+            Terminator::Return(v) => write!(f, "ret {v}"),
+            Terminator::TailCall { func, args } => {
+                write!(f, "tail call {func}(")?;
+                write_args(args, f)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn write_args(args: &[Operand], f: &mut Formatter<'_>) -> Result {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{arg}")?;
+    }
+    Ok(())
+}
+
+impl Function {
+    fn fmt_inst(&self, inst: &Inst, f: &mut Formatter<'_>) -> Result {
+        if let Some(dst) = inst.dst() {
+            write!(f, "{dst}:{} = ", self.ty(dst))?;
+        }
+        match inst {
+            Inst::Binary { op, lhs, rhs, .. } => write!(f, "{op} {lhs}, {rhs}"),
+            Inst::Cmp { op, lhs, rhs, .. } => write!(f, "{op} {lhs}, {rhs}"),
+            Inst::Zext { src, .. } => write!(f, "zext {src}"),
+            Inst::Extend {
+                src, bits, signed, ..
+            } => {
+                let op = if *signed { "sext" } else { "zext" };
+                write!(f, "{op}.{bits} {src}")
+            }
+            Inst::Copy { src, .. } => write!(f, "copy {src}"),
+            Inst::Load { slot, .. } => write!(f, "load {slot}"),
+            Inst::Store { slot, src } => write!(f, "store {slot}, {src}"),
+            Inst::Call { func, args, .. } => {
+                write!(f, "call {func}(")?;
+                write_args(args, f)?;
+                write!(f, ")")
+            }
+        }
+    }
+
+    fn fmt_block(&self, block: &Block, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{}:", block.id)?;
+        for inst in block.insts.iter() {
+            write!(f, "  ")?;
+            self.fmt_inst(inst, f)?;
+            writeln!(f)?;
+        }
+        writeln!(f, "  {}", block.term)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "function {}(", self.name)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{param}:{}", self.ty(*param))?;
+        }
+        writeln!(f, ") {{")?;
+        for (i, name) in self.slots.iter().enumerate() {
+            writeln!(f, "  {} = slot {name}", Slot(i as u32))?;
+        }
+        for block in self.blocks.iter() {
+            self.fmt_block(block, f)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (i, func) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{func}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+use crate::instr::{BlockId, Operand, Slot, Type, Value};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid node: {0}")]
+    InvalidNode(String),
+    #[error("left value of assignment must be identifier")]
+    LeftValueMustBeIdentifier,
+    #[error("{0}: function has no blocks")]
+    NoBlocks(String),
+    #[error("{0}: {1} is not at index {2}")]
+    MisplacedBlock(String, BlockId, usize),
+    #[error("{0}: unknown block {1}")]
+    UnknownBlock(String, BlockId),
+    #[error("{0}: unknown slot {1}")]
+    UnknownSlot(String, Slot),
+    #[error("{0}: {1} is defined more than once")]
+    Redefined(String, Value),
+    #[error("{0}: {1} is used before it is defined")]
+    UseBeforeDef(String, Value),
+    #[error("{0}: expected {1}, but {2} has type {3}")]
+    TypeMismatch(String, Type, Operand, Type),
+}
@@ -0,0 +1,73 @@
+use crate::instr::{BlockId, Inst, Slot, Terminator, Type, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub id: BlockId,
+    pub insts: Vec<Inst>,
+    pub term: Terminator,
+}
+
+/// A function body as a control flow graph, the first block is the entry and
+/// every block id is its index in `blocks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Value>,
+    /// names of the local variables, indexed by slot
+    pub slots: Vec<String>,
+    /// types of the values, indexed by value
+    pub values: Vec<Type>,
+    pub blocks: Vec<Block>,
+}
+
+impl Function {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            params: Vec::new(),
+            slots: Vec::new(),
+            values: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn new_value(&mut self, ty: Type) -> Value {
+        self.values.push(ty);
+        Value(self.values.len() as u32 - 1)
+    }
+
+    pub fn new_slot(&mut self, name: &str) -> Slot {
+        self.slots.push(name.to_string());
+        Slot(self.slots.len() as u32 - 1)
+    }
+
+    pub fn ty(&self, value: Value) -> Type {
+        self.values[value.0 as usize]
+    }
+
+    pub fn block(&self, id: BlockId) -> &Block {
+        &self.blocks[id.0 as usize]
+    }
+
+    pub fn block_mut(&mut self, id: BlockId) -> &mut Block {
+        &mut self.blocks[id.0 as usize]
+    }
+
+    /// Predecessors of every block, indexed by block.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut preds = vec![Vec::new(); self.blocks.len()];
+        for block in self.blocks.iter() {
+            for succ in block.term.successors() {
+                if !preds[succ.0 as usize].contains(&block.id) {
+                    preds[succ.0 as usize].push(block.id);
+                }
+            }
+        }
+        preds
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Module {
+    pub functions: Vec<Function>,
+}
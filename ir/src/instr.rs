@@ -0,0 +1,239 @@
+/// Type of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// Result of a comparison, consumed by branches.
+    I1,
+    I64,
+}
+
+/// Virtual register, defined by exactly one instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+/// Stack slot holding a local variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Slot(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Value(Value),
+    Const(i64),
+}
+
+impl Operand {
+    pub fn value(&self) -> Option<Value> {
+        match self {
+            Operand::Value(v) => Some(*v),
+            Operand::Const(_) => None,
+        }
+    }
+}
+
+impl From<Value> for Operand {
+    fn from(value: Value) -> Self {
+        Operand::Value(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    UDiv,
+}
+
+/// Comparisons, signed apart from the `U` ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
+
+impl CmpOp {
+    /// The comparison with the operands swapped.
+    pub fn swap(&self) -> Self {
+        match self {
+            CmpOp::Eq => CmpOp::Eq,
+            CmpOp::Ne => CmpOp::Ne,
+            CmpOp::Lt => CmpOp::Gt,
+            CmpOp::Le => CmpOp::Ge,
+            CmpOp::Gt => CmpOp::Lt,
+            CmpOp::Ge => CmpOp::Le,
+            CmpOp::Ult => CmpOp::Ugt,
+            CmpOp::Ule => CmpOp::Uge,
+            CmpOp::Ugt => CmpOp::Ult,
+            CmpOp::Uge => CmpOp::Ule,
+        }
+    }
+
+    /// The comparison that holds exactly when this one does not.
+    pub fn negate(&self) -> Self {
+        match self {
+            CmpOp::Eq => CmpOp::Ne,
+            CmpOp::Ne => CmpOp::Eq,
+            CmpOp::Lt => CmpOp::Ge,
+            CmpOp::Le => CmpOp::Gt,
+            CmpOp::Gt => CmpOp::Le,
+            CmpOp::Ge => CmpOp::Lt,
+            CmpOp::Ult => CmpOp::Uge,
+            CmpOp::Ule => CmpOp::Ugt,
+            CmpOp::Ugt => CmpOp::Ule,
+            CmpOp::Uge => CmpOp::Ult,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inst {
+    /// dst:i64 = lhs op rhs
+    Binary {
+        dst: Value,
+        op: BinOp,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    /// dst:i1 = lhs op rhs
+    Cmp {
+        dst: Value,
+        op: CmpOp,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    /// dst:i64 = zero extended i1
+    Zext {
+        dst: Value,
+        src: Operand,
+    },
+    /// dst:i64 = the low `bits` of src, sign or zero extended
+    Extend {
+        dst: Value,
+        src: Operand,
+        bits: u32,
+        signed: bool,
+    },
+    Copy {
+        dst: Value,
+        src: Operand,
+    },
+    Load {
+        dst: Value,
+        slot: Slot,
+    },
+    Store {
+        slot: Slot,
+        src: Operand,
+    },
+    Call {
+        dst: Value,
+        func: String,
+        args: Vec<Operand>,
+    },
+}
+
+impl Inst {
+    pub fn dst(&self) -> Option<Value> {
+        match self {
+            Inst::Binary { dst, .. }
+            | Inst::Cmp { dst, .. }
+            | Inst::Zext { dst, .. }
+            | Inst::Extend { dst, .. }
+            | Inst::Copy { dst, .. }
+            | Inst::Load { dst, .. }
+            | Inst::Call { dst, .. } => Some(*dst),
+            Inst::Store { .. } => None,
+        }
+    }
+
+    pub fn operands(&self) -> Vec<Operand> {
+        match self {
+            Inst::Binary { lhs, rhs, .. } | Inst::Cmp { lhs, rhs, .. } => vec![*lhs, *rhs],
+            Inst::Zext { src, .. }
+            | Inst::Extend { src, .. }
+            | Inst::Copy { src, .. }
+            | Inst::Store { src, .. } => vec![*src],
+            Inst::Load { .. } => vec![],
+            Inst::Call { args, .. } => args.clone(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Inst::Binary { lhs, rhs, .. } | Inst::Cmp { lhs, rhs, .. } => vec![lhs, rhs],
+            Inst::Zext { src, .. }
+            | Inst::Extend { src, .. }
+            | Inst::Copy { src, .. }
+            | Inst::Store { src, .. } => vec![src],
+            Inst::Load { .. } => vec![],
+            Inst::Call { args, .. } => args.iter_mut().collect(),
+        }
+    }
+
+    /// Values read by the instruction.
+    pub fn uses(&self) -> Vec<Value> {
+        self.operands().iter().filter_map(Operand::value).collect()
+    }
+
+    /// Whether removing the instruction can change the behavior of the program.
+    pub fn has_side_effects(&self) -> bool {
+        matches!(self, Inst::Store { .. } | Inst::Call { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        cond: Operand,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Return(Operand),
+    /// Return the result of a call, reusing the frame of the caller.
+    TailCall {
+        func: String,
+        args: Vec<Operand>,
+    },
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Return(_) | Terminator::TailCall { .. } => vec![],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Terminator::Jump(_) => vec![],
+            Terminator::Branch { cond, .. } => vec![cond],
+            Terminator::Return(v) => vec![v],
+            Terminator::TailCall { args, .. } => args.iter_mut().collect(),
+        }
+    }
+
+    pub fn uses(&self) -> Vec<Value> {
+        match self {
+            Terminator::Jump(_) => vec![],
+            Terminator::Branch { cond, .. } => cond.value().into_iter().collect(),
+            Terminator::Return(v) => v.value().into_iter().collect(),
+            Terminator::TailCall { args, .. } => args.iter().filter_map(Operand::value).collect(),
+        }
+    }
+}
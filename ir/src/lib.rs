@@ -0,0 +1,13 @@
+pub mod cfg;
+mod display;
+mod error;
+pub mod function;
+pub mod instr;
+pub mod liveness;
+pub mod loops;
+pub mod lower;
+pub mod regalloc;
+pub mod tail;
+pub mod verify;
+
+pub use error::Error;
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+
+use crate::{function::Function, instr::Value};
+
+/// Values live at the entry and exit of every block, indexed by block.
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    pub live_in: Vec<HashSet<Value>>,
+    pub live_out: Vec<HashSet<Value>>,
+}
+
+impl Liveness {
+    pub fn new(func: &Function) -> Self {
+        let n = func.blocks.len();
+        // values used before being defined in the block and values defined in it
+        let mut uses = vec![HashSet::new(); n];
+        let mut defs = vec![HashSet::new(); n];
+        for block in func.blocks.iter() {
+            let i = block.id.0 as usize;
+            for inst in block.insts.iter() {
+                for v in inst.uses() {
+                    if !defs[i].contains(&v) {
+                        uses[i].insert(v);
+                    }
+                }
+                if let Some(dst) = inst.dst() {
+                    defs[i].insert(dst);
+                }
+            }
+            for v in block.term.uses() {
+                if !defs[i].contains(&v) {
+                    uses[i].insert(v);
+                }
+            }
+        }
+
+        let mut live_in: Vec<HashSet<Value>> = vec![HashSet::new(); n];
+        let mut live_out: Vec<HashSet<Value>> = vec![HashSet::new(); n];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in func.blocks.iter().rev() {
+                let i = block.id.0 as usize;
+                let mut out = HashSet::new();
+                for succ in block.term.successors() {
+                    out.extend(live_in[succ.0 as usize].iter().copied());
+                }
+                let mut inn: HashSet<Value> = out.difference(&defs[i]).copied().collect();
+                inn.extend(uses[i].iter().copied());
+                if inn != live_in[i] || out != live_out[i] {
+                    live_in[i] = inn;
+                    live_out[i] = out;
+                    changed = true;
+                }
+            }
+        }
+        Self { live_in, live_out }
+    }
+}
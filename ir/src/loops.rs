@@ -0,0 +1,436 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    cfg::{natural_loops, Dominators, Loop},
+    function::{Block, Function, Module},
+    instr::{BinOp, BlockId, Inst, Operand, Slot, Terminator, Type, Value},
+};
+
+/// Move loop invariant instructions into a preheader and replace
+/// multiplications of induction variables by a constant with a running sum.
+///
+/// Returns the number of hoisted and strength reduced instructions.
+pub fn optimize_loops(module: &mut Module) -> usize {
+    module.functions.iter_mut().map(optimize_function).sum()
+}
+
+fn optimize_function(func: &mut Function) -> usize {
+    let doms = Dominators::new(func);
+    let headers: Vec<BlockId> = natural_loops(func, &doms)
+        .iter()
+        .map(|l| l.header)
+        .collect();
+    let mut count = 0;
+    // inner loops first, so what they hoist can move further out
+    for header in headers {
+        // blocks only get appended, so the ids stay valid
+        let doms = Dominators::new(func);
+        let Some(l) = natural_loops(func, &doms)
+            .into_iter()
+            .find(|l| l.header == header)
+        else {
+            continue;
+        };
+        let Some(pre) = preheader(func, &l) else {
+            continue;
+        };
+        count += hoist(func, &l, pre);
+        count += reduce(func, &l, pre);
+    }
+    count
+}
+
+// the only block entering the loop from outside, added when missing
+fn preheader(func: &mut Function, l: &Loop) -> Option<BlockId> {
+    let preds = func.predecessors();
+    let outside: Vec<BlockId> = preds[l.header.0 as usize]
+        .iter()
+        .copied()
+        .filter(|p| !l.contains(*p))
+        .collect();
+    // the entry block has to stay first
+    if outside.is_empty() {
+        return None;
+    }
+    if let [pred] = outside[..] {
+        if func.block(pred).term == Terminator::Jump(l.header) {
+            return Some(pred);
+        }
+    }
+    let pre = BlockId(func.blocks.len() as u32);
+    for pred in outside {
+        retarget(&mut func.block_mut(pred).term, l.header, pre);
+    }
+    func.blocks.push(Block {
+        id: pre,
+        insts: Vec::new(),
+        term: Terminator::Jump(l.header),
+    });
+    Some(pre)
+}
+
+fn retarget(term: &mut Terminator, from: BlockId, to: BlockId) {
+    let retarget = |b: &mut BlockId| {
+        if *b == from {
+            *b = to;
+        }
+    };
+    match term {
+        Terminator::Jump(target) => retarget(target),
+        Terminator::Branch {
+            then, otherwise, ..
+        } => {
+            retarget(then);
+            retarget(otherwise);
+        }
+        Terminator::Return(_) | Terminator::TailCall { .. } => {}
+    }
+}
+
+fn stored_slots(func: &Function, l: &Loop) -> HashSet<Slot> {
+    l.blocks
+        .iter()
+        .flat_map(|b| func.block(*b).insts.iter())
+        .filter_map(|inst| match inst {
+            Inst::Store { slot, .. } => Some(*slot),
+            _ => None,
+        })
+        .collect()
+}
+
+// move instructions whose result is the same in every iteration to the
+// preheader, divisions stay as they could trap when the loop is not entered
+fn hoist(func: &mut Function, l: &Loop, pre: BlockId) -> usize {
+    let stored = stored_slots(func, l);
+    let mut defined: HashSet<Value> = l
+        .blocks
+        .iter()
+        .flat_map(|b| func.block(*b).insts.iter())
+        .filter_map(Inst::dst)
+        .collect();
+    let mut hoisted = Vec::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in l.blocks.iter() {
+            let block = func.block_mut(*b);
+            let mut i = 0;
+            while i < block.insts.len() {
+                let inst = &block.insts[i];
+                let invariant = match inst {
+                    Inst::Binary {
+                        op: BinOp::Div | BinOp::UDiv,
+                        ..
+                    } => false,
+                    Inst::Binary { .. }
+                    | Inst::Cmp { .. }
+                    | Inst::Zext { .. }
+                    | Inst::Extend { .. }
+                    | Inst::Copy { .. } => inst.uses().iter().all(|v| !defined.contains(v)),
+                    // slots never have their address taken, so only stores change them
+                    Inst::Load { slot, .. } => !stored.contains(slot),
+                    Inst::Store { .. } | Inst::Call { .. } => false,
+                };
+                if invariant {
+                    let inst = block.insts.remove(i);
+                    if let Some(dst) = inst.dst() {
+                        defined.remove(&dst);
+                    }
+                    hoisted.push(inst);
+                    changed = true;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+    let count = hoisted.len();
+    func.block_mut(pre).insts.extend(hoisted);
+    count
+}
+
+// where a value is defined in the loop
+fn definitions(func: &Function, l: &Loop) -> HashMap<Value, Inst> {
+    l.blocks
+        .iter()
+        .flat_map(|b| func.block(*b).insts.iter())
+        .filter_map(|inst| inst.dst().map(|dst| (dst, inst.clone())))
+        .collect()
+}
+
+// slots stored once in the loop with their own value plus a constant,
+// mapped to that constant
+fn induction_slots(func: &Function, l: &Loop) -> HashMap<Slot, i64> {
+    let defs = definitions(func, l);
+    let mut stores: HashMap<Slot, Vec<Operand>> = HashMap::new();
+    for inst in l.blocks.iter().flat_map(|b| func.block(*b).insts.iter()) {
+        if let Inst::Store { slot, src } = inst {
+            stores.entry(*slot).or_default().push(*src);
+        }
+    }
+    let loads = |v: &Operand, slot: Slot| match v {
+        Operand::Value(v) => matches!(defs.get(v), Some(Inst::Load { slot: s, .. }) if *s == slot),
+        Operand::Const(_) => false,
+    };
+    stores
+        .into_iter()
+        .filter_map(|(slot, srcs)| {
+            let [Operand::Value(src)] = srcs[..] else {
+                return None;
+            };
+            let step = match defs.get(&src)? {
+                Inst::Binary {
+                    op: BinOp::Add,
+                    lhs,
+                    rhs: Operand::Const(c),
+                    ..
+                }
+                | Inst::Binary {
+                    op: BinOp::Add,
+                    lhs: Operand::Const(c),
+                    rhs: lhs,
+                    ..
+                } if loads(lhs, slot) => *c,
+                Inst::Binary {
+                    op: BinOp::Sub,
+                    lhs,
+                    rhs: Operand::Const(c),
+                    ..
+                } if loads(lhs, slot) => c.wrapping_neg(),
+                _ => return None,
+            };
+            Some((slot, step))
+        })
+        .collect()
+}
+
+// replace `%m = mul (load i), k` with a slot holding i * k that is updated
+// right after the store to i
+fn reduce(func: &mut Function, l: &Loop, pre: BlockId) -> usize {
+    let steps = induction_slots(func, l);
+    let defs = definitions(func, l);
+    // (mul, load of the induction slot, induction slot, factor)
+    let mut muls = Vec::new();
+    for inst in defs.values() {
+        let Inst::Binary {
+            dst,
+            op: BinOp::Mul,
+            lhs,
+            rhs,
+        } = inst
+        else {
+            continue;
+        };
+        let ((Operand::Value(load), Operand::Const(k)) | (Operand::Const(k), Operand::Value(load))) =
+            (*lhs, *rhs)
+        else {
+            continue;
+        };
+        if let Some(Inst::Load { slot, .. }) = defs.get(&load) {
+            if steps.contains_key(slot) {
+                muls.push((*dst, load, *slot, k));
+            }
+        }
+    }
+    muls.sort_by_key(|(dst, ..)| *dst);
+
+    let mut scaled: HashMap<(Slot, i64), Slot> = HashMap::new();
+    for (mul, load, slot, k) in muls.iter().copied() {
+        let t = match scaled.get(&(slot, k)) {
+            Some(t) => *t,
+            None => {
+                let t = func.new_slot(&format!("{}*{k}", func.slots[slot.0 as usize]));
+                init_scaled(func, pre, slot, t, k);
+                update_scaled(func, l, slot, t, steps[&slot].wrapping_mul(k));
+                scaled.insert((slot, k), t);
+                t
+            }
+        };
+        let (b, i) = position(func, l, mul);
+        func.block_mut(b).insts.remove(i);
+        // the scaled slot is read where the induction slot was
+        let (b, i) = position(func, l, load);
+        let insts = &mut func.block_mut(b).insts;
+        insts.insert(i + 1, Inst::Load { dst: mul, slot: t });
+        if !is_used(func, load) {
+            func.block_mut(b).insts.remove(i);
+        }
+    }
+    muls.len()
+}
+
+fn is_used(func: &Function, value: Value) -> bool {
+    func.blocks.iter().any(|b| {
+        b.insts.iter().any(|inst| inst.uses().contains(&value)) || b.term.uses().contains(&value)
+    })
+}
+
+fn init_scaled(func: &mut Function, pre: BlockId, slot: Slot, t: Slot, k: i64) {
+    let value = func.new_value(Type::I64);
+    let product = func.new_value(Type::I64);
+    func.block_mut(pre).insts.extend([
+        Inst::Load { dst: value, slot },
+        Inst::Binary {
+            dst: product,
+            op: BinOp::Mul,
+            lhs: Operand::Value(value),
+            rhs: Operand::Const(k),
+        },
+        Inst::Store {
+            slot: t,
+            src: Operand::Value(product),
+        },
+    ]);
+}
+
+fn update_scaled(func: &mut Function, l: &Loop, slot: Slot, t: Slot, step: i64) {
+    let value = func.new_value(Type::I64);
+    let sum = func.new_value(Type::I64);
+    for b in l.blocks.iter() {
+        let insts = &mut func.blocks[b.0 as usize].insts;
+        let Some(i) = insts
+            .iter()
+            .position(|inst| matches!(inst, Inst::Store { slot: s, .. } if *s == slot))
+        else {
+            continue;
+        };
+        insts.splice(
+            i + 1..i + 1,
+            [
+                Inst::Load {
+                    dst: value,
+                    slot: t,
+                },
+                Inst::Binary {
+                    dst: sum,
+                    op: BinOp::Add,
+                    lhs: Operand::Value(value),
+                    rhs: Operand::Const(step),
+                },
+                Inst::Store {
+                    slot: t,
+                    src: Operand::Value(sum),
+                },
+            ],
+        );
+        return;
+    }
+}
+
+// block and index of the instruction defining `value` in the loop
+fn position(func: &Function, l: &Loop, value: Value) -> (BlockId, usize) {
+    l.blocks
+        .iter()
+        .find_map(|b| {
+            let i = func
+                .block(*b)
+                .insts
+                .iter()
+                .position(|inst| inst.dst() == Some(value))?;
+            Some((*b, i))
+        })
+        .expect("value defined in the loop")
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::optimize_loops;
+    use crate::{lower::lower, verify::verify};
+
+    #[rstest(
+        input,
+        expect,
+        case(
+            "b = 0; n = 3; for (i = 0; i < 10; i = i + 1) b = b + i * 4 + n * 2; return b;",
+            vec![
+                "function main() {",
+                "  $0 = slot b",
+                "  $1 = slot n",
+                "  $2 = slot i",
+                "  $3 = slot i*4",
+                "bb0:",
+                "  store $0, 0",
+                "  store $1, 3",
+                "  store $2, 0",
+                "  %6:i64 = load $1",
+                "  %7:i64 = mul %6, 2",
+                "  %12:i64 = load $2",
+                "  %13:i64 = mul %12, 4",
+                "  store $3, %13",
+                "  jmp bb1",
+                "bb1:",
+                "  %0:i64 = load $2",
+                "  %1:i1 = lt %0, 10",
+                "  br %1, bb2, bb3",
+                "bb2:",
+                "  %2:i64 = load $0",
+                "  %4:i64 = load $3",
+                "  %5:i64 = add %2, %4",
+                "  %8:i64 = add %5, %7",
+                "  store $0, %8",
+                "  %9:i64 = load $2",
+                "  %10:i64 = add %9, 1",
+                "  store $2, %10",
+                "  %14:i64 = load $3",
+                "  %15:i64 = add %14, 4",
+                "  store $3, %15",
+                "  jmp bb1",
+                "bb3:",
+                "  %11:i64 = load $0",
+                "  ret %11",
+                "bb4:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+        // i is stored twice so it is not an induction variable, the loop is
+        // entered from a branch so it gets a new preheader
+        case(
+            "a = 1; if (a) while (a < 9) { a = a * 3; a = a - 1; } return a;",
+            vec![
+                "function main() {",
+                "  $0 = slot a",
+                "bb0:",
+                "  store $0, 1",
+                "  %0:i64 = load $0",
+                "  %1:i1 = ne %0, 0",
+                "  br %1, bb1, bb2",
+                "bb1:",
+                "  jmp bb3",
+                "bb2:",
+                "  %8:i64 = load $0",
+                "  ret %8",
+                "bb3:",
+                "  %2:i64 = load $0",
+                "  %3:i1 = lt %2, 9",
+                "  br %3, bb4, bb5",
+                "bb4:",
+                "  %4:i64 = load $0",
+                "  %5:i64 = mul %4, 3",
+                "  store $0, %5",
+                "  %6:i64 = load $0",
+                "  %7:i64 = sub %6, 1",
+                "  store $0, %7",
+                "  jmp bb3",
+                "bb5:",
+                "  jmp bb2",
+                "bb6:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+    )]
+    fn test_optimize_loops(input: &str, expect: Vec<&str>) {
+        let tokens = Tokenizer::default().process(input.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let mut module = lower(&parser.nodes).unwrap();
+        optimize_loops(&mut module);
+        verify(&module).unwrap();
+        assert_eq!(expect, module.to_string().lines().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use parser::ast::{Node, NodeKind};
+
+use crate::{
+    error::Error,
+    function::{Block, Function, Module},
+    instr::{BinOp, BlockId, CmpOp, Inst, Operand, Slot, Terminator, Type},
+};
+
+/// Lower the top level statements of a program into the `main` function.
+///
+/// Falling off the end of `main` returns the value of the last statement when
+/// it is an expression or a block ending in one, and 0 otherwise, which every
+/// backend and the interpreter follow.
+pub fn lower(nodes: &[Node]) -> Result<Module, Error> {
+    let mut lowerer = Lowerer::new("main");
+    let mut last = Operand::Const(0);
+    for node in nodes.iter() {
+        last = lowerer.stmt(node)?.unwrap_or(Operand::Const(0));
+    }
+    lowerer.terminate(Terminator::Return(last));
+    Ok(Module {
+        functions: vec![lowerer.finish()],
+    })
+}
+
+#[derive(Debug)]
+struct Lowerer {
+    func: Function,
+    // instructions and terminator of the blocks being built
+    blocks: Vec<(Vec<Inst>, Option<Terminator>)>,
+    current: BlockId,
+    slots: HashMap<String, Slot>,
+}
+
+impl Lowerer {
+    fn new(name: &str) -> Self {
+        Self {
+            func: Function::new(name),
+            blocks: vec![(Vec::new(), None)],
+            current: BlockId(0),
+            slots: HashMap::new(),
+        }
+    }
+
+    fn finish(mut self) -> Function {
+        self.func.blocks = self
+            .blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (insts, term))| Block {
+                id: BlockId(i as u32),
+                insts,
+                // blocks are always terminated before switching away
+                term: term.unwrap_or(Terminator::Return(Operand::Const(0))),
+            })
+            .collect();
+        self.func
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push((Vec::new(), None));
+        BlockId(self.blocks.len() as u32 - 1)
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    fn push(&mut self, inst: Inst) {
+        self.blocks[self.current.0 as usize].0.push(inst);
+    }
+
+    fn terminate(&mut self, term: Terminator) {
+        let block = &mut self.blocks[self.current.0 as usize];
+        if block.1.is_none() {
+            block.1 = Some(term);
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> Slot {
+        if let Some(slot) = self.slots.get(name) {
+            return *slot;
+        }
+        let slot = self.func.new_slot(name);
+        self.slots.insert(name.to_string(), slot);
+        slot
+    }
+
+    // returns the value of expression statements
+    fn stmt(&mut self, node: &Node) -> Result<Option<Operand>, Error> {
+        match &node.kind {
+            NodeKind::Return => {
+                let value = self.expr(child(&node.lhs, node)?)?;
+                self.terminate(Terminator::Return(value));
+                // anything after the return lands in an unreachable block
+                let next = self.new_block();
+                self.switch_to(next);
+            }
+            NodeKind::If => {
+                let then = self.new_block();
+                let end = self.new_block();
+                let body = child(&node.rhs, node)?;
+                if body.kind == NodeKind::Else {
+                    let otherwise = self.new_block();
+                    self.branch(child(&node.lhs, node)?, then, otherwise)?;
+                    self.switch_to(then);
+                    if let Some(then) = &body.lhs {
+                        self.stmt(then)?;
+                    }
+                    self.terminate(Terminator::Jump(end));
+                    self.switch_to(otherwise);
+                    if let Some(otherwise) = &body.rhs {
+                        self.stmt(otherwise)?;
+                    }
+                } else {
+                    self.branch(child(&node.lhs, node)?, then, end)?;
+                    self.switch_to(then);
+                    self.stmt(body)?;
+                }
+                self.terminate(Terminator::Jump(end));
+                self.switch_to(end);
+            }
+            NodeKind::While => {
+                self.lower_loop(node.lhs.as_deref(), child(&node.rhs, node)?)?;
+            }
+            NodeKind::For => {
+                let parts = node
+                    .for_parts()
+                    .ok_or_else(|| Error::InvalidNode(node.kind.to_string()))?;
+                if let Some(init) = parts.init {
+                    self.stmt(init)?;
+                }
+                self.lower_loop(parts.cond, parts.iteration)?;
+            }
+            NodeKind::Block(nodes) => {
+                let mut last = None;
+                for node in nodes.iter() {
+                    last = self.stmt(node)?;
+                }
+                return Ok(last);
+            }
+            NodeKind::Prototype(_, _) => {}
+            _ => return self.expr(node).map(Some),
+        }
+        Ok(None)
+    }
+
+    // loop with the condition checked before the body, no condition loops forever
+    fn lower_loop(&mut self, cond: Option<&Node>, body: &Node) -> Result<(), Error> {
+        let header = self.new_block();
+        let body_block = self.new_block();
+        let end = self.new_block();
+        self.terminate(Terminator::Jump(header));
+        self.switch_to(header);
+        match cond {
+            Some(cond) => self.branch(cond, body_block, end)?,
+            None => self.terminate(Terminator::Jump(body_block)),
+        }
+        self.switch_to(body_block);
+        self.stmt(body)?;
+        self.terminate(Terminator::Jump(header));
+        self.switch_to(end);
+        Ok(())
+    }
+
+    // branch on the truth of an expression
+    fn branch(&mut self, cond: &Node, then: BlockId, otherwise: BlockId) -> Result<(), Error> {
+        let cond = match compare_op(cond) {
+            Some(op) => {
+                let lhs = self.expr(child(&cond.lhs, cond)?)?;
+                let rhs = self.expr(child(&cond.rhs, cond)?)?;
+                self.cmp(op, lhs, rhs)
+            }
+            None => {
+                let value = self.expr(cond)?;
+                self.cmp(CmpOp::Ne, value, Operand::Const(0))
+            }
+        };
+        self.terminate(Terminator::Branch {
+            cond,
+            then,
+            otherwise,
+        });
+        Ok(())
+    }
+
+    fn cmp(&mut self, op: CmpOp, lhs: Operand, rhs: Operand) -> Operand {
+        let dst = self.func.new_value(Type::I1);
+        self.push(Inst::Cmp { dst, op, lhs, rhs });
+        Operand::Value(dst)
+    }
+
+    fn expr(&mut self, node: &Node) -> Result<Operand, Error> {
+        match &node.kind {
+            NodeKind::Num(n) => Ok(Operand::Const(*n as i64)),
+            NodeKind::LocalVar(name, _) => {
+                let slot = self.slot(name);
+                let dst = self.func.new_value(Type::I64);
+                self.push(Inst::Load { dst, slot });
+                Ok(Operand::Value(dst))
+            }
+            NodeKind::Assignment => {
+                let Some(NodeKind::LocalVar(name, _)) = node.lhs.as_ref().map(|n| &n.kind) else {
+                    return Err(Error::LeftValueMustBeIdentifier);
+                };
+                let slot = self.slot(name);
+                let src = self.expr(child(&node.rhs, node)?)?;
+                self.push(Inst::Store { slot, src });
+                Ok(src)
+            }
+            NodeKind::Cast => {
+                let src = self.expr(child(&node.lhs, node)?)?;
+                // values are kept extended to 64 bits, so only narrower types need work
+                let Some(ty) = node.ty.filter(|ty| ty.is_integer() && ty.bits() < 64) else {
+                    return Ok(src);
+                };
+                let dst = self.func.new_value(Type::I64);
+                self.push(Inst::Extend {
+                    dst,
+                    src,
+                    bits: ty.bits(),
+                    signed: !ty.is_unsigned(),
+                });
+                Ok(Operand::Value(dst))
+            }
+            NodeKind::Func(func, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let dst = self.func.new_value(Type::I64);
+                self.push(Inst::Call {
+                    dst,
+                    func: func.clone(),
+                    args,
+                });
+                Ok(Operand::Value(dst))
+            }
+            kind => {
+                let lhs = self.expr(child(&node.lhs, node)?)?;
+                let rhs = self.expr(child(&node.rhs, node)?)?;
+                if let Some(op) = compare_op(node) {
+                    let cond = self.cmp(op, lhs, rhs);
+                    let dst = self.func.new_value(Type::I64);
+                    self.push(Inst::Zext { dst, src: cond });
+                    return Ok(Operand::Value(dst));
+                }
+                let op = match kind {
+                    NodeKind::Add => BinOp::Add,
+                    NodeKind::Sub => BinOp::Sub,
+                    NodeKind::Mul => BinOp::Mul,
+                    NodeKind::Div if node.is_unsigned() => BinOp::UDiv,
+                    NodeKind::Div => BinOp::Div,
+                    _ => return Err(Error::InvalidNode(kind.to_string())),
+                };
+                let dst = self.func.new_value(Type::I64);
+                self.push(Inst::Binary { dst, op, lhs, rhs });
+                Ok(Operand::Value(dst))
+            }
+        }
+    }
+}
+
+// comparison of the node, unsigned when its operands are
+fn compare_op(node: &Node) -> Option<CmpOp> {
+    let unsigned = node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned());
+    match (&node.kind, unsigned) {
+        (NodeKind::Equal, _) => Some(CmpOp::Eq),
+        (NodeKind::NotEqual, _) => Some(CmpOp::Ne),
+        (NodeKind::LessThan, false) => Some(CmpOp::Lt),
+        (NodeKind::LessThanOrEqual, false) => Some(CmpOp::Le),
+        (NodeKind::GreaterThan, false) => Some(CmpOp::Gt),
+        (NodeKind::GreaterThanOrEqual, false) => Some(CmpOp::Ge),
+        (NodeKind::LessThan, true) => Some(CmpOp::Ult),
+        (NodeKind::LessThanOrEqual, true) => Some(CmpOp::Ule),
+        (NodeKind::GreaterThan, true) => Some(CmpOp::Ugt),
+        (NodeKind::GreaterThanOrEqual, true) => Some(CmpOp::Uge),
+        _ => None,
+    }
+}
+
+fn child<'a>(child: &'a Option<Box<Node>>, parent: &Node) -> Result<&'a Node, Error> {
+    child
+        .as_deref()
+        .ok_or_else(|| Error::InvalidNode(parent.kind.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::lower;
+    use crate::verify::verify;
+
+    fn lower_src(src: &str) -> String {
+        let tokens = Tokenizer::default().process(src.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let module = lower(&parser.nodes).unwrap();
+        verify(&module).unwrap();
+        module.to_string()
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case(
+            "1 + 2 * 3;",
+            vec![
+                "function main() {",
+                "bb0:",
+                "  %0:i64 = mul 2, 3",
+                "  %1:i64 = add 1, %0",
+                "  ret %1",
+                "}",
+            ]
+        ),
+        case(
+            "a = 1; if (a == 1) return 10; else return a;",
+            vec![
+                "function main() {",
+                "  $0 = slot a",
+                "bb0:",
+                "  store $0, 1",
+                "  %0:i64 = load $0",
+                "  %1:i1 = eq %0, 1",
+                "  br %1, bb1, bb3",
+                "bb1:",
+                "  ret 10",
+                "bb2:",
+                "  ret 0",
+                "bb3:",
+                "  %2:i64 = load $0",
+                "  ret %2",
+                "bb4:",
+                "  jmp bb2",
+                "bb5:",
+                "  jmp bb2",
+                "}",
+            ]
+        ),
+        case(
+            "b = 0; for (a = 0; a < 10; a = a + 1) b = b + 1; return b;",
+            vec![
+                "function main() {",
+                "  $0 = slot b",
+                "  $1 = slot a",
+                "bb0:",
+                "  store $0, 0",
+                "  store $1, 0",
+                "  jmp bb1",
+                "bb1:",
+                "  %0:i64 = load $1",
+                "  %1:i1 = lt %0, 10",
+                "  br %1, bb2, bb3",
+                "bb2:",
+                "  %2:i64 = load $0",
+                "  %3:i64 = add %2, 1",
+                "  store $0, %3",
+                "  %4:i64 = load $1",
+                "  %5:i64 = add %4, 1",
+                "  store $1, %5",
+                "  jmp bb1",
+                "bb3:",
+                "  %6:i64 = load $0",
+                "  ret %6",
+                "bb4:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+        case(
+            "a = 0; while (a) a = foo(1, 2) < 3;",
+            vec![
+                "function main() {",
+                "  $0 = slot a",
+                "bb0:",
+                "  store $0, 0",
+                "  jmp bb1",
+                "bb1:",
+                "  %0:i64 = load $0",
+                "  %1:i1 = ne %0, 0",
+                "  br %1, bb2, bb3",
+                "bb2:",
+                "  %2:i64 = call foo(1, 2)",
+                "  %3:i1 = lt %2, 3",
+                "  %4:i64 = zext %3",
+                "  store $0, %4",
+                "  jmp bb1",
+                "bb3:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+    )]
+    fn test_lower(input: &str, expect: Vec<&str>) {
+        assert_eq!(expect, lower_src(input).lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_lower_typed() {
+        let src = "unsigned a = 7; char c = a; if (a < 9) return a / 2; return c;";
+        let tokens = Tokenizer::default().process(src.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let nodes = sema::types::check(parser.nodes).unwrap();
+        let module = lower(&nodes).unwrap();
+        verify(&module).unwrap();
+        let expect = vec![
+            "function main() {",
+            "  $0 = slot a",
+            "  $1 = slot c",
+            "bb0:",
+            "  store $0, 7",
+            "  %0:i64 = load $0",
+            "  %1:i64 = sext.8 %0",
+            "  store $1, %1",
+            "  %2:i64 = load $0",
+            "  %3:i1 = ult %2, 9",
+            "  br %3, bb1, bb2",
+            "bb1:",
+            "  %4:i64 = load $0",
+            "  %5:i64 = udiv %4, 2",
+            "  %6:i64 = zext.32 %5",
+            "  %7:i64 = sext.32 %6",
+            "  ret %7",
+            "bb2:",
+            "  %8:i64 = load $1",
+            "  %9:i64 = sext.32 %8",
+            "  ret %9",
+            "bb3:",
+            "  jmp bb2",
+            "bb4:",
+            "  ret 0",
+            "}",
+        ];
+        assert_eq!(expect, module.to_string().lines().collect::<Vec<_>>());
+    }
+}
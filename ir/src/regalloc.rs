@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use crate::{
+    function::Function,
+    instr::{Inst, Value},
+    liveness::Liveness,
+};
+
+/// Where a value lives during its whole lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location<R> {
+    Reg(R),
+    /// index of a spill slot in the frame
+    Stack(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Allocation<R> {
+    pub locations: HashMap<Value, Location<R>>,
+    pub spill_slots: u32,
+    /// callee saved registers that have to be preserved by the function
+    pub callee_saved: Vec<R>,
+}
+
+impl<R: Copy> Allocation<R> {
+    pub fn location(&self, value: Value) -> Location<R> {
+        // values without a location are never defined, any slot will do
+        self.locations
+            .get(&value)
+            .copied()
+            .unwrap_or(Location::Stack(0))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    value: Value,
+    start: usize,
+    end: usize,
+    crosses_call: bool,
+}
+
+/// Assign registers to values with linear scan over live intervals. Values
+/// live across a call only get callee saved registers, the rest prefer the
+/// caller saved ones, and values that do not fit are spilled to the stack.
+pub fn linear_scan<R: Copy + Eq>(
+    func: &Function,
+    caller_saved: &[R],
+    callee_saved: &[R],
+) -> Allocation<R> {
+    let intervals = intervals(func);
+
+    let mut locations = HashMap::new();
+    let mut spill_slots = 0;
+    let mut used_callee_saved: Vec<R> = Vec::new();
+    let mut active: Vec<(Interval, R)> = Vec::new();
+    let mut spill = |locations: &mut HashMap<Value, Location<R>>, value: Value| {
+        locations.insert(value, Location::Stack(spill_slots));
+        spill_slots += 1;
+    };
+
+    for interval in intervals.iter() {
+        // an interval ending where another starts keeps its register, the
+        // instruction reads its operands while writing the result
+        active.retain(|(a, _)| a.end >= interval.start);
+
+        let allowed = |r: &R| !interval.crosses_call || callee_saved.contains(r);
+        let free = caller_saved
+            .iter()
+            .chain(callee_saved.iter())
+            .filter(|r| allowed(r))
+            .find(|r| active.iter().all(|(_, a)| a != *r));
+        let reg = match free {
+            Some(reg) => Some(*reg),
+            None => {
+                // steal the register of the active interval ending last
+                let victim = active
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (a, r))| allowed(r) && a.end > interval.end)
+                    .max_by_key(|(_, (a, _))| a.end)
+                    .map(|(i, _)| i);
+                match victim {
+                    Some(i) => {
+                        let (victim, reg) = active.remove(i);
+                        spill(&mut locations, victim.value);
+                        Some(reg)
+                    }
+                    None => None,
+                }
+            }
+        };
+        match reg {
+            Some(reg) => {
+                if callee_saved.contains(&reg) && !used_callee_saved.contains(&reg) {
+                    used_callee_saved.push(reg);
+                }
+                locations.insert(interval.value, Location::Reg(reg));
+                active.push((*interval, reg));
+            }
+            None => spill(&mut locations, interval.value),
+        }
+    }
+
+    // keep the saved registers in the order they were given
+    let callee_saved = callee_saved
+        .iter()
+        .filter(|r| used_callee_saved.contains(r))
+        .copied()
+        .collect();
+    Allocation {
+        locations,
+        spill_slots,
+        callee_saved,
+    }
+}
+
+// live intervals over the instructions numbered in block order, sorted by start
+fn intervals(func: &Function) -> Vec<Interval> {
+    let liveness = Liveness::new(func);
+    let mut ranges: HashMap<Value, (usize, usize)> = HashMap::new();
+    let mut extend = |value: Value, pos: usize| {
+        let range = ranges.entry(value).or_insert((pos, pos));
+        range.0 = range.0.min(pos);
+        range.1 = range.1.max(pos);
+    };
+    for param in func.params.iter() {
+        extend(*param, 0);
+    }
+
+    let mut calls = Vec::new();
+    let mut pos = 0;
+    for block in func.blocks.iter() {
+        let i = block.id.0 as usize;
+        for v in liveness.live_in[i].iter() {
+            extend(*v, pos);
+        }
+        for inst in block.insts.iter() {
+            pos += 1;
+            for v in inst.uses() {
+                extend(v, pos);
+            }
+            if let Some(dst) = inst.dst() {
+                extend(dst, pos);
+            }
+            if let Inst::Call { .. } = inst {
+                calls.push(pos);
+            }
+        }
+        pos += 1;
+        for v in block.term.uses() {
+            extend(v, pos);
+        }
+        for v in liveness.live_out[i].iter() {
+            extend(*v, pos);
+        }
+    }
+
+    let mut intervals: Vec<Interval> = ranges
+        .into_iter()
+        .map(|(value, (start, end))| Interval {
+            value,
+            start,
+            end,
+            crosses_call: calls.iter().any(|c| start < *c && *c < end),
+        })
+        .collect();
+    intervals.sort_by_key(|i| (i.start, i.value));
+    intervals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{linear_scan, Location};
+    use crate::{
+        function::{Block, Function},
+        instr::{BinOp, BlockId, Inst, Operand, Terminator, Type, Value},
+    };
+
+    fn add(dst: u32, lhs: Operand, rhs: Operand) -> Inst {
+        Inst::Binary {
+            dst: Value(dst),
+            op: BinOp::Add,
+            lhs,
+            rhs,
+        }
+    }
+
+    // %0 = 1 + 1, %1 = call f(), %2 = %0 + %1, %3 = %2 + %0, ret %3
+    fn function() -> Function {
+        let mut func = Function::new("f");
+        func.values = vec![Type::I64; 4];
+        func.blocks = vec![Block {
+            id: BlockId(0),
+            insts: vec![
+                add(0, Operand::Const(1), Operand::Const(1)),
+                Inst::Call {
+                    dst: Value(1),
+                    func: "g".to_string(),
+                    args: vec![],
+                },
+                add(2, Operand::Value(Value(0)), Operand::Value(Value(1))),
+                add(3, Operand::Value(Value(2)), Operand::Value(Value(0))),
+            ],
+            term: Terminator::Return(Operand::Value(Value(3))),
+        }];
+        func
+    }
+
+    #[test]
+    fn test_linear_scan() {
+        let alloc = linear_scan(&function(), &["r0", "r1"], &["s0"]);
+        // %0 lives across the call
+        assert_eq!(Location::Reg("s0"), alloc.location(Value(0)));
+        assert_eq!(Location::Reg("r0"), alloc.location(Value(1)));
+        assert_eq!(Location::Reg("r1"), alloc.location(Value(2)));
+        // %1 is still read by the instruction defining %2 and keeps r0
+        assert_eq!(Location::Reg("r0"), alloc.location(Value(3)));
+        assert_eq!(vec!["s0"], alloc.callee_saved);
+        assert_eq!(0, alloc.spill_slots);
+    }
+
+    #[test]
+    fn test_linear_scan_spill() {
+        let alloc = linear_scan(&function(), &["r0"], &[]);
+        assert_eq!(Location::Stack(0), alloc.location(Value(0)));
+        assert_eq!(Location::Reg("r0"), alloc.location(Value(1)));
+        assert_eq!(Location::Stack(1), alloc.location(Value(2)));
+        assert_eq!(Location::Reg("r0"), alloc.location(Value(3)));
+        assert_eq!(2, alloc.spill_slots);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    function::Module,
+    instr::{Inst, Operand, Terminator},
+};
+
+/// Turn calls whose result is returned right away into tail calls, which tear
+/// down the frame and jump to the callee so that it returns straight to our
+/// caller. Calls with more than `max_args` arguments are kept, their arguments
+/// would not fit in registers and the stack area of the caller may be smaller.
+///
+/// Returns the number of calls turned into tail calls.
+pub fn tail_calls(module: &mut Module, max_args: usize) -> usize {
+    let mut count = 0;
+    for block in module
+        .functions
+        .iter_mut()
+        .flat_map(|f| f.blocks.iter_mut())
+    {
+        let Terminator::Return(Operand::Value(v)) = block.term else {
+            continue;
+        };
+        match block.insts.last() {
+            Some(Inst::Call { dst, args, .. }) if *dst == v && args.len() <= max_args => {}
+            _ => continue,
+        }
+        let Some(Inst::Call { func, args, .. }) = block.insts.pop() else {
+            unreachable!()
+        };
+        block.term = Terminator::TailCall { func, args };
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::tail_calls;
+    use crate::{lower::lower, verify::verify};
+
+    #[rstest(
+        input,
+        max_args,
+        expect,
+        case(
+            "a = 1; if (a) return foo(1, 2);",
+            6,
+            vec![
+                "function main() {",
+                "  $0 = slot a",
+                "bb0:",
+                "  store $0, 1",
+                "  %0:i64 = load $0",
+                "  %1:i1 = ne %0, 0",
+                "  br %1, bb1, bb2",
+                "bb1:",
+                "  tail call foo(1, 2)",
+                "bb2:",
+                "  ret 0",
+                "bb3:",
+                "  jmp bb2",
+                "}",
+            ]
+        ),
+        // the result is used after the call
+        case(
+            "return foo() + 1;",
+            6,
+            vec![
+                "function main() {",
+                "bb0:",
+                "  %0:i64 = call foo()",
+                "  %1:i64 = add %0, 1",
+                "  ret %1",
+                "bb1:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+        case(
+            "return foo(1, 2, 3);",
+            2,
+            vec![
+                "function main() {",
+                "bb0:",
+                "  %0:i64 = call foo(1, 2, 3)",
+                "  ret %0",
+                "bb1:",
+                "  ret 0",
+                "}",
+            ]
+        ),
+    )]
+    fn test_tail_calls(input: &str, max_args: usize, expect: Vec<&str>) {
+        let tokens = Tokenizer::default().process(input.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let mut module = lower(&parser.nodes).unwrap();
+        tail_calls(&mut module, max_args);
+        verify(&module).unwrap();
+        assert_eq!(expect, module.to_string().lines().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use crate::{
+    cfg::Dominators,
+    error::Error,
+    function::{Function, Module},
+    instr::{BlockId, Inst, Operand, Terminator, Type, Value},
+};
+
+/// Check that every function of the module is well formed.
+pub fn verify(module: &Module) -> Result<(), Error> {
+    module.functions.iter().try_for_each(verify_function)
+}
+
+/// Check the structure of the control flow graph, that values are defined
+/// once before they are used and that operands have the expected types.
+pub fn verify_function(func: &Function) -> Result<(), Error> {
+    let name = &func.name;
+    if func.blocks.is_empty() {
+        return Err(Error::NoBlocks(name.clone()));
+    }
+    for (i, block) in func.blocks.iter().enumerate() {
+        if block.id.0 as usize != i {
+            return Err(Error::MisplacedBlock(name.clone(), block.id, i));
+        }
+        for succ in block.term.successors() {
+            if succ.0 as usize >= func.blocks.len() {
+                return Err(Error::UnknownBlock(name.clone(), succ));
+            }
+        }
+    }
+
+    // where every value is defined, as (block, index) with parameters at -1
+    let mut defs: HashMap<Value, (BlockId, isize)> = HashMap::new();
+    let mut define = |value: Value, at: (BlockId, isize)| {
+        if value.0 as usize >= func.values.len() || defs.insert(value, at).is_some() {
+            return Err(Error::Redefined(name.clone(), value));
+        }
+        Ok(())
+    };
+    for param in func.params.iter() {
+        define(*param, (BlockId(0), -1))?;
+    }
+    for block in func.blocks.iter() {
+        for (i, inst) in block.insts.iter().enumerate() {
+            if let Some(dst) = inst.dst() {
+                define(dst, (block.id, i as isize))?;
+            }
+        }
+    }
+
+    let doms = Dominators::new(func);
+    let verifier = Verifier { func, defs, doms };
+    for block in func.blocks.iter() {
+        for (i, inst) in block.insts.iter().enumerate() {
+            verifier.inst(block.id, i as isize, inst)?;
+        }
+        verifier.term(block.id, block.insts.len() as isize, &block.term)?;
+    }
+    Ok(())
+}
+
+struct Verifier<'a> {
+    func: &'a Function,
+    defs: HashMap<Value, (BlockId, isize)>,
+    doms: Dominators,
+}
+
+impl Verifier<'_> {
+    fn inst(&self, block: BlockId, at: isize, inst: &Inst) -> Result<(), Error> {
+        match inst {
+            Inst::Binary { dst, lhs, rhs, .. } => {
+                self.operand(block, at, lhs, Type::I64)?;
+                self.operand(block, at, rhs, Type::I64)?;
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I64)
+            }
+            Inst::Cmp { dst, lhs, rhs, .. } => {
+                self.operand(block, at, lhs, Type::I64)?;
+                self.operand(block, at, rhs, Type::I64)?;
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I1)
+            }
+            Inst::Zext { dst, src } => {
+                self.operand(block, at, src, Type::I1)?;
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I64)
+            }
+            Inst::Extend { dst, src, .. } => {
+                self.operand(block, at, src, Type::I64)?;
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I64)
+            }
+            Inst::Copy { dst, src } => {
+                let ty = self.func.ty(*dst);
+                self.operand(block, at, src, ty)
+            }
+            Inst::Load { dst, slot } => {
+                self.slot(*slot)?;
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I64)
+            }
+            Inst::Store { slot, src } => {
+                self.slot(*slot)?;
+                self.operand(block, at, src, Type::I64)
+            }
+            Inst::Call { dst, args, .. } => {
+                for arg in args.iter() {
+                    self.operand(block, at, arg, Type::I64)?;
+                }
+                self.operand(block, at + 1, &Operand::Value(*dst), Type::I64)
+            }
+        }
+    }
+
+    fn term(&self, block: BlockId, at: isize, term: &Terminator) -> Result<(), Error> {
+        match term {
+            Terminator::Jump(_) => Ok(()),
+            Terminator::Branch { cond, .. } => self.operand(block, at, cond, Type::I1),
+            Terminator::Return(v) => self.operand(block, at, v, Type::I64),
+            Terminator::TailCall { args, .. } => {
+                for arg in args.iter() {
+                    self.operand(block, at, arg, Type::I64)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn slot(&self, slot: crate::instr::Slot) -> Result<(), Error> {
+        if slot.0 as usize >= self.func.slots.len() {
+            return Err(Error::UnknownSlot(self.func.name.clone(), slot));
+        }
+        Ok(())
+    }
+
+    // the operand has to be defined before `at` in `block` and be of type `ty`
+    fn operand(&self, block: BlockId, at: isize, op: &Operand, ty: Type) -> Result<(), Error> {
+        let name = &self.func.name;
+        let actual = match op {
+            Operand::Const(_) => Type::I64,
+            Operand::Value(v) => {
+                let Some((def_block, def_at)) = self.defs.get(v) else {
+                    return Err(Error::UseBeforeDef(name.clone(), *v));
+                };
+                let defined = if *def_block == block {
+                    *def_at < at
+                } else {
+                    // uses in unreachable blocks are not checked for dominance
+                    !self.doms.is_reachable(block) || self.doms.dominates(*def_block, block)
+                };
+                if !defined {
+                    return Err(Error::UseBeforeDef(name.clone(), *v));
+                }
+                self.func.ty(*v)
+            }
+        };
+        if actual != ty {
+            return Err(Error::TypeMismatch(name.clone(), ty, *op, actual));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::verify_function;
+    use crate::{
+        error::Error,
+        function::{Block, Function},
+        instr::{BinOp, BlockId, CmpOp, Inst, Operand, Slot, Terminator, Type, Value},
+    };
+
+    fn function(values: Vec<Type>, blocks: Vec<(Vec<Inst>, Terminator)>) -> Function {
+        let mut func = Function::new("f");
+        func.values = values;
+        func.slots = vec!["a".to_string()];
+        func.blocks = blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (insts, term))| Block {
+                id: BlockId(i as u32),
+                insts,
+                term,
+            })
+            .collect();
+        func
+    }
+
+    fn add(dst: u32, lhs: Operand) -> Inst {
+        Inst::Binary {
+            dst: Value(dst),
+            op: BinOp::Add,
+            lhs,
+            rhs: Operand::Const(1),
+        }
+    }
+
+    #[test]
+    fn test_verify_ok() {
+        let func = function(
+            vec![Type::I64, Type::I1],
+            vec![
+                (
+                    vec![
+                        add(0, Operand::Const(1)),
+                        Inst::Cmp {
+                            dst: Value(1),
+                            op: CmpOp::Lt,
+                            lhs: Operand::Value(Value(0)),
+                            rhs: Operand::Const(2),
+                        },
+                    ],
+                    Terminator::Branch {
+                        cond: Operand::Value(Value(1)),
+                        then: BlockId(1),
+                        otherwise: BlockId(1),
+                    },
+                ),
+                (vec![], Terminator::Return(Operand::Value(Value(0)))),
+            ],
+        );
+        verify_function(&func).unwrap();
+    }
+
+    #[rstest(
+        func,
+        expect,
+        case(function(vec![], vec![]), "f: function has no blocks"),
+        case(
+            function(vec![], vec![(vec![], Terminator::Jump(BlockId(3)))]),
+            "f: unknown block bb3"
+        ),
+        case(
+            function(vec![Type::I64], vec![(vec![add(0, Operand::Value(Value(0)))], Terminator::Return(Operand::Const(0)))]),
+            "f: %0 is used before it is defined"
+        ),
+        case(
+            function(
+                vec![Type::I64],
+                vec![(vec![add(0, Operand::Const(0)), add(0, Operand::Const(0))], Terminator::Return(Operand::Const(0)))]
+            ),
+            "f: %0 is defined more than once"
+        ),
+        case(
+            function(
+                vec![Type::I64],
+                vec![
+                    (vec![], Terminator::Branch { cond: Operand::Const(1), then: BlockId(1), otherwise: BlockId(2) }),
+                    (vec![add(0, Operand::Const(0))], Terminator::Jump(BlockId(2))),
+                    (vec![], Terminator::Return(Operand::Value(Value(0)))),
+                ]
+            ),
+            "f: expected i1, but 1 has type i64"
+        ),
+        case(
+            function(
+                vec![Type::I64],
+                vec![
+                    (vec![], Terminator::Jump(BlockId(1))),
+                    (vec![], Terminator::Return(Operand::Value(Value(0)))),
+                    (vec![add(0, Operand::Const(0))], Terminator::Jump(BlockId(1))),
+                ]
+            ),
+            "f: %0 is used before it is defined"
+        ),
+        case(
+            function(vec![], vec![(vec![Inst::Store { slot: Slot(1), src: Operand::Const(0) }], Terminator::Return(Operand::Const(0)))]),
+            "f: unknown slot $1"
+        ),
+    )]
+    fn test_verify_error(func: Function, expect: &str) {
+        let err: Error = verify_function(&func).unwrap_err();
+        assert_eq!(expect, err.to_string());
+    }
+}
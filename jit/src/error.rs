@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("jit is only supported on x86-64 Linux")]
+    UnsupportedHost,
+    #[error("failed to map executable memory: {0}")]
+    Map(std::io::Error),
+    #[error("undefined symbol: {0}")]
+    UndefinedSymbol(String),
+    #[error("relocation against {0} out of range")]
+    RelocationOutOfRange(String),
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, ffi::CString, ptr};
+
+use asm::object::{Object, RelocTarget, SectionId};
+pub use error::Error;
+use memory::{page_align, Mapping};
+
+mod error;
+mod memory;
+
+// jmp [rip], followed by the absolute address of the function
+const STUB: [u8; 6] = [0xff, 0x25, 0x00, 0x00, 0x00, 0x00];
+const STUB_SIZE: usize = STUB.len() + 8;
+
+/// Machine code of an object loaded into the memory of this process.
+///
+/// The text is followed by a stub jumping to each function the object does
+/// not define, looked up with `dlsym`, so calls into libc reach it however far
+/// away it is mapped. The data comes on the next page and stays writable.
+#[derive(Debug)]
+pub struct JitModule {
+    mapping: Mapping,
+    // offsets of the symbols defined by the object from the start of the mapping
+    symbols: HashMap<String, usize>,
+}
+
+impl JitModule {
+    pub fn load(object: &Object) -> Result<Self, Error> {
+        if !cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+            return Err(Error::UnsupportedHost);
+        }
+
+        // every symbol not defined in the object is taken to be a function
+        let mut externals = Vec::new();
+        for reloc in object.relocations.iter() {
+            if let RelocTarget::Symbol(name) = &reloc.target {
+                let defined = object.symbol(name).is_some_and(|s| s.section.is_some());
+                if !defined && !externals.contains(name) {
+                    externals.push(name.clone());
+                }
+            }
+        }
+
+        let stubs = object.text.len().next_multiple_of(16);
+        let data = page_align(stubs + externals.len() * STUB_SIZE);
+        let mut mapping = Mapping::new(data + object.data.len())?;
+        let section = |id| match id {
+            SectionId::Text => 0,
+            SectionId::Data => data,
+        };
+
+        let mut symbols = HashMap::new();
+        for symbol in object.symbols.iter() {
+            if let Some(id) = symbol.section {
+                symbols.insert(symbol.name.clone(), section(id) + symbol.offset as usize);
+            }
+        }
+        let bytes = mapping.bytes_mut();
+        bytes[..object.text.len()].copy_from_slice(&object.text);
+        bytes[data..data + object.data.len()].copy_from_slice(&object.data);
+        let mut addresses = symbols.clone();
+        for (i, name) in externals.iter().enumerate() {
+            let stub = stubs + i * STUB_SIZE;
+            bytes[stub..stub + STUB.len()].copy_from_slice(&STUB);
+            bytes[stub + STUB.len()..stub + STUB_SIZE]
+                .copy_from_slice(&(lookup(name)? as u64).to_le_bytes());
+            addresses.insert(name.clone(), stub);
+        }
+
+        for reloc in object.relocations.iter() {
+            let (name, target) = match &reloc.target {
+                RelocTarget::Symbol(name) => (name.as_str(), addresses[name]),
+                RelocTarget::Section(SectionId::Text) => (".text", section(SectionId::Text)),
+                RelocTarget::Section(SectionId::Data) => (".data", section(SectionId::Data)),
+            };
+            let at = reloc.offset as usize;
+            let value = target as i64 + reloc.addend - at as i64;
+            let value =
+                i32::try_from(value).map_err(|_| Error::RelocationOutOfRange(name.to_string()))?;
+            bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        mapping.make_executable(data)?;
+        Ok(Self { mapping, symbols })
+    }
+
+    /// Address of a symbol defined by the object.
+    pub fn symbol(&self, name: &str) -> Option<*const u8> {
+        let base = self.mapping.addr();
+        self.symbols
+            .get(name)
+            .map(|offset| (base + offset) as *const u8)
+    }
+
+    /// Call `main` and return its value, flushing what it printed through
+    /// libc afterwards.
+    ///
+    /// # Safety
+    ///
+    /// The object must be machine code for this host following the System V
+    /// calling convention, so running it cannot corrupt the process.
+    pub unsafe fn run_main(&self) -> Result<i64, Error> {
+        let addr = self
+            .symbol("main")
+            .ok_or_else(|| Error::UndefinedSymbol("main".to_string()))?;
+        let main: extern "C" fn() -> i64 = std::mem::transmute(addr);
+        let value = main();
+        libc::fflush(ptr::null_mut());
+        Ok(value)
+    }
+}
+
+// address of a function in the process, including the libc teruc links with
+fn lookup(name: &str) -> Result<usize, Error> {
+    let undefined = || Error::UndefinedSymbol(name.to_string());
+    let symbol = CString::new(name).map_err(|_| undefined())?;
+    // SAFETY: the name is a valid nul terminated string
+    let addr = unsafe { libc::dlsym(libc::RTLD_DEFAULT, symbol.as_ptr()) };
+    if addr.is_null() {
+        return Err(undefined());
+    }
+    Ok(addr as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use asm::x86::{
+        directive::Directive,
+        encoder,
+        instr::Instr,
+        module::AsmModule,
+        operand::{Memory, Operand},
+        reg::Reg,
+    };
+    use generator::Generator;
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::{Error, JitModule};
+
+    // compile the source like teruc does, through the IR when `ir` is set
+    fn run(src: &str, ir: bool) -> Result<i64, Error> {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        let nodes = sema::types::check(parser.nodes).unwrap();
+        let module = if ir {
+            let module = ir::lower::lower(&nodes).unwrap();
+            Generator::new(false)
+                .with_regalloc(true)
+                .generate_module(&module)
+                .unwrap()
+        } else {
+            Generator::new(false).generate_program(&nodes).unwrap()
+        };
+        let object = encoder::assemble(&module).unwrap();
+        let jit = JitModule::load(&object)?;
+        unsafe { jit.run_main() }
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("return 42;", 42),
+        case("5+6*7;", 47),
+        case("a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);", 14),
+        case("a = 0; b = 0; while (a < 10) { a = a + 1; b = b + a; } return b;", 55),
+        case("b = 0; for (a = 0; a < 10; a = a + 1) b = b + a; return b;", 45),
+        case("unsigned a = 0; return a - 1 > 5;", 1),
+        case("char c = 200; return c < 0;", 1),
+        case("long labs(long); a = labs(7); return a * 2;", 14),
+        case("int getpid(); return getpid() > 0;", 1)
+    )]
+    fn test_run(input: &str, expect: i64) {
+        assert_eq!(expect, run(input, false).unwrap());
+        assert_eq!(expect, run(input, true).unwrap());
+    }
+
+    #[test]
+    fn test_undefined_symbol() {
+        let e = run("int teruc_undefined(); return teruc_undefined();", true).unwrap_err();
+        assert_eq!("undefined symbol: teruc_undefined", e.to_string());
+    }
+
+    #[test]
+    fn test_data() {
+        let mut module = AsmModule::new();
+        module.directive(Directive::Data);
+        module.label(".Lx");
+        module.directive(Directive::Quad(40));
+        module.directive(Directive::Text);
+        module.directive(Directive::Globl("main".to_string()));
+        module.label("main");
+        module.instr(Instr::Add(
+            Operand::Mem(Memory::symbol(".Lx")),
+            Operand::imm(2),
+        ));
+        module.instr(Instr::Mov(
+            Operand::reg(Reg::Rax),
+            Operand::Mem(Memory::symbol(".Lx")),
+        ));
+        module.instr(Instr::Ret);
+        let jit = JitModule::load(&encoder::assemble(&module).unwrap()).unwrap();
+
+        assert_eq!(42, unsafe { jit.run_main() }.unwrap());
+        assert_eq!(44, unsafe { jit.run_main() }.unwrap());
+    }
+}
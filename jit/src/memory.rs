@@ -0,0 +1,79 @@
+use std::{io, ptr};
+
+use crate::error::Error;
+
+/// Anonymous pages mapped into this process, unmapped when dropped.
+#[derive(Debug)]
+pub(crate) struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mapping {
+    /// Map `len` bytes rounded up to whole pages, readable and writable.
+    pub fn new(len: usize) -> Result<Self, Error> {
+        let len = page_align(len.max(1));
+        // SAFETY: a fresh anonymous mapping does not alias any memory
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::Map(io::Error::last_os_error()));
+        }
+        Ok(Self {
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    pub fn addr(&self) -> usize {
+        self.ptr as usize
+    }
+
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the mapping is writable until it is made executable
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    /// Make the first `len` bytes, a multiple of the page size, executable and
+    /// no longer writable.
+    pub fn make_executable(&mut self, len: usize) -> Result<(), Error> {
+        if len == 0 {
+            return Ok(());
+        }
+        // SAFETY: the range lies within the mapping
+        let result = unsafe {
+            libc::mprotect(
+                self.ptr as *mut libc::c_void,
+                len,
+                libc::PROT_READ | libc::PROT_EXEC,
+            )
+        };
+        if result != 0 {
+            return Err(Error::Map(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: nothing refers to the code once the module is dropped
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+pub(crate) fn page_align(len: usize) -> usize {
+    // SAFETY: sysconf has no preconditions
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    len.div_ceil(page) * page
+}
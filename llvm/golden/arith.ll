@@ -0,0 +1,24 @@
+define i32 @main() {
+entry:
+  %a = alloca i64
+  %b = alloca i64
+  store i64 3, ptr %a
+  %0 = load i64, ptr %a
+  %1 = load i64, ptr %a
+  %2 = add i64 %1, 2
+  %3 = mul i64 %0, %2
+  %4 = load i64, ptr %a
+  %5 = sub i64 %4, 1
+  %6 = sdiv i64 4, %5
+  %7 = sub i64 %3, %6
+  store i64 %7, ptr %b
+  %8 = load i64, ptr %b
+  %9 = load i64, ptr %a
+  %10 = icmp eq i64 %9, 3
+  %11 = zext i1 %10 to i64
+  %12 = add i64 %8, %11
+  %13 = trunc i64 %12 to i32
+  %14 = sext i32 %13 to i64
+  %15 = trunc i64 %14 to i32
+  ret i32 %15
+}
@@ -0,0 +1,24 @@
+define i32 @main() {
+entry:
+  %a = alloca i64
+  store i64 5, ptr %a
+  %0 = load i64, ptr %a
+  %1 = icmp slt i64 3, %0
+  br i1 %1, label %then0, label %else0
+
+then0:
+  %2 = load i64, ptr %a
+  %3 = mul i64 %2, 2
+  %4 = trunc i64 %3 to i32
+  %5 = sext i32 %4 to i64
+  %6 = trunc i64 %5 to i32
+  ret i32 %6
+
+else0:
+  %7 = sub i64 0, 1
+  %8 = trunc i64 %7 to i32
+  ret i32 %8
+
+endif0:
+  ret i32 0
+}
@@ -0,0 +1,17 @@
+define i32 @main() {
+entry:
+  %x = alloca i64
+  %0 = call i32 @add(i32 1, i32 2)
+  %1 = sext i32 %0 to i64
+  store i64 %1, ptr %x
+  %2 = call i32 @add(i32 3, i32 4)
+  %3 = sext i32 %2 to i64
+  %4 = load i64, ptr %x
+  %5 = add i64 %3, %4
+  %6 = trunc i64 %5 to i32
+  %7 = sext i32 %6 to i64
+  %8 = trunc i64 %7 to i32
+  ret i32 %8
+}
+
+declare i32 @add(i32, i32)
@@ -0,0 +1,14 @@
+define i32 @main() {
+entry:
+  %big = alloca i64
+  %neg = alloca i64
+  store i64 81985529216486895, ptr %big
+  %0 = sub i64 0, 70000
+  store i64 %0, ptr %neg
+  %1 = load i64, ptr %neg
+  %2 = load i64, ptr %big
+  %3 = icmp slt i64 %1, %2
+  %4 = zext i1 %3 to i64
+  %5 = trunc i64 %4 to i32
+  ret i32 %5
+}
@@ -0,0 +1,30 @@
+define i32 @main() {
+entry:
+  %b = alloca i64
+  %a = alloca i64
+  store i64 0, ptr %b
+  store i64 0, ptr %a
+  br label %begin0
+
+begin0:
+  %0 = load i64, ptr %a
+  %1 = icmp slt i64 %0, 10
+  br i1 %1, label %body0, label %end0
+
+body0:
+  %2 = load i64, ptr %b
+  %3 = load i64, ptr %a
+  %4 = add i64 %2, %3
+  store i64 %4, ptr %b
+  %5 = load i64, ptr %a
+  %6 = add i64 %5, 1
+  store i64 %6, ptr %a
+  br label %begin0
+
+end0:
+  %7 = load i64, ptr %b
+  %8 = trunc i64 %7 to i32
+  %9 = sext i32 %8 to i64
+  %10 = trunc i64 %9 to i32
+  ret i32 %10
+}
@@ -0,0 +1,34 @@
+define i32 @main() {
+entry:
+  %a = alloca i64
+  %c = alloca i64
+  %n = alloca i64
+  store i64 0, ptr %a
+  store i64 -56, ptr %c
+  store i64 7, ptr %n
+  %0 = load i64, ptr %a
+  %1 = sub i64 %0, 1
+  %2 = trunc i64 %1 to i32
+  %3 = zext i32 %2 to i64
+  %4 = icmp ult i64 5, %3
+  br i1 %4, label %then0, label %endif0
+
+then0:
+  %5 = load i64, ptr %n
+  %6 = udiv i64 %5, 2
+  %7 = load i64, ptr %c
+  %8 = add i64 %6, %7
+  %9 = trunc i64 %8 to i32
+  %10 = sext i32 %9 to i64
+  %11 = trunc i64 %10 to i32
+  ret i32 %11
+
+endif0:
+  %12 = load i64, ptr %c
+  %13 = trunc i64 %12 to i32
+  %14 = sext i32 %13 to i64
+  %15 = icmp slt i64 %14, 0
+  %16 = zext i1 %15 to i64
+  %17 = trunc i64 %16 to i32
+  ret i32 %17
+}
@@ -0,0 +1,34 @@
+define i32 @main() {
+entry:
+  %a = alloca i64
+  store i64 0, ptr %a
+  br label %begin0
+
+begin0:
+  %0 = load i64, ptr %a
+  %1 = icmp slt i64 %0, 5
+  br i1 %1, label %body0, label %end0
+
+body0:
+  %2 = load i64, ptr %a
+  %3 = add i64 %2, 1
+  store i64 %3, ptr %a
+  %4 = load i64, ptr %a
+  %5 = icmp eq i64 %4, 3
+  br i1 %5, label %then1, label %endif1
+
+then1:
+  %6 = load i64, ptr %a
+  %7 = trunc i64 %6 to i32
+  %8 = sext i32 %7 to i64
+  %9 = trunc i64 %8 to i32
+  ret i32 %9
+
+endif1:
+  br label %begin0
+
+end0:
+  %10 = load i64, ptr %a
+  %11 = trunc i64 %10 to i32
+  ret i32 %11
+}
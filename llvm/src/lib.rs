@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+pub use error::Error;
+use parser::ast::{child, local_vars, Node, NodeKind, Type};
+
+mod error;
+
+// a function called but not defined, declared with the types of its prototype
+#[derive(Debug)]
+struct Declare {
+    name: String,
+    params: Vec<Type>,
+    ret: Type,
+}
+
+/// Code generator for LLVM IR, taking the syntax tree to a text module that
+/// `opt`, `llc` or `clang` can optimize and compile.
+///
+/// Values are kept in 64 bits like on the other targets. Every variable is an
+/// `i64` slot made with `alloca` and accessed with loads and stores, leaving
+/// SSA construction to `mem2reg`. Calls convert the arguments and the result
+/// to the types of the prototype, and `main` returns an `i32` as in C. The
+/// language has no arrays, structs or pointers yet, so nothing needs a
+/// `getelementptr`.
+#[derive(Debug, Default)]
+pub struct LlvmGenerator {
+    labels: u32,
+    // number of the next unnamed value
+    values: u32,
+    // whether the current block has ended with a terminator
+    terminated: bool,
+    body: String,
+    declares: Vec<Declare>,
+    // parameter types unless unspecified and the result type
+    prototypes: HashMap<String, (Option<Vec<Type>>, Type)>,
+}
+
+impl LlvmGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate a module defining `main` with the program as its body, the
+    /// declarations of the functions it calls following. Without a return
+    /// `main` ends with the value of the last statement truncated to its `i32`
+    /// result, see `ir::lower::lower`.
+    pub fn generate_program(&mut self, nodes: &[Node]) -> Result<String, Error> {
+        let locals = local_vars(nodes);
+
+        self.values = 0;
+        self.terminated = false;
+        let mut value = None;
+        for node in nodes.iter() {
+            value = self.stmt(node)?;
+        }
+        if !self.terminated {
+            self.ret(&value.unwrap_or_else(|| "0".to_string()));
+        }
+
+        let mut out = String::from("define i32 @main() {\nentry:\n");
+        for local in locals.iter() {
+            out.push_str(&format!("  %{local} = alloca i64\n"));
+        }
+        out.push_str(&std::mem::take(&mut self.body));
+        out.push_str("}\n");
+        if !self.declares.is_empty() {
+            out.push('\n');
+        }
+        for declare in self.declares.iter() {
+            let params: Vec<String> = declare.params.iter().map(|ty| param_type(*ty)).collect();
+            out.push_str(&format!(
+                "declare {} @{}({})\n",
+                param_type(declare.ret),
+                declare.name,
+                params.join(", ")
+            ));
+        }
+        self.declares.clear();
+        self.prototypes.clear();
+        Ok(out)
+    }
+
+    // generate a statement, returning the value of expression statements
+    fn stmt(&mut self, node: &Node) -> Result<Option<String>, Error> {
+        match &node.kind {
+            NodeKind::Return => {
+                let value = self.expr(child(&node.lhs)?)?;
+                self.ret(&value);
+            }
+            NodeKind::If => {
+                let cond = self.cond(child(&node.lhs)?)?;
+                let body = child(&node.rhs)?;
+                let id = self.new_label_id();
+                let (then, otherwise) = match body.kind {
+                    NodeKind::Else => (body.lhs.as_deref(), Some(format!("else{id}"))),
+                    _ => (Some(body), None),
+                };
+                let end = format!("endif{id}");
+                self.emit(format!(
+                    "br i1 {cond}, label %then{id}, label %{}",
+                    otherwise.as_ref().unwrap_or(&end)
+                ));
+                self.terminated = true;
+                self.label(&format!("then{id}"));
+                if let Some(then) = then {
+                    self.stmt(then)?;
+                }
+                if let Some(otherwise) = otherwise {
+                    self.br(&end);
+                    self.label(&otherwise);
+                    if let Some(node) = &body.rhs {
+                        self.stmt(node)?;
+                    }
+                }
+                self.label(&end);
+            }
+            NodeKind::While => self.generate_loop(node.lhs.as_deref(), child(&node.rhs)?)?,
+            NodeKind::For => {
+                let parts = node.for_parts().ok_or(Error::InvalidNode)?;
+                if let Some(init) = parts.init {
+                    self.stmt(init)?;
+                }
+                self.generate_loop(parts.cond, parts.iteration)?;
+            }
+            NodeKind::Block(nodes) => {
+                let mut value = None;
+                for node in nodes.iter() {
+                    value = self.stmt(node)?;
+                }
+                return Ok(value);
+            }
+            NodeKind::Prototype(name, params) => {
+                let ret = node.ty.unwrap_or(Type::Long);
+                self.prototypes.insert(name.clone(), (params.clone(), ret));
+            }
+            _ => return self.expr(node).map(Some),
+        }
+        Ok(None)
+    }
+
+    fn generate_loop(&mut self, cond: Option<&Node>, body: &Node) -> Result<(), Error> {
+        let id = self.new_label_id();
+        self.label(&format!("begin{id}"));
+        if let Some(cond) = cond {
+            let cond = self.cond(cond)?;
+            self.emit(format!("br i1 {cond}, label %body{id}, label %end{id}"));
+            self.terminated = true;
+        }
+        self.label(&format!("body{id}"));
+        self.stmt(body)?;
+        self.br(&format!("begin{id}"));
+        self.label(&format!("end{id}"));
+        Ok(())
+    }
+
+    // the truth of the expression as an i1
+    fn cond(&mut self, node: &Node) -> Result<String, Error> {
+        let Some(pred) = predicate(node) else {
+            let value = self.expr(node)?;
+            return Ok(self.value(format!("icmp ne i64 {value}, 0")));
+        };
+        let lhs = self.expr(child(&node.lhs)?)?;
+        let rhs = self.expr(child(&node.rhs)?)?;
+        Ok(self.value(format!("icmp {pred} i64 {lhs}, {rhs}")))
+    }
+
+    // generate the expression, returning the i64 operand holding its value
+    fn expr(&mut self, node: &Node) -> Result<String, Error> {
+        let value = match &node.kind {
+            NodeKind::Num(n) => (*n as i64).to_string(),
+            NodeKind::LocalVar(name, _) => self.value(format!("load i64, ptr %{name}")),
+            NodeKind::Assignment => {
+                let Some(name) = child(&node.lhs)?.local_var() else {
+                    return Err(Error::LeftValueMustBeIdentifier);
+                };
+                let value = self.expr(child(&node.rhs)?)?;
+                self.emit(format!("store i64 {value}, ptr %{name}"));
+                value
+            }
+            NodeKind::Cast => {
+                let value = self.expr(child(&node.lhs)?)?;
+                match node.ty {
+                    Some(ty) if ty.is_integer() => {
+                        let value = self.truncate(&value, ty);
+                        self.extend(&value, ty)
+                    }
+                    _ => value,
+                }
+            }
+            NodeKind::Func(name, args) => {
+                let (params, ret) = self.declare(name, args.len())?;
+                let mut operands = Vec::new();
+                for (arg, ty) in args.iter().zip(params) {
+                    let value = self.expr(arg)?;
+                    let value = self.truncate(&value, ty);
+                    operands.push(format!("{} {value}", param_type(ty)));
+                }
+                let call = format!("call {} @{name}({})", param_type(ret), operands.join(", "));
+                // the value of a void call is never used
+                if ret == Type::Void {
+                    self.emit(call);
+                    "0".to_string()
+                } else {
+                    let value = self.value(call);
+                    self.extend(&value, ret)
+                }
+            }
+            NodeKind::Equal
+            | NodeKind::NotEqual
+            | NodeKind::LessThan
+            | NodeKind::LessThanOrEqual
+            | NodeKind::GreaterThan
+            | NodeKind::GreaterThanOrEqual => {
+                let cond = self.cond(node)?;
+                self.value(format!("zext i1 {cond} to i64"))
+            }
+            kind => {
+                let op = match kind {
+                    NodeKind::Add => "add",
+                    NodeKind::Sub => "sub",
+                    NodeKind::Mul => "mul",
+                    NodeKind::Div if node.is_unsigned() => "udiv",
+                    NodeKind::Div => "sdiv",
+                    _ => return Err(Error::InvalidNode),
+                };
+                let lhs = self.expr(child(&node.lhs)?)?;
+                let rhs = self.expr(child(&node.rhs)?)?;
+                self.value(format!("{op} i64 {lhs}, {rhs}"))
+            }
+        };
+        Ok(value)
+    }
+
+    // declare a function the first time it is called, returning its types
+    fn declare(&mut self, name: &str, args: usize) -> Result<(Vec<Type>, Type), Error> {
+        // main is the function being generated
+        let (params, ret) = if name == "main" {
+            (Vec::new(), Type::Int)
+        } else if let Some(declare) = self.declares.iter().find(|d| d.name == name) {
+            (declare.params.clone(), declare.ret)
+        } else {
+            let (params, ret) = self
+                .prototypes
+                .get(name)
+                .cloned()
+                .unwrap_or((None, Type::Long));
+            let params = params.unwrap_or_else(|| vec![Type::Long; args]);
+            self.declares.push(Declare {
+                name: name.to_string(),
+                params: params.clone(),
+                ret,
+            });
+            (params, ret)
+        };
+        if params.len() != args {
+            return Err(Error::ArgumentCount(name.to_string(), args, params.len()));
+        }
+        Ok((params, ret))
+    }
+
+    // constants are converted here rather than by an instruction
+    fn truncate(&mut self, value: &str, ty: Type) -> String {
+        match (ty.bits(), value.parse::<i64>()) {
+            (64, _) => value.to_string(),
+            (8, Ok(n)) => (n as i8).to_string(),
+            (32, Ok(n)) => (n as i32).to_string(),
+            (bits, _) => self.value(format!("trunc i64 {value} to i{bits}")),
+        }
+    }
+
+    fn extend(&mut self, value: &str, ty: Type) -> String {
+        let op = if ty.is_unsigned() { "zext" } else { "sext" };
+        match (ty.bits(), value.parse::<i64>()) {
+            (64, _) => value.to_string(),
+            (_, Ok(n)) => (ty.convert(n as u64) as i64).to_string(),
+            (bits, _) => self.value(format!("{op} i{bits} {value} to i64")),
+        }
+    }
+
+    // return the i64 value from main as an int
+    fn ret(&mut self, value: &str) {
+        let value = self.truncate(value, Type::Int);
+        self.emit(format!("ret i32 {value}"));
+        self.terminated = true;
+    }
+
+    fn br(&mut self, label: &str) {
+        if !self.terminated {
+            self.emit(format!("br label %{label}"));
+            self.terminated = true;
+        }
+    }
+
+    // start a block, falling through from the current one
+    fn label(&mut self, label: &str) {
+        self.br(label);
+        self.body.push_str(&format!("\n{label}:\n"));
+        self.terminated = false;
+    }
+
+    // emit an instruction defining the next unnamed value
+    fn value(&mut self, instr: String) -> String {
+        let value = format!("%{}", self.values);
+        self.values += 1;
+        self.emit(format!("{value} = {instr}"));
+        value
+    }
+
+    fn emit(&mut self, instr: impl AsRef<str>) {
+        // code after a terminator is unreachable but needs a block of its own
+        if self.terminated {
+            let id = self.new_label_id();
+            self.label(&format!("dead{id}"));
+        }
+        self.body.push_str("  ");
+        self.body.push_str(instr.as_ref());
+        self.body.push('\n');
+    }
+
+    fn new_label_id(&mut self) -> u32 {
+        let id = self.labels;
+        self.labels += 1;
+        id
+    }
+}
+
+// type of a parameter or result, chars are extended by the caller as the
+// x86-64 and AArch64 ABIs expect
+fn param_type(ty: Type) -> String {
+    match ty {
+        Type::Void => "void".to_string(),
+        Type::Char => "i8 signext".to_string(),
+        Type::UChar => "i8 zeroext".to_string(),
+        ty => format!("i{}", ty.bits()),
+    }
+}
+
+// icmp predicate of the node, unsigned when its operands are
+fn predicate(node: &Node) -> Option<&'static str> {
+    let unsigned = node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned());
+    match (&node.kind, unsigned) {
+        (NodeKind::Equal, _) => Some("eq"),
+        (NodeKind::NotEqual, _) => Some("ne"),
+        (NodeKind::LessThan, false) => Some("slt"),
+        (NodeKind::LessThan, true) => Some("ult"),
+        (NodeKind::LessThanOrEqual, false) => Some("sle"),
+        (NodeKind::LessThanOrEqual, true) => Some("ule"),
+        (NodeKind::GreaterThan, false) => Some("sgt"),
+        (NodeKind::GreaterThan, true) => Some("ugt"),
+        (NodeKind::GreaterThanOrEqual, false) => Some("sge"),
+        (NodeKind::GreaterThanOrEqual, true) => Some("uge"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::LlvmGenerator;
+
+    fn generate(src: &str) -> String {
+        LlvmGenerator::new()
+            .generate_program(&golden::checked(src))
+            .unwrap()
+    }
+
+    #[rstest(
+        name,
+        case("return"),
+        case("arith"),
+        case("branch"),
+        case("loop"),
+        case("while"),
+        case("call"),
+        case("unsigned"),
+        case("constants")
+    )]
+    fn test_golden(name: &str) {
+        golden::check(env!("CARGO_MANIFEST_DIR"), name, "ll", |nodes| {
+            LlvmGenerator::new().generate_program(&nodes).unwrap()
+        });
+    }
+
+    #[test]
+    fn test_generate_allocas() {
+        // every variable gets its slot in the entry block before any code
+        let ll = generate("b = 1; { a = b; } while (c) c = a; return b;");
+        assert!(ll.starts_with(
+            "define i32 @main() {\nentry:\n  %b = alloca i64\n  %a = alloca i64\n  %c = alloca i64\n  store i64 1, ptr %b\n"
+        ));
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("a = 7; return a / 2;", "= sdiv i64 %0, 2\n"),
+        case("unsigned a = 7; return a / 2;", "= udiv i64 %0, 2\n"),
+        case("a = 7; return a < 3;", "= icmp slt i64 %0, 3\n"),
+        case("unsigned a = 7; return a <= 3;", "= icmp ule i64 %0, 3\n"),
+        // constants are converted when generated, other values by instructions
+        case("char c = 300; return c;", "  store i64 44, ptr %c\n"),
+        case("a = 300; char c = a;", "= trunc i64 %0 to i8\n  %2 = sext i8 %1 to i64\n"),
+        // main returns an int whatever the value of the last statement is
+        case("a = 1; a;", "  %0 = load i64, ptr %a\n  %1 = trunc i64 %0 to i32\n  ret i32 %1\n"),
+        // code after a terminator starts a block of its own
+        case(
+            "a = 1; while (a) { return 3; a = 2; }",
+            "  ret i32 3\n\ndead1:\n  store i64 2, ptr %a\n"
+        )
+    )]
+    fn test_generate_instructions(input: &str, expect: &str) {
+        let ll = generate(input);
+        assert!(ll.contains(expect), "{ll}");
+    }
+
+    #[test]
+    fn test_generate_declare() {
+        let ll = generate("int putchar(int); void f(char); f(1); return putchar(65);");
+        assert!(ll.contains("  call void @f(i8 signext 1)\n"));
+        assert!(ll.contains("  %0 = call i32 @putchar(i32 65)\n  %1 = sext i32 %0 to i64\n"));
+        assert!(ll.ends_with("\ndeclare void @f(i8 signext)\ndeclare i32 @putchar(i32)\n"));
+    }
+}
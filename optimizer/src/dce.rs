@@ -0,0 +1,304 @@
+use std::fmt::Display;
+
+use parser::ast::{Node, NodeKind};
+
+use crate::fold::is_pure;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A statement that can never be executed was removed.
+    Unreachable(NodeKind),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Unreachable(kind) => write!(f, "unreachable code: {kind} is never executed"),
+        }
+    }
+}
+
+/// Remove statements following an unconditional return or an endless loop,
+/// branches on constant false conditions and expression statements whose
+/// value is unused and which have no side effects. The value of the last
+/// statement of the program is kept as it becomes the exit status.
+pub fn eliminate_program(nodes: Vec<Node>) -> (Vec<Node>, Vec<Warning>) {
+    let mut eliminator = Eliminator::default();
+    let (nodes, _) = eliminator.stmts(nodes, true);
+    (nodes, eliminator.warnings)
+}
+
+#[derive(Debug, Default)]
+struct Eliminator {
+    warnings: Vec<Warning>,
+}
+
+impl Eliminator {
+    // returns the remaining statements and whether control never reaches their end
+    fn stmts(&mut self, nodes: Vec<Node>, keep_last: bool) -> (Vec<Node>, bool) {
+        let len = nodes.len();
+        let mut out = Vec::new();
+        let mut diverges = false;
+        for (i, node) in nodes.into_iter().enumerate() {
+            if diverges {
+                self.unreachable(&node);
+                break;
+            }
+            let (node, d) = self.stmt(node, keep_last && i + 1 == len);
+            out.extend(node);
+            diverges = d;
+        }
+        (out, diverges)
+    }
+
+    fn stmt(&mut self, node: Node, keep_value: bool) -> (Option<Node>, bool) {
+        let Node {
+            kind,
+            lhs,
+            rhs,
+            ty,
+            span,
+        } = node;
+        match kind {
+            NodeKind::Return => (Some(Node::new(kind, lhs, rhs).at(span)), true),
+            NodeKind::If => {
+                let (cond, body) = (lhs.map(|n| *n), rhs.map(|n| *n));
+                let (then, otherwise, branches) = match body {
+                    Some(Node {
+                        kind: NodeKind::Else,
+                        lhs,
+                        rhs,
+                        span,
+                        ..
+                    }) => (lhs.map(|n| *n), rhs.map(|n| *n), span),
+                    body => (body, None, span),
+                };
+                match cond.as_ref().and_then(Node::num) {
+                    Some(0) => {
+                        self.unreachable_opt(then.as_ref());
+                        let (node, diverges) = self.branch(otherwise);
+                        (without_value(node, keep_value), diverges)
+                    }
+                    Some(_) => {
+                        self.unreachable_opt(otherwise.as_ref());
+                        let (node, diverges) = self.branch(then);
+                        (without_value(node, keep_value), diverges)
+                    }
+                    None => {
+                        let (then, then_diverges) = self.body(then);
+                        let has_else = otherwise.is_some();
+                        let (otherwise, else_diverges) = self.body(otherwise);
+                        let body = if has_else {
+                            Node::new(
+                                NodeKind::Else,
+                                Some(Box::new(then)),
+                                Some(Box::new(otherwise)),
+                            )
+                            .at(branches)
+                        } else {
+                            then
+                        };
+                        let node =
+                            Node::new(kind, cond.map(Box::new), Some(Box::new(body))).at(span);
+                        (Some(node), has_else && then_diverges && else_diverges)
+                    }
+                }
+            }
+            NodeKind::While => match lhs.as_ref().and_then(|n| n.num()) {
+                Some(0) => {
+                    self.unreachable_opt(rhs.as_deref());
+                    (without_value(None, keep_value), false)
+                }
+                // there is no break, a loop on a constant true condition never ends
+                cond => {
+                    let (body, _) = self.body(rhs.map(|n| *n));
+                    let node = Node::new(kind, lhs, Some(Box::new(body))).at(span);
+                    (Some(node), cond.is_some())
+                }
+            },
+            NodeKind::For => {
+                // the condition and iteration are laid out as in `NodeKind::For`
+                let init = lhs.and_then(|n| self.stmt(*n, false).0);
+                let Some(cond) = rhs else {
+                    return (without_value(init, keep_value), false);
+                };
+                let Node {
+                    lhs: test,
+                    rhs: body,
+                    span: cond_span,
+                    ..
+                } = *cond;
+                match test.as_ref().map(|n| n.num()) {
+                    Some(Some(0)) => {
+                        self.unreachable_opt(body.as_deref());
+                        (without_value(init, keep_value), false)
+                    }
+                    test_value => {
+                        let body = self.iteration(body.map(|n| *n));
+                        let cond =
+                            Node::new(NodeKind::If, test, Some(Box::new(body))).at(cond_span);
+                        let node =
+                            Node::new(kind, init.map(Box::new), Some(Box::new(cond))).at(span);
+                        // no condition or a constant one loops forever
+                        (Some(node), !matches!(test_value, Some(None)))
+                    }
+                }
+            }
+            NodeKind::Block(nodes) => {
+                let (nodes, diverges) = self.stmts(nodes, keep_value);
+                (
+                    Some(Node::new(NodeKind::Block(nodes), lhs, rhs).at(span)),
+                    diverges,
+                )
+            }
+            kind => {
+                let node = Node {
+                    kind,
+                    lhs,
+                    rhs,
+                    ty,
+                    span,
+                };
+                if !keep_value && is_pure(&node) {
+                    return (None, false);
+                }
+                (Some(node), false)
+            }
+        }
+    }
+
+    // the body of a control statement, an empty block when nothing is left
+    fn body(&mut self, node: Option<Node>) -> (Node, bool) {
+        match node.map(|n| self.stmt(n, false)) {
+            Some((Some(node), diverges)) => (node, diverges),
+            Some((None, diverges)) => (empty(), diverges),
+            None => (empty(), false),
+        }
+    }
+
+    // the body and the step of a for, each keeping its slot in the iteration
+    // block as laid out in `NodeKind::For`
+    fn iteration(&mut self, node: Option<Node>) -> Node {
+        let Some(Node {
+            kind: NodeKind::Block(nodes),
+            span,
+            ..
+        }) = node
+        else {
+            return self.body(node).0;
+        };
+        let mut nodes = nodes.into_iter();
+        let (body, diverges) = self.body(nodes.next());
+        let step = match nodes.next() {
+            Some(step) if diverges => {
+                self.unreachable(&step);
+                empty()
+            }
+            step => self.body(step).0,
+        };
+        Node::new(NodeKind::Block(vec![body, step]), None, None).at(span)
+    }
+
+    // the branch taken by an if with a constant condition
+    fn branch(&mut self, node: Option<Node>) -> (Option<Node>, bool) {
+        match node {
+            Some(node) => self.stmt(node, false),
+            None => (None, false),
+        }
+    }
+
+    fn unreachable_opt(&mut self, node: Option<&Node>) {
+        if let Some(node) = node {
+            self.unreachable(node);
+        }
+    }
+
+    fn unreachable(&mut self, node: &Node) {
+        // nothing to report for empty statements
+        if let NodeKind::Block(nodes) = &node.kind {
+            if nodes.is_empty() {
+                return;
+            }
+        }
+        self.warnings.push(Warning::Unreachable(node.kind.clone()));
+    }
+}
+
+fn empty() -> Node {
+    Node::new(NodeKind::Block(Vec::new()), None, None)
+}
+
+// the replacement of a control statement ending the program leaves no value
+// like the statement did, ending it with an empty block when it would
+fn without_value(node: Option<Node>, keep_value: bool) -> Option<Node> {
+    match node {
+        _ if !keep_value => node,
+        Some(node) if !has_value(&node) => Some(node),
+        Some(node) => Some(Node::new(NodeKind::Block(vec![node, empty()]), None, None)),
+        None => Some(empty()),
+    }
+}
+
+// whether the statement leaves a value, see `ir::lower::lower`
+fn has_value(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::Block(nodes) => nodes.last().is_some_and(has_value),
+        NodeKind::Return
+        | NodeKind::If
+        | NodeKind::While
+        | NodeKind::For
+        | NodeKind::Prototype(_, _) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::{ast::Node, parser::Parser};
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::eliminate_program;
+
+    fn parse(src: &str) -> Vec<Node> {
+        let tokens = Tokenizer::default().process(src.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        parser.nodes
+    }
+
+    #[rstest(
+        input,
+        expect,
+        warnings,
+        case("return 1; a = 2; return a;", "return 1;", vec!["unreachable code: Assignment is never executed"]),
+        case("a = 1; if (0) a = 2; return a;", "a = 1; return a;", vec!["unreachable code: Assignment is never executed"]),
+        case("if (0) return 1; else return 2;", "return 2;", vec!["unreachable code: Return is never executed"]),
+        case("if (3) return 1; else return 2;", "return 1;", vec!["unreachable code: Return is never executed"]),
+        case("while (0) { a = 1; } return 0;", "return 0;", vec!["unreachable code: Block is never executed"]),
+        case("for (a = 0; 0; a = a + 1) b = 1; return a;", "a = 0; return a;", vec!["unreachable code: Block is never executed"]),
+        case("a = 1; a + 1; b; return a;", "a = 1; return a;", vec![]),
+        case("a = 1; a + 1;", "a = 1; a + 1;", vec![]),
+        case("foo(); 1 / 0; { a; }", "foo(); 1 / 0; { a; }", vec![]),
+        case("if (a) { return 1; } else return 2; a = 1;", "if (a) { return 1; } else return 2;", vec!["unreachable code: Assignment is never executed"]),
+        case("if (a) return 1; a = 1;", "if (a) return 1; a = 1;", vec![]),
+        case("while (1) a = a + 1; return a;", "while (1) a = a + 1;", vec!["unreachable code: Return is never executed"]),
+        case("for (a = 0; ; a = a + 1) { a; } return 1;", "for (a = 0; ; a = a + 1) {}", vec!["unreachable code: Return is never executed"]),
+        case("while (a) { b; return 1; c = 2; }", "while (a) { return 1; }", vec!["unreachable code: Assignment is never executed"]),
+        // the step keeps its slot when the body goes
+        case("for (a = 0; a < 3; a = a + 1) 5; return a;", "for (a = 0; a < 3; a = a + 1) {} return a;", vec![]),
+        case("for (a = 0; a < 3; a = a + 1) return a;", "for (a = 0; a < 3;) return a;", vec!["unreachable code: Assignment is never executed"]),
+        // a constant control statement at the end still leaves no value
+        case("a = 1; if (1) a = 5;", "a = 1; { a = 5; {} }", vec![]),
+        case("a = 6; if (0) a = 2;", "a = 6; {}", vec!["unreachable code: Assignment is never executed"]),
+        case("a = 3; while (0) a = 2;", "a = 3; {}", vec!["unreachable code: Assignment is never executed"]),
+        case("for (a = 4; 0; a = a + 1) a;", "{ a = 4; {} }", vec!["unreachable code: Block is never executed"]),
+        case("a = 1; if (1) return a;", "a = 1; return a;", vec![]),
+    )]
+    fn test_eliminate(input: &str, expect: &str, warnings: Vec<&str>) {
+        let (nodes, actual) = eliminate_program(parse(input));
+        assert_eq!(parse(expect), nodes);
+        let actual: Vec<String> = actual.iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, actual);
+    }
+}
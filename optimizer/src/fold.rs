@@ -0,0 +1,212 @@
+use parser::ast::{Node, NodeKind};
+
+/// Fold constant subtrees and simplify algebraic identities in every statement.
+pub fn fold_program(nodes: Vec<Node>) -> Vec<Node> {
+    nodes.into_iter().map(fold).collect()
+}
+
+/// Evaluate constant subtrees with the wraparound of 64 bit integers, signed
+/// unless the operands have an unsigned type, and simplify identities like
+/// `x*1`, `x+0` and `x-x`. Divisions that would trap at runtime are left alone.
+pub fn fold(node: Node) -> Node {
+    let Node {
+        kind,
+        lhs,
+        rhs,
+        ty,
+        span,
+    } = node;
+    let kind = match kind {
+        NodeKind::Block(nodes) => NodeKind::Block(fold_program(nodes)),
+        NodeKind::Func(name, args) => NodeKind::Func(name, fold_program(args)),
+        kind => kind,
+    };
+    let lhs = match kind {
+        // the target of an assignment stays a variable
+        NodeKind::Assignment => lhs,
+        _ => lhs.map(|n| Box::new(fold(*n))),
+    };
+    let rhs = rhs.map(|n| Box::new(fold(*n)));
+    simplify(Node {
+        kind,
+        lhs,
+        rhs,
+        ty,
+        span,
+    })
+}
+
+fn simplify(node: Node) -> Node {
+    let Node {
+        kind,
+        lhs,
+        rhs,
+        ty,
+        span,
+    } = node;
+    // results keep the type of the expression they replace
+    let num = |n: u64| Node {
+        ty,
+        ..Node::new_num(n).at(span)
+    };
+    if kind == NodeKind::Cast {
+        if let (Some(n), Some(to)) = (lhs.as_ref().and_then(|n| n.num()), ty) {
+            return num(to.convert(n));
+        }
+    }
+    let (lhs, rhs) = match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => (*lhs, *rhs),
+        (lhs, rhs) => {
+            return Node {
+                kind,
+                lhs,
+                rhs,
+                ty,
+                span,
+            }
+        }
+    };
+    let (l, r) = (value(&lhs), value(&rhs));
+    if let (Some(l), Some(r)) = (l, r) {
+        if let Some(n) = evaluate(&kind, l, r, lhs.is_unsigned()) {
+            return num(n as u64);
+        }
+    }
+
+    match (&kind, l, r) {
+        (NodeKind::Add, _, Some(0))
+        | (NodeKind::Sub, _, Some(0))
+        | (NodeKind::Mul, _, Some(1))
+        | (NodeKind::Div, _, Some(1)) => lhs,
+        (NodeKind::Add, Some(0), _) | (NodeKind::Mul, Some(1), _) => rhs,
+        (NodeKind::Mul, _, Some(0)) if is_pure(&lhs) => num(0),
+        (NodeKind::Mul, Some(0), _) if is_pure(&rhs) => num(0),
+        (NodeKind::Sub, _, _) if lhs == rhs && is_pure(&lhs) => num(0),
+        _ => Node {
+            kind,
+            lhs: Some(Box::new(lhs)),
+            rhs: Some(Box::new(rhs)),
+            ty,
+            span,
+        },
+    }
+}
+
+fn evaluate(kind: &NodeKind, l: i64, r: i64, unsigned: bool) -> Option<i64> {
+    let (ul, ur) = (l as u64, r as u64);
+    let n = match kind {
+        NodeKind::Add => l.wrapping_add(r),
+        NodeKind::Sub => l.wrapping_sub(r),
+        NodeKind::Mul => l.wrapping_mul(r),
+        // division by zero and i64::MIN / -1 trap at runtime
+        NodeKind::Div if unsigned => ul.checked_div(ur)? as i64,
+        NodeKind::Div => l.checked_div(r)?,
+        NodeKind::LessThan if unsigned => (ul < ur) as i64,
+        NodeKind::LessThanOrEqual if unsigned => (ul <= ur) as i64,
+        NodeKind::GreaterThan if unsigned => (ul > ur) as i64,
+        NodeKind::GreaterThanOrEqual if unsigned => (ul >= ur) as i64,
+        NodeKind::Equal => (l == r) as i64,
+        NodeKind::NotEqual => (l != r) as i64,
+        NodeKind::LessThan => (l < r) as i64,
+        NodeKind::LessThanOrEqual => (l <= r) as i64,
+        NodeKind::GreaterThan => (l > r) as i64,
+        NodeKind::GreaterThanOrEqual => (l >= r) as i64,
+        _ => return None,
+    };
+    Some(n)
+}
+
+// numbers as the 64 bits they are held in
+fn value(node: &Node) -> Option<i64> {
+    node.num().map(|n| n as i64)
+}
+
+/// Whether evaluating the node has no effect besides producing its value.
+pub fn is_pure(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::Num(_) | NodeKind::LocalVar(_, _) => true,
+        NodeKind::Cast => node.lhs.as_deref().is_some_and(is_pure),
+        NodeKind::Add
+        | NodeKind::Sub
+        | NodeKind::Mul
+        | NodeKind::Equal
+        | NodeKind::NotEqual
+        | NodeKind::LessThan
+        | NodeKind::GreaterThan
+        | NodeKind::LessThanOrEqual
+        | NodeKind::GreaterThanOrEqual => {
+            node.lhs.as_deref().is_some_and(is_pure) && node.rhs.as_deref().is_some_and(is_pure)
+        }
+        // division may trap
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::{
+        ast::{Node, NodeKind},
+        parser::Parser,
+    };
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::fold_program;
+
+    fn parse(src: &str) -> Vec<Node> {
+        let tokens = Tokenizer::default().process(src.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        parser.nodes
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("5+6*7;", "47;"),
+        case("(3+5)/2;", "4;"),
+        case("-3 < 2;", "1;"),
+        case("2 >= 3;", "0;"),
+        case("0 - 9223372036854775807 - 2;", "9223372036854775807;"),
+        case("4611686018427387904 * 4;", "0;"),
+        case("a * 1;", "a;"),
+        case("1 * a + 0;", "a;"),
+        case("0 + a - 0;", "a;"),
+        case("a / 1;", "a;"),
+        case("a - a;", "0;"),
+        case("(a + 1) * 0;", "0;"),
+        case("a = 2 * 3;", "a = 6;"),
+        case("if (1 == 1) return 2 + 2;", "if (1) return 4;"),
+        case("{ a = 1 + 1; b = 2 * a * 1; }", "{ a = 2; b = 2 * a; }")
+    )]
+    fn test_fold(input: &str, expect: &str) {
+        assert_eq!(parse(expect), fold_program(parse(input)));
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("18446744073709551615 / 2;", 9223372036854775807),
+        case("18446744073709551615 > 1;", 1),
+        case("0 - 1 < 18446744073709551615;", 0),
+        case("0 - 1 < 1;", 1)
+    )]
+    fn test_fold_typed(input: &str, expect: u64) {
+        let nodes = sema::types::check(parse(input)).unwrap();
+        let folded = fold_program(nodes);
+        assert_eq!(NodeKind::Num(expect), folded.last().unwrap().kind);
+    }
+
+    #[rstest(
+        input,
+        case("1 / 0;"),
+        case("a / 0;"),
+        case("(0 - 9223372036854775807 - 1) / (0 - 1);"),
+        case("(a = 1) - (a = 1);"),
+        case("foo() * 0;")
+    )]
+    fn test_fold_keeps_runtime_behavior(input: &str) {
+        let folded = fold_program(parse(input));
+        assert!(!matches!(folded[0].kind, NodeKind::Num(_)), "{folded:?}");
+    }
+}
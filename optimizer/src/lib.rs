@@ -0,0 +1,3 @@
+pub mod dce;
+pub mod fold;
+pub mod pass;
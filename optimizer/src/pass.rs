@@ -0,0 +1,308 @@
+use std::{convert::Infallible, fmt::Debug, io};
+
+use asm::x86::{module::AsmModule, peephole, Syntax};
+use ir::function::Module;
+use parser::ast::Node;
+
+use crate::{dce, dce::Warning, fold};
+
+/// What a `PassManager` runs its passes over.
+pub trait Unit {
+    type Error;
+
+    /// Check that a pass left the unit well formed.
+    fn verify(&self) -> Result<(), Self::Error>;
+
+    /// Print the unit to stderr for `-fdump-<name>`.
+    fn dump(&self);
+}
+
+/// A transformation run by the `PassManager`.
+pub trait Pass<U> {
+    /// Name used by `-f<name>`, `-fno-<name>` and `-fdump-<name>`.
+    fn name(&self) -> &'static str;
+
+    fn run(&self, unit: &mut U);
+}
+
+/// The statements of a program and the warnings the passes found in them.
+#[derive(Debug, Default, PartialEq)]
+pub struct Program {
+    pub nodes: Vec<Node>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Program {
+    pub fn new(nodes: Vec<Node>) -> Self {
+        Self {
+            nodes,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Unit for Program {
+    type Error = Infallible;
+
+    fn verify(&self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn dump(&self) {
+        eprint!("{}", parser::printer::print(&self.nodes));
+    }
+}
+
+impl Unit for Module {
+    type Error = ir::Error;
+
+    fn verify(&self) -> Result<(), ir::Error> {
+        ir::verify::verify(self)
+    }
+
+    fn dump(&self) {
+        eprint!("{self}");
+    }
+}
+
+impl Unit for AsmModule {
+    type Error = Infallible;
+
+    fn verify(&self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn dump(&self) {
+        // like eprint, which also has nowhere to report a failed write
+        let _ = Syntax::Intel.write_module(self, &mut io::stderr());
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstantFolding;
+
+impl Pass<Program> for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "fold"
+    }
+
+    fn run(&self, program: &mut Program) {
+        program.nodes = fold::fold_program(std::mem::take(&mut program.nodes));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeadCodeElimination;
+
+impl Pass<Program> for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run(&self, program: &mut Program) {
+        let (nodes, found) = dce::eliminate_program(std::mem::take(&mut program.nodes));
+        program.nodes = nodes;
+        program.warnings.extend(found);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoopOptimization;
+
+impl Pass<Module> for LoopOptimization {
+    fn name(&self) -> &'static str {
+        "loops"
+    }
+
+    fn run(&self, module: &mut Module) {
+        ir::loops::optimize_loops(module);
+    }
+}
+
+/// Tail calls for a target passing up to `max_args` arguments in registers.
+#[derive(Debug, Clone, Copy)]
+pub struct TailCalls {
+    pub max_args: usize,
+}
+
+impl Pass<Module> for TailCalls {
+    fn name(&self) -> &'static str {
+        "tail-calls"
+    }
+
+    fn run(&self, module: &mut Module) {
+        ir::tail::tail_calls(module, self.max_args);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Peephole;
+
+impl Pass<AsmModule> for Peephole {
+    fn name(&self) -> &'static str {
+        "peephole"
+    }
+
+    fn run(&self, module: &mut AsmModule) {
+        peephole::optimize(module);
+    }
+}
+
+/// Runs the enabled passes in the order they were added, verifying and
+/// optionally dumping the unit after each of them.
+pub struct PassManager<U> {
+    // every pass with whether it runs
+    passes: Vec<(Box<dyn Pass<U>>, bool)>,
+    dumps: Vec<&'static str>,
+}
+
+impl<U> Default for PassManager<U> {
+    fn default() -> Self {
+        Self {
+            passes: Vec::new(),
+            dumps: Vec::new(),
+        }
+    }
+}
+
+impl<U> Debug for PassManager<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassManager")
+            .field("passes", &self.names())
+            .field("dumps", &self.dumps)
+            .finish()
+    }
+}
+
+impl<U> PassManager<U> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pass, which runs when `enabled` unless a flag says otherwise.
+    pub fn add<P: Pass<U> + 'static>(&mut self, pass: P, enabled: bool) {
+        self.passes.push((Box::new(pass), enabled));
+    }
+
+    /// Apply `-f<name>`, `-fno-<name>` or `-fdump-<name>` if it names one of
+    /// the passes, returning whether it did.
+    pub fn flag(&mut self, flag: &str) -> bool {
+        let (name, enable) = match flag.strip_prefix("no-") {
+            Some(name) => (name, Some(false)),
+            None => match flag.strip_prefix("dump-") {
+                Some(name) => (name, None),
+                None => (flag, Some(true)),
+            },
+        };
+        let Some((pass, enabled)) = self.passes.iter_mut().find(|(p, _)| p.name() == name) else {
+            return false;
+        };
+        match enable {
+            Some(enable) => *enabled = enable,
+            None => self.dumps.push(pass.name()),
+        }
+        true
+    }
+
+    /// Names of the passes that run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.passes
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(pass, _)| pass.name())
+            .collect()
+    }
+
+    pub fn run(&self, unit: &mut U) -> Result<(), U::Error>
+    where
+        U: Unit,
+    {
+        for (pass, _) in self.passes.iter().filter(|(_, enabled)| *enabled) {
+            pass.run(unit);
+            unit.verify()?;
+            if self.dumps.contains(&pass.name()) {
+                unit.dump();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::{ast::Node, parser::Parser};
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::{ConstantFolding, DeadCodeElimination, PassManager, Program, TailCalls};
+
+    fn parse(src: &str) -> Vec<Node> {
+        let tokens = Tokenizer::default().process(src.to_string()).unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        parser.nodes
+    }
+
+    fn passes() -> PassManager<Program> {
+        let mut pm = PassManager::new();
+        pm.add(ConstantFolding, true);
+        pm.add(DeadCodeElimination, true);
+        pm
+    }
+
+    #[test]
+    fn test_run_in_order() {
+        let pm = passes();
+        assert_eq!(vec!["fold", "dce"], pm.names());
+
+        // folding turns the condition into a constant that dce can see
+        let mut program = Program::new(parse("a = 1; if (2 - 2) a = 2; return a;"));
+        let Ok(()) = pm.run(&mut program);
+        assert_eq!(parse("a = 1; return a;"), program.nodes);
+        assert_eq!(1, program.warnings.len());
+    }
+
+    #[test]
+    fn test_run_nothing() {
+        let nodes = parse("a = 1 + 2; return a;");
+        let mut program = Program::new(nodes.clone());
+        let Ok(()) = PassManager::new().run(&mut program);
+        assert_eq!(Program::new(nodes), program);
+    }
+
+    #[rstest(max_args, expect, case(2, "tail call foo(1, 2)"), case(1, "ret %0"))]
+    fn test_run_ir(max_args: usize, expect: &str) {
+        let mut pm = PassManager::new();
+        pm.add(TailCalls { max_args }, true);
+        let mut module = ir::lower::lower(&parse("return foo(1, 2);")).unwrap();
+        pm.run(&mut module).unwrap();
+        assert!(module.to_string().contains(expect), "{module}");
+    }
+
+    #[rstest(
+        flags,
+        expect,
+        case(&[], vec!["fold", "dce"]),
+        case(&["no-fold"], vec!["dce"]),
+        case(&["no-fold", "no-dce", "fold"], vec!["fold"]),
+        case(&["dump-dce"], vec!["fold", "dce"])
+    )]
+    fn test_flag(flags: &[&str], expect: Vec<&str>) {
+        let mut pm = passes();
+        for flag in flags {
+            assert!(pm.flag(flag));
+        }
+        assert_eq!(expect, pm.names());
+    }
+
+    #[rstest(
+        flag,
+        case("ir"),
+        case("no-regalloc"),
+        case("dump-peephole"),
+        case("fold-all")
+    )]
+    fn test_flag_unknown(flag: &str) {
+        assert!(!passes().flag(flag));
+    }
+}
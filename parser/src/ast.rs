@@ -1,9 +1,78 @@
 use std::{collections::HashMap, fmt::Display};
 
-use token::Token;
+use token::{Span, Token};
 
 use crate::error::Error;
 
+/// Types of C values.
+///
+/// Values live in 64 bit registers and slots, sign extended for signed types
+/// and zero extended for unsigned ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    Char,
+    UChar,
+    Int,
+    UInt,
+    Long,
+    ULong,
+}
+
+impl Type {
+    pub fn is_integer(&self) -> bool {
+        *self != Type::Void
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, Type::UChar | Type::UInt | Type::ULong)
+    }
+
+    pub fn bits(&self) -> u32 {
+        match self {
+            Type::Void => 0,
+            Type::Char | Type::UChar => 8,
+            Type::Int | Type::UInt => 32,
+            Type::Long | Type::ULong => 64,
+        }
+    }
+
+    /// Integer conversion rank, types of the same width share it.
+    pub fn rank(&self) -> u32 {
+        match self {
+            Type::Void => 0,
+            Type::Char | Type::UChar => 1,
+            Type::Int | Type::UInt => 2,
+            Type::Long | Type::ULong => 3,
+        }
+    }
+
+    /// Convert a value already held in 64 bits to this type.
+    pub fn convert(&self, n: u64) -> u64 {
+        match self {
+            Type::Char => n as i8 as u64,
+            Type::UChar => n as u8 as u64,
+            Type::Int => n as i32 as u64,
+            Type::UInt => n as u32 as u64,
+            Type::Void | Type::Long | Type::ULong => n,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Char => write!(f, "char"),
+            Type::UChar => write!(f, "unsigned char"),
+            Type::Int => write!(f, "int"),
+            Type::UInt => write!(f, "unsigned int"),
+            Type::Long => write!(f, "long"),
+            Type::ULong => write!(f, "unsigned long"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NodeKind {
     Add,
@@ -23,9 +92,16 @@ pub enum NodeKind {
     If,
     Else,
     While,
+    /// `for (A; B; C) D` is parsed as `lhs: A, rhs: If(B, Block[D, C])`, the
+    /// body followed by the step runs while the condition holds. Without a
+    /// step `C` is an empty block, which keeps the two in their slots when a
+    /// pass empties the body, see `Node::for_parts`
     For,
     Block(Vec<Node>),
     Func(String, Vec<Node>), // name, args(now args only accept number)
+    Prototype(String, Option<Vec<Type>>), // name, parameter types unless unspecified
+    Declaration,             // lhs: variable, rhs: initializer
+    Cast,                    // lhs: operand converted to the type of the node
 }
 
 impl TryFrom<Token> for NodeKind {
@@ -78,45 +154,84 @@ impl Display for NodeKind {
             NodeKind::For => write!(f, "For"),
             NodeKind::Block(_) => write!(f, "Block"),
             NodeKind::Func(s, _) => write!(f, "Func({s})"),
+            NodeKind::Prototype(s, _) => write!(f, "Prototype({s})"),
+            NodeKind::Declaration => write!(f, "Declaration"),
+            NodeKind::Cast => write!(f, "Cast"),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parts of a `for (init; cond; step) body` loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForParts<'a> {
+    pub init: Option<&'a Node>,
+    pub cond: Option<&'a Node>,
+    /// what runs while the condition holds, the block of the body and the step
+    pub iteration: &'a Node,
+    pub body: &'a Node,
+    pub step: Option<&'a Node>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Node {
     pub kind: NodeKind,
     pub lhs: Option<Box<Node>>,
     pub rhs: Option<Box<Node>>,
+    // type of the value once checked, declared type of declarations
+    pub ty: Option<Type>,
+    // where the node is in the source, unknown for built nodes
+    pub span: Span,
 }
 
+// the span is left out so that trees from different sources compare equal
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.lhs == other.lhs
+            && self.rhs == other.rhs
+            && self.ty == other.ty
+    }
+}
+
+impl Eq for Node {}
+
 impl Node {
     pub fn new(kind: NodeKind, lhs: Option<Box<Node>>, rhs: Option<Box<Node>>) -> Self {
-        Self { kind, lhs, rhs }
+        Self {
+            kind,
+            lhs,
+            rhs,
+            ty: None,
+            span: Span::default(),
+        }
     }
 
     pub fn new_num(n: u64) -> Self {
-        Self {
-            kind: NodeKind::Num(n),
-            lhs: None,
-            rhs: None,
-        }
+        Self::new(NodeKind::Num(n), None, None)
     }
 
     pub fn new_local_var(s: String, offset: u32) -> Self {
-        Self {
-            kind: NodeKind::LocalVar(s, offset),
-            lhs: None,
-            rhs: None,
-        }
+        Self::new(NodeKind::LocalVar(s, offset), None, None)
+    }
+
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_ty(mut self, ty: Type) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    /// Whether the value is of an unsigned type, unchecked nodes are signed.
+    pub fn is_unsigned(&self) -> bool {
+        self.ty.is_some_and(|ty| ty.is_unsigned())
     }
 
     pub fn num_from_token(token: Token) -> Result<Node, Error> {
         if let Token::Num(n) = token {
-            Ok(Node {
-                kind: NodeKind::Num(n),
-                lhs: None,
-                rhs: None,
-            })
+            Ok(Node::new_num(n))
         } else {
             Err(Error::UnexpectedToken(Token::Num(0), token))
         }
@@ -129,7 +244,32 @@ impl Node {
         }
     }
 
-    fn local_var(&self) -> Option<String> {
+    /// Split a `For` into its parts, `None` for any other node. The body and
+    /// the step are the two statements of the iteration block, an empty block
+    /// standing for a missing step or a body that passes have emptied.
+    pub fn for_parts(&self) -> Option<ForParts<'_>> {
+        if self.kind != NodeKind::For {
+            return None;
+        }
+        let cond = self.rhs.as_deref()?;
+        let iteration = cond.rhs.as_deref()?;
+        let NodeKind::Block(nodes) = &iteration.kind else {
+            return None;
+        };
+        let [body, step] = nodes.as_slice() else {
+            return None;
+        };
+        let no_step = matches!(&step.kind, NodeKind::Block(nodes) if nodes.is_empty());
+        Some(ForParts {
+            init: self.lhs.as_deref(),
+            cond: cond.lhs.as_deref(),
+            iteration,
+            body,
+            step: (!no_step).then_some(step),
+        })
+    }
+
+    pub fn local_var(&self) -> Option<String> {
         match &self.kind {
             NodeKind::LocalVar(s, _) => Some(s.clone()),
             _ => None,
@@ -160,3 +300,39 @@ impl Display for Node {
 }
 
 pub type LocalVars = HashMap<String, u32>;
+
+/// A child that the parser always gives nodes of the kind is missing, so the
+/// tree was not built by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("missing child node")]
+pub struct MissingChild;
+
+/// The child of a node whose kind always has it.
+pub fn child(child: &Option<Box<Node>>) -> Result<&Node, MissingChild> {
+    child.as_deref().ok_or(MissingChild)
+}
+
+/// The variables of a program in order of appearance, each named once, for
+/// the generators that give every variable a slot of its own.
+pub fn local_vars(nodes: &[Node]) -> Vec<String> {
+    let mut names = Vec::new();
+    for node in nodes.iter() {
+        collect_local_vars(node, &mut names);
+    }
+    names
+}
+
+fn collect_local_vars(node: &Node, names: &mut Vec<String>) {
+    match &node.kind {
+        NodeKind::LocalVar(name, _) if !names.contains(name) => names.push(name.clone()),
+        NodeKind::Block(nodes) | NodeKind::Func(_, nodes) => {
+            for node in nodes.iter() {
+                collect_local_vars(node, names);
+            }
+        }
+        _ => {}
+    }
+    for child in [&node.lhs, &node.rhs].into_iter().flatten() {
+        collect_local_vars(child, names);
+    }
+}
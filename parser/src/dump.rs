@@ -0,0 +1,286 @@
+use token::Location;
+
+use crate::ast::{Node, NodeKind, Type};
+
+const INDENT: &str = "  ";
+
+/// Dump the tree as a JSON array with an object per statement.
+///
+/// Every node has its `kind`, its `type` when known and its `span`, from the
+/// `start` of its first token to the `end` just past its last one, which are
+/// line 0 for nodes that did not come from the parser. Operands are `lhs` and
+/// `rhs`, the arguments of a call are `args` and the statements of a block
+/// are `body`.
+pub fn json(nodes: &[Node]) -> String {
+    let nodes = nodes.iter().map(|node| json_node(node, 1)).collect();
+    let mut s = json_array(nodes, 0);
+    s.push('\n');
+    s
+}
+
+/// Dump the tree as S-expressions, one per statement.
+///
+/// A node is written as `(Kind attributes... :type "T" :span L:C-L:C children...)`
+/// with each child on a line of its own.
+pub fn sexpr(nodes: &[Node]) -> String {
+    let mut s = String::new();
+    for node in nodes.iter() {
+        sexpr_node(node, 0, &mut s);
+        s.push('\n');
+    }
+    s
+}
+
+// the object closes at `depth`, its fields are one level deeper
+fn json_node(node: &Node, depth: usize) -> String {
+    let mut fields = vec![("kind", json_string(kind_name(&node.kind)))];
+    match &node.kind {
+        NodeKind::Num(n) => fields.push(("value", n.to_string())),
+        NodeKind::LocalVar(name, offset) => {
+            fields.push(("name", json_string(name)));
+            fields.push(("offset", offset.to_string()));
+        }
+        NodeKind::Func(name, args) => {
+            fields.push(("name", json_string(name)));
+            let args = args.iter().map(|arg| json_node(arg, depth + 2)).collect();
+            fields.push(("args", json_array(args, depth + 1)));
+        }
+        NodeKind::Block(nodes) => {
+            let nodes = nodes
+                .iter()
+                .map(|node| json_node(node, depth + 2))
+                .collect();
+            fields.push(("body", json_array(nodes, depth + 1)));
+        }
+        NodeKind::Prototype(name, params) => {
+            fields.push(("name", json_string(name)));
+            let params = match params {
+                Some(params) => {
+                    let params: Vec<String> = params
+                        .iter()
+                        .map(|ty| json_string(&ty.to_string()))
+                        .collect();
+                    format!("[{}]", params.join(", "))
+                }
+                None => "null".to_string(),
+            };
+            fields.push(("params", params));
+        }
+        _ => {}
+    }
+    if let Some(ty) = node.ty {
+        fields.push(("type", json_string(&ty.to_string())));
+    }
+    let span = format!(
+        "{{\"start\": {}, \"end\": {}}}",
+        json_location(node.span.start),
+        json_location(node.span.end)
+    );
+    fields.push(("span", span));
+    if let Some(lhs) = &node.lhs {
+        fields.push(("lhs", json_node(lhs, depth + 1)));
+    }
+    if let Some(rhs) = &node.rhs {
+        fields.push(("rhs", json_node(rhs, depth + 1)));
+    }
+
+    let indent = INDENT.repeat(depth + 1);
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{indent}\"{key}\": {value}"))
+        .collect();
+    format!("{{\n{}\n{}}}", fields.join(",\n"), INDENT.repeat(depth))
+}
+
+fn json_array(items: Vec<String>, depth: usize) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    let indent = INDENT.repeat(depth + 1);
+    let items: Vec<String> = items.iter().map(|item| format!("{indent}{item}")).collect();
+    format!("[\n{}\n{}]", items.join(",\n"), INDENT.repeat(depth))
+}
+
+fn json_location(loc: Location) -> String {
+    format!("{{\"line\": {}, \"column\": {}}}", loc.line, loc.column)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn sexpr_node(node: &Node, depth: usize, out: &mut String) {
+    out.push('(');
+    out.push_str(kind_name(&node.kind));
+    let mut children: Vec<&Node> = Vec::new();
+    match &node.kind {
+        NodeKind::Num(n) => out.push_str(&format!(" {n}")),
+        NodeKind::LocalVar(name, offset) => out.push_str(&format!(" {name} {offset}")),
+        NodeKind::Func(name, args) => {
+            out.push_str(&format!(" {name}"));
+            children.extend(args.iter());
+        }
+        NodeKind::Block(nodes) => children.extend(nodes.iter()),
+        // unspecified parameters are nil, `(void)` is ()
+        NodeKind::Prototype(name, params) => {
+            let params = match params {
+                Some(params) => {
+                    let params: Vec<String> = params.iter().map(sexpr_type).collect();
+                    format!("({})", params.join(" "))
+                }
+                None => "nil".to_string(),
+            };
+            out.push_str(&format!(" {name} {params}"));
+        }
+        _ => {}
+    }
+    if let Some(ty) = &node.ty {
+        out.push_str(&format!(" :type {}", sexpr_type(ty)));
+    }
+    out.push_str(&format!(" :span {}", node.span));
+    children.extend(node.lhs.as_deref());
+    children.extend(node.rhs.as_deref());
+    for child in children {
+        out.push('\n');
+        out.push_str(&INDENT.repeat(depth + 1));
+        sexpr_node(child, depth + 1, out);
+    }
+    out.push(')');
+}
+
+fn sexpr_type(ty: &Type) -> String {
+    format!("\"{ty}\"")
+}
+
+fn kind_name(kind: &NodeKind) -> &'static str {
+    match kind {
+        NodeKind::Add => "Add",
+        NodeKind::Sub => "Sub",
+        NodeKind::Mul => "Mul",
+        NodeKind::Div => "Div",
+        NodeKind::Equal => "Equal",
+        NodeKind::NotEqual => "NotEqual",
+        NodeKind::LessThan => "LessThan",
+        NodeKind::GreaterThan => "GreaterThan",
+        NodeKind::LessThanOrEqual => "LessThanOrEqual",
+        NodeKind::GreaterThanOrEqual => "GreaterThanOrEqual",
+        NodeKind::Assignment => "Assignment",
+        NodeKind::LocalVar(_, _) => "LocalVar",
+        NodeKind::Num(_) => "Num",
+        NodeKind::Return => "Return",
+        NodeKind::If => "If",
+        NodeKind::Else => "Else",
+        NodeKind::While => "While",
+        NodeKind::For => "For",
+        NodeKind::Block(_) => "Block",
+        NodeKind::Func(_, _) => "Func",
+        NodeKind::Prototype(_, _) => "Prototype",
+        NodeKind::Declaration => "Declaration",
+        NodeKind::Cast => "Cast",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokenizer::Tokenizer;
+
+    use super::{json, sexpr};
+    use crate::{ast::Node, parser::Parser};
+
+    fn parse(src: &str) -> Vec<Node> {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        parser.nodes
+    }
+
+    #[test]
+    fn test_json() {
+        let nodes = parse("int f(char);\nunsigned a = f(1) + 2;");
+        let expect = r#"[
+  {
+    "kind": "Prototype",
+    "name": "f",
+    "params": ["char"],
+    "type": "int",
+    "span": {"start": {"line": 1, "column": 1}, "end": {"line": 1, "column": 13}}
+  },
+  {
+    "kind": "Declaration",
+    "type": "unsigned int",
+    "span": {"start": {"line": 2, "column": 1}, "end": {"line": 2, "column": 23}},
+    "lhs": {
+      "kind": "LocalVar",
+      "name": "a",
+      "offset": 8,
+      "span": {"start": {"line": 2, "column": 10}, "end": {"line": 2, "column": 11}}
+    },
+    "rhs": {
+      "kind": "Add",
+      "span": {"start": {"line": 2, "column": 14}, "end": {"line": 2, "column": 22}},
+      "lhs": {
+        "kind": "Func",
+        "name": "f",
+        "args": [
+          {
+            "kind": "Num",
+            "value": 1,
+            "span": {"start": {"line": 2, "column": 16}, "end": {"line": 2, "column": 17}}
+          }
+        ],
+        "span": {"start": {"line": 2, "column": 14}, "end": {"line": 2, "column": 18}}
+      },
+      "rhs": {
+        "kind": "Num",
+        "value": 2,
+        "span": {"start": {"line": 2, "column": 21}, "end": {"line": 2, "column": 22}}
+      }
+    }
+  }
+]
+"#;
+        assert_eq!(expect, json(&nodes));
+    }
+
+    #[test]
+    fn test_json_unknown_span() {
+        let expect = r#"[
+  {
+    "kind": "Block",
+    "body": [],
+    "span": {"start": {"line": 0, "column": 0}, "end": {"line": 0, "column": 0}}
+  }
+]
+"#;
+        assert_eq!(
+            expect,
+            json(&[Node::new(crate::ast::NodeKind::Block(vec![]), None, None)])
+        );
+    }
+
+    #[test]
+    fn test_sexpr() {
+        let nodes = parse("void g(void);\nif (a == 1) { g(); } else a;");
+        let expect = r#"(Prototype g () :type "void" :span 1:1-1:14)
+(If :span 2:1-2:29
+  (Equal :span 2:5-2:11
+    (LocalVar a 8 :span 2:5-2:6)
+    (Num 1 :span 2:10-2:11))
+  (Else :span 2:13-2:29
+    (Block :span 2:13-2:21
+      (Func g :span 2:15-2:18))
+    (LocalVar a 8 :span 2:27-2:28)))
+"#;
+        assert_eq!(expect, sexpr(&nodes));
+    }
+}
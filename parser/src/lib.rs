@@ -1,3 +1,7 @@
 pub mod ast;
+pub mod dump;
 mod error;
 pub mod parser;
+pub mod printer;
+
+pub use error::Error;
@@ -1,9 +1,9 @@
 use std::collections::VecDeque;
 
-use token::Token;
+use token::{Location, Span, Token};
 
 use crate::{
-    ast::{LocalVars, Node, NodeKind},
+    ast::{LocalVars, Node, NodeKind, Type},
     error::Error,
 };
 
@@ -15,6 +15,10 @@ stmt       = expr ";"
                 | "while" "(" expr ")" stmt
                 | "for" "(" expr? ";" expr? ";" expr? ")" stmt
                 | "return" expr ";"
+                | declaration
+declaration = type ident ("(" params ")" | ("=" expr)?) ";"
+params     = "void" | (type ident?)*
+type       = "void" | "char" | "int" | "long" | "unsigned" ("char" | "int" | "long")?
 expr       = assign
 assign     = equality ("=" assign)?
 equality   = relational ("==" relational | "!=" relational)*
@@ -30,6 +34,10 @@ primary    = num
 #[derive(Debug)]
 pub struct Parser {
     tokens: VecDeque<Token>,
+    // where each of the remaining tokens is, empty when not known
+    spans: VecDeque<Span>,
+    // where the last token taken ends
+    end: Location,
     pub nodes: Vec<Node>,
     local_val_offset: u32,
     local_vars: LocalVars,
@@ -39,12 +47,24 @@ impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens: tokens.into(),
+            spans: VecDeque::new(),
+            end: Location::default(),
             nodes: Vec::new(),
             local_val_offset: 1,
             local_vars: LocalVars::new(),
         }
     }
 
+    /// Parser for tokens from `Tokenizer::locate`, which records on each node
+    /// the span of its tokens.
+    pub fn with_locations(tokens: Vec<(Token, Span)>) -> Self {
+        let (tokens, spans): (Vec<_>, Vec<_>) = tokens.into_iter().unzip();
+        Self {
+            spans: spans.into(),
+            ..Self::new(tokens)
+        }
+    }
+
     pub fn parse(&mut self) -> Result<(), Error> {
         self.program()
     }
@@ -64,10 +84,15 @@ impl Parser {
                 | "while" "(" expr ")" stmt
                 | "for" "(" expr? ";" expr? ";" expr? ")" stmt
                 | "return" expr ";"
+                | declaration
     */
     fn stmt(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let node = if let Some(t) = self.tokens.front() {
             match t {
+                Token::Int | Token::Char | Token::Long | Token::Unsigned | Token::Void => {
+                    self.declaration()?
+                }
                 Token::Return => {
                     self.consume(Token::Return)?;
                     let node = Node::new(NodeKind::Return, Some(Box::new(self.expr()?)), None);
@@ -83,13 +108,15 @@ impl Parser {
 
                     if let Some(t) = self.tokens.front() {
                         if t.eq(&Token::Else) {
-                            // else
+                            // else, spanning both branches
                             self.consume(Token::Else)?;
+                            let then = rhs.span.start;
                             rhs = Node::new(
                                 NodeKind::Else,
                                 Some(Box::new(rhs)),
                                 Some(Box::new(self.stmt()?)),
-                            );
+                            )
+                            .at(self.span(then));
                         }
                     }
                     Node::new(NodeKind::If, Some(Box::new(lhs)), Some(Box::new(rhs)))
@@ -127,7 +154,7 @@ impl Parser {
 
                     // C
                     let expr = match self.tokens.front() {
-                        Some(Token::Semicolon) => None,
+                        Some(Token::CloseParen) => None,
                         Some(_) => Some(self.expr()?),
                         None => return Err(Error::InvalidTermination),
                     };
@@ -135,20 +162,20 @@ impl Parser {
 
                     // D
                     let stmt = self.stmt()?;
-                    let mut block_nodes = vec![stmt];
-                    if let Some(node) = expr {
-                        block_nodes.push(node);
-                    }
+                    // the step keeps its slot, see `NodeKind::For`
+                    let step =
+                        expr.unwrap_or_else(|| Node::new(NodeKind::Block(Vec::new()), None, None));
+                    let block_nodes = vec![stmt, step];
 
+                    // the nodes made up for the loop span all of it
                     let rhs = Node::new(
                         NodeKind::If,
                         if_lhs,
-                        Some(Box::new(Node::new(
-                            NodeKind::Block(block_nodes),
-                            None,
-                            None,
-                        ))),
-                    );
+                        Some(Box::new(
+                            Node::new(NodeKind::Block(block_nodes), None, None).at(self.span(loc)),
+                        )),
+                    )
+                    .at(self.span(loc));
                     Node::new(NodeKind::For, lhs, Some(Box::new(rhs)))
                 }
                 Token::OpenBrace => {
@@ -168,9 +195,10 @@ impl Parser {
                     Node::new(NodeKind::Block(nodes), None, None)
                 }
                 _ => {
+                    // the expression keeps its span without the semicolon
                     let node = self.expr()?;
                     self.consume(Token::Semicolon)?;
-                    node
+                    return Ok(node);
                 }
             }
         } else {
@@ -186,7 +214,79 @@ impl Parser {
         //     }
         // }
         // let _ = self.tokens.pop_front();
-        Ok(node)
+        Ok(node.at(self.span(loc)))
+    }
+
+    // declaration = type ident ("(" params ")" | ("=" expr)?) ";"
+    fn declaration(&mut self) -> Result<Node, Error> {
+        let ty = self.ty()?;
+        let loc = self.location();
+        let name = match self.advance() {
+            Some(Token::Identifier(s)) => s,
+            Some(t) => return Err(Error::UnexpectedToken(Token::Identifier(String::new()), t)),
+            None => return Err(Error::InvalidTermination),
+        };
+        let node = if let Some(Token::OpenParen) = self.tokens.front() {
+            self.consume(Token::OpenParen)?;
+            let params = self.params()?;
+            self.consume(Token::CloseParen)?;
+            Node::new(NodeKind::Prototype(name, params), None, None)
+        } else {
+            let var = self.local_var(name).at(self.span(loc));
+            let init = match self.tokens.front() {
+                Some(Token::Assignment) => {
+                    self.consume(Token::Assignment)?;
+                    Some(Box::new(self.expr()?))
+                }
+                _ => None,
+            };
+            Node::new(NodeKind::Declaration, Some(Box::new(var)), init)
+        };
+        self.consume(Token::Semicolon)?;
+        Ok(node.with_ty(ty))
+    }
+
+    // params = "void" | (type ident?)*
+    // an empty list leaves the parameters unspecified
+    fn params(&mut self) -> Result<Option<Vec<Type>>, Error> {
+        let mut params = Vec::new();
+        match self.tokens.front() {
+            Some(Token::CloseParen) => return Ok(None),
+            Some(Token::Void) if self.tokens.get(1) == Some(&Token::CloseParen) => {
+                self.consume(Token::Void)?;
+                return Ok(Some(params));
+            }
+            _ => {}
+        }
+        while !matches!(self.tokens.front(), Some(Token::CloseParen) | None) {
+            params.push(self.ty()?);
+            if let Some(Token::Identifier(_)) = self.tokens.front() {
+                self.advance();
+            }
+        }
+        Ok(Some(params))
+    }
+
+    // type = "void" | "char" | "int" | "long" | "unsigned" ("char" | "int" | "long")?
+    fn ty(&mut self) -> Result<Type, Error> {
+        let ty = match self.advance() {
+            Some(Token::Void) => Type::Void,
+            Some(Token::Char) => Type::Char,
+            Some(Token::Int) => Type::Int,
+            Some(Token::Long) => Type::Long,
+            Some(Token::Unsigned) => match self.tokens.front() {
+                Some(Token::Char) => Type::UChar,
+                Some(Token::Int) => Type::UInt,
+                Some(Token::Long) => Type::ULong,
+                _ => return Ok(Type::UInt),
+            },
+            Some(t) => return Err(Error::UnexpectedToken(Token::Int, t)),
+            None => return Err(Error::InvalidTermination),
+        };
+        if matches!(ty, Type::UChar | Type::UInt | Type::ULong) {
+            self.advance();
+        }
+        Ok(ty)
     }
 
     // expr = assign
@@ -196,16 +296,18 @@ impl Parser {
 
     // assign = equality ("=" assign)?
     fn assign(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let mut node = self.equality()?;
 
         if let Some(t) = self.tokens.front() {
             if t.eq(&Token::Assignment) {
-                self.tokens.pop_front();
+                self.advance();
                 node = Node::new(
                     NodeKind::Assignment,
                     Some(Box::new(node)),
                     Some(Box::new(self.assign()?)),
-                );
+                )
+                .at(self.span(loc));
             }
         }
 
@@ -214,24 +316,27 @@ impl Parser {
 
     // equality = relational ("==" relational | "!=" relational)*
     fn equality(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let mut node = self.relational()?;
         while let Some(p) = self.tokens.front() {
             match p {
                 Token::Equal => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
                     node = Node::new(
                         NodeKind::Equal,
                         Some(Box::new(node)),
                         Some(Box::new(self.relational()?)),
                     )
+                    .at(self.span(loc))
                 }
                 Token::NotEqual => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
                     node = Node::new(
                         NodeKind::NotEqual,
                         Some(Box::new(node)),
                         Some(Box::new(self.relational()?)),
                     )
+                    .at(self.span(loc))
                 }
                 _ => return Ok(node),
             }
@@ -241,20 +346,22 @@ impl Parser {
 
     // relational = add ("<" add | "<=" add | ">" add | ">=" add)*
     fn relational(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let mut node = self.add()?;
         while let Some(p) = self.tokens.front() {
             match p {
                 Token::LessThan => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
                     node = Node::new(
                         NodeKind::LessThan,
                         Some(Box::new(node)),
                         Some(Box::new(self.add()?)),
                     )
+                    .at(self.span(loc))
                 }
                 // GreaterThan(lhs, rhs) is translate to LessThan(rhs, lhs)
                 Token::GreaterThan => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
 
                     // node = Node::new(
                     //     NodeKind::GreaterThan,
@@ -266,18 +373,20 @@ impl Parser {
                         Some(Box::new(self.add()?)),
                         Some(Box::new(node)),
                     )
+                    .at(self.span(loc))
                 }
                 Token::LessThanOrEqual => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
                     node = Node::new(
                         NodeKind::LessThanOrEqual,
                         Some(Box::new(node)),
                         Some(Box::new(self.add()?)),
                     )
+                    .at(self.span(loc))
                 }
                 // GreaterThanOrEqual(lhs, rhs) is translate to LessThanOrEqual(rhs, lhs)
                 Token::GreaterThanOrEqual => {
-                    self.tokens.pop_front(); // consume
+                    self.advance(); // consume
 
                     // node = Node::new(
                     //     NodeKind::GreaterThanOrEqual,
@@ -289,6 +398,7 @@ impl Parser {
                         Some(Box::new(self.add()?)),
                         Some(Box::new(node)),
                     )
+                    .at(self.span(loc))
                 }
                 _ => return Ok(node),
             }
@@ -298,6 +408,7 @@ impl Parser {
 
     // add = mul ("+" mul | "-" mul)*
     fn add(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let mut node = self.mul()?;
 
         while let Some(p) = self.tokens.front() {
@@ -309,6 +420,7 @@ impl Parser {
                         Some(Box::new(node)),
                         Some(Box::new(self.mul()?)),
                     )
+                    .at(self.span(loc))
                 }
                 Token::Sub => {
                     self.consume(Token::Sub)?;
@@ -317,6 +429,7 @@ impl Parser {
                         Some(Box::new(node)),
                         Some(Box::new(self.mul()?)),
                     )
+                    .at(self.span(loc))
                 }
                 _ => return Ok(node),
             }
@@ -326,6 +439,7 @@ impl Parser {
 
     // mul = unary ("*" unary | "/" unary)*
     fn mul(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         let mut node = self.unary()?;
 
         while let Some(p) = self.tokens.front() {
@@ -336,7 +450,8 @@ impl Parser {
                         NodeKind::Mul,
                         Some(Box::new(node)),
                         Some(Box::new(self.unary()?)),
-                    );
+                    )
+                    .at(self.span(loc));
                 }
                 Token::Div => {
                     self.consume(Token::Div)?;
@@ -344,7 +459,8 @@ impl Parser {
                         NodeKind::Div,
                         Some(Box::new(node)),
                         Some(Box::new(self.unary()?)),
-                    );
+                    )
+                    .at(self.span(loc));
                 }
                 _ => return Ok(node),
             }
@@ -355,6 +471,7 @@ impl Parser {
 
     // unary = ("+" | "-")? primary
     fn unary(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         if let Some(p) = self.tokens.front() {
             match p {
                 Token::Add => {
@@ -363,11 +480,13 @@ impl Parser {
                 }
                 Token::Sub => {
                     self.consume(Token::Sub)?;
+                    let zero = Node::new_num(0).at(self.span(loc));
                     Ok(Node::new(
                         NodeKind::Sub,
-                        Some(Box::new(Node::new_num(0))),
+                        Some(Box::new(zero)),
                         Some(Box::new(self.primary()?)),
-                    ))
+                    )
+                    .at(self.span(loc)))
                 }
                 _ => self.primary(),
             }
@@ -382,6 +501,7 @@ impl Parser {
                 | "(" expr ")"
     */
     fn primary(&mut self) -> Result<Node, Error> {
+        let loc = self.location();
         if let Some(t) = self.tokens.front() {
             if t.eq(&Token::OpenParen) {
                 // continue to parse expr
@@ -397,9 +517,9 @@ impl Parser {
                 } else {
                     Err(Error::InvalidTermination)
                 }
-            } else if let Some(t) = self.tokens.pop_front() {
+            } else if let Some(t) = self.advance() {
                 match t {
-                    Token::Num(n) => Ok(Node::new_num(n)),
+                    Token::Num(n) => Ok(Node::new_num(n).at(self.span(loc))),
                     Token::Identifier(s) => {
                         // func
                         if let Some(tt) = self.tokens.front() {
@@ -407,14 +527,16 @@ impl Parser {
                                 self.consume(Token::OpenParen)?;
                                 // args
                                 let mut args = vec![];
-                                while let Some(ttt) = self.tokens.pop_front() {
+                                while let Some(ttt) = self.tokens.front().cloned() {
+                                    let arg = self.location();
+                                    self.advance();
                                     if ttt.eq(&Token::CloseParen) {
                                         break;
                                     }
                                     match ttt {
                                         Token::CloseParen => break,
                                         Token::Num(n) => {
-                                            args.push(Node::new_num(n));
+                                            args.push(Node::new_num(n).at(self.span(arg)));
                                         }
                                         _ => {
                                             return Err(Error::UnexpectedToken(Token::Num(0), ttt))
@@ -424,19 +546,13 @@ impl Parser {
                                 if args.len() > 6 {
                                     return Err(Error::TooManyArguments(s));
                                 }
-                                return Ok(Node::new(NodeKind::Func(s, args), None, None));
+                                return Ok(Node::new(NodeKind::Func(s, args), None, None)
+                                    .at(self.span(loc)));
                                 // TODO: implement lhs and rhs
                             }
                         }
                         // ident
-                        if let Some(offset) = self.find_local_var(&s) {
-                            Ok(Node::new_local_var(s, offset))
-                        } else {
-                            let offset = self.local_val_offset * 8;
-                            self.local_val_offset += 1;
-                            self.local_vars.insert(s.clone(), offset);
-                            Ok(Node::new_local_var(s, offset))
-                        }
+                        Ok(self.local_var(s).at(self.span(loc)))
                     }
                     _ => Err(Error::InvalidToken(t)),
                 }
@@ -448,6 +564,37 @@ impl Parser {
         }
     }
 
+    // location of the next token
+    fn location(&self) -> Location {
+        self.spans
+            .front()
+            .map(|span| span.start)
+            .unwrap_or_default()
+    }
+
+    // from the start to the end of the last token taken
+    fn span(&self, start: Location) -> Span {
+        Span::new(start, self.end)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        if let Some(span) = self.spans.pop_front() {
+            self.end = span.end;
+        }
+        self.tokens.pop_front()
+    }
+
+    // the variable with its slot, which is allocated on first sight
+    fn local_var(&mut self, name: String) -> Node {
+        if let Some(offset) = self.find_local_var(&name) {
+            return Node::new_local_var(name, offset);
+        }
+        let offset = self.local_val_offset * 8;
+        self.local_val_offset += 1;
+        self.local_vars.insert(name.clone(), offset);
+        Node::new_local_var(name, offset)
+    }
+
     fn find_local_var(&self, name: &str) -> Option<u32> {
         self.local_vars.get(name).copied()
     }
@@ -455,7 +602,7 @@ impl Parser {
     fn consume(&mut self, expect: Token) -> Result<(), Error> {
         if let Some(t) = self.tokens.front() {
             if t.eq(&expect) {
-                self.tokens.pop_front();
+                self.advance();
                 Ok(())
             } else {
                 Err(Error::UnexpectedToken(expect, t.clone()))
@@ -470,8 +617,9 @@ impl Parser {
 mod tests {
     use rstest::rstest;
     use token::Token;
+    use tokenizer::Tokenizer;
 
-    use crate::ast::{Node, NodeKind};
+    use crate::ast::{local_vars, Node, NodeKind, Type};
 
     use super::Parser;
 
@@ -617,10 +765,153 @@ mod tests {
             vec![Token::Identifier("foo".to_string()), Token::OpenParen, Token::Num(1), Token::Num(2), Token::CloseParen, Token::Semicolon],
             vec![Node::new(NodeKind::Func("foo".to_string(), vec![Node::new_num(1), Node::new_num(2)]), None, None)],
         ),
+        case(
+            vec![Token::Int, Token::Identifier("foo".to_string()), Token::OpenParen, Token::CloseParen, Token::Semicolon],
+            vec![Node::new(NodeKind::Prototype("foo".to_string(), None), None, None).with_ty(Type::Int)],
+        ),
+        case(
+            vec![
+                Token::Int, Token::Identifier("add".to_string()), Token::OpenParen,
+                Token::Int, Token::Identifier("a".to_string()), Token::Unsigned, Token::Long, Token::CloseParen, Token::Semicolon,
+            ],
+            vec![Node::new(NodeKind::Prototype("add".to_string(), Some(vec![Type::Int, Type::ULong])), None, None).with_ty(Type::Int)],
+        ),
+        case(
+            vec![Token::Void, Token::Identifier("f".to_string()), Token::OpenParen, Token::Void, Token::CloseParen, Token::Semicolon],
+            vec![Node::new(NodeKind::Prototype("f".to_string(), Some(vec![])), None, None).with_ty(Type::Void)],
+        ),
+        case(
+            vec![
+                Token::Unsigned, Token::Identifier("a".to_string()), Token::Semicolon,
+                Token::Char, Token::Identifier("b".to_string()), Token::Assignment, Token::Identifier("a".to_string()), Token::Semicolon,
+            ],
+            vec![
+                Node::new(NodeKind::Declaration, Some(Box::new(Node::new_local_var("a".to_string(), 8))), None).with_ty(Type::UInt),
+                Node::new(NodeKind::Declaration, Some(Box::new(Node::new_local_var("b".to_string(), 16))), Some(Box::new(Node::new_local_var("a".to_string(), 8)))).with_ty(Type::Char),
+            ],
+        ),
     )]
     fn test_parser_parse(input: Vec<Token>, expect: Vec<Node>) {
         let mut parser = Parser::new(input);
         parser.parse().unwrap();
         assert_eq!(expect, parser.nodes);
     }
+
+    #[test]
+    fn test_parser_locations() {
+        let tokens = Tokenizer::default()
+            .locate("a = 1;\nreturn a + foo();".to_string())
+            .unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+
+        let ret = &parser.nodes[1];
+        let add = ret.lhs.as_ref().unwrap();
+        let locations: Vec<_> = [
+            ret,
+            add,
+            add.lhs.as_ref().unwrap(),
+            add.rhs.as_ref().unwrap(),
+        ]
+        .iter()
+        .map(|node| node.span.to_string())
+        .collect();
+        assert_eq!(
+            vec!["2:1-2:18", "2:8-2:17", "2:8-2:9", "2:12-2:17"],
+            locations
+        );
+    }
+
+    #[test]
+    fn test_parser_spans() {
+        let tokens = Tokenizer::default()
+            .locate(
+                "if (a) b = -1; else { b = 2; }
+for (;; b) a;"
+                    .to_string(),
+            )
+            .unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+
+        let branches = parser.nodes[0].rhs.as_ref().unwrap();
+        let neg = branches.lhs.as_ref().unwrap().rhs.as_ref().unwrap();
+        let body = parser.nodes[1].rhs.as_ref().unwrap();
+        let spans: Vec<_> = [
+            &parser.nodes[0],
+            branches,
+            neg,
+            neg.lhs.as_ref().unwrap(),
+            &parser.nodes[1],
+            body,
+            body.rhs.as_ref().unwrap(),
+        ]
+        .iter()
+        .map(|node| node.span.to_string())
+        .collect();
+        let expect = vec![
+            "1:1-1:31",
+            "1:8-1:31",
+            "1:12-1:14",
+            "1:12-1:13",
+            "2:1-2:14",
+            "2:1-2:14",
+            "2:1-2:14",
+        ];
+        assert_eq!(expect, spans);
+    }
+
+    #[rstest(
+        input,
+        init,
+        cond,
+        body,
+        step,
+        case(
+            "for (i = 0; i < 3; i = i + 1) a;",
+            "1:6-1:11",
+            "1:13-1:18",
+            "1:31-1:32",
+            "1:20-1:29"
+        ),
+        case("for (;; i = i + 1) { a; }", "", "", "1:20-1:26", "1:9-1:18"),
+        case("for (i = 0; i < 3;) a;", "1:6-1:11", "1:13-1:18", "1:21-1:22", "")
+    )]
+    fn test_parser_for_parts(input: &str, init: &str, cond: &str, body: &str, step: &str) {
+        let tokens = Tokenizer::default().locate(input.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+
+        let parts = parser.nodes[0].for_parts().unwrap();
+        let span = |node: Option<&Node>| node.map(|n| n.span.to_string()).unwrap_or_default();
+        assert_eq!(
+            [init, cond, body, step],
+            [parts.init, parts.cond, Some(parts.body), parts.step].map(span)
+        );
+        // the iteration is the block the body and the step run in, with an
+        // empty block in place of a missing step
+        let step = parts.step.cloned();
+        let step = step.unwrap_or_else(|| Node::new(NodeKind::Block(Vec::new()), None, None));
+        assert_eq!(
+            NodeKind::Block(vec![parts.body.clone(), step]),
+            parts.iteration.kind
+        );
+        assert_eq!(None, parts.iteration.for_parts());
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("a = 1; b = a; return b + c;", vec!["a", "b", "c"]),
+        case("{ x = 1; } while (y) x = y;", vec!["x", "y"]),
+        case("for (i = 0; i < 3; i = i + 1) j = i; return 0;", vec!["i", "j"]),
+        case("return 1;", vec![])
+    )]
+    fn test_local_vars(input: &str, expect: Vec<&str>) {
+        let tokens = Tokenizer::default().locate(input.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+
+        assert_eq!(expect, local_vars(&parser.nodes));
+    }
 }
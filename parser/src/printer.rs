@@ -0,0 +1,388 @@
+use crate::ast::{Node, NodeKind, Type};
+
+const INDENT: &str = "    ";
+
+// binding power of the operators, higher binds tighter
+const ASSIGN: u8 = 1;
+const EQUALITY: u8 = 2;
+const RELATIONAL: u8 = 3;
+const ADD: u8 = 4;
+const MUL: u8 = 5;
+const UNARY: u8 = 6;
+const PRIMARY: u8 = 7;
+
+/// Print the program as C source, one statement per line.
+///
+/// Parentheses are only added where the precedence of the operators needs
+/// them, so parsing the output gives back the same tree. `0 - x` and `-x`
+/// parse to the same tree and are printed as `-x`.
+pub fn print(nodes: &[Node]) -> String {
+    let mut printer = Printer::default();
+    for node in nodes.iter() {
+        printer.stmt(node);
+    }
+    printer.out
+}
+
+#[derive(Debug, Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn stmt(&mut self, node: &Node) {
+        self.indent();
+        self.stmt_inline(node);
+        self.out.push('\n');
+    }
+
+    // the statement starting where the output is, without the final newline
+    fn stmt_inline(&mut self, node: &Node) {
+        match &node.kind {
+            NodeKind::Return => {
+                self.out.push_str("return ");
+                self.child(&node.lhs, ASSIGN);
+                self.out.push(';');
+            }
+            NodeKind::If => {
+                self.out.push_str("if (");
+                self.child(&node.lhs, ASSIGN);
+                self.out.push(')');
+                let Some(body) = &node.rhs else {
+                    return;
+                };
+                match (&body.kind, &body.lhs) {
+                    (NodeKind::Else, Some(then)) => {
+                        // an inner if without else would take the else
+                        if dangles(then) {
+                            self.out.push_str(" {\n");
+                            self.nested(then);
+                            self.indent();
+                            self.out.push('}');
+                        } else {
+                            self.body(then);
+                        }
+                        if is_block(then) || dangles(then) {
+                            self.out.push(' ');
+                        } else {
+                            self.out.push('\n');
+                            self.indent();
+                        }
+                        self.out.push_str("else");
+                        match &body.rhs {
+                            Some(other) if other.kind == NodeKind::If => {
+                                self.out.push(' ');
+                                self.stmt_inline(other);
+                            }
+                            Some(other) => self.body(other),
+                            None => self.out.push(';'),
+                        }
+                    }
+                    _ => self.body(body),
+                }
+            }
+            NodeKind::While => {
+                self.out.push_str("while (");
+                self.child(&node.lhs, ASSIGN);
+                self.out.push(')');
+                if let Some(body) = &node.rhs {
+                    self.body(body);
+                }
+            }
+            NodeKind::For => {
+                self.out.push_str("for (");
+                self.child(&node.lhs, ASSIGN);
+                self.out.push(';');
+                let Some(parts) = node.for_parts() else {
+                    return;
+                };
+                if let Some(cond) = parts.cond {
+                    self.out.push(' ');
+                    self.expr(cond, ASSIGN);
+                }
+                self.out.push(';');
+                if let Some(step) = parts.step {
+                    self.out.push(' ');
+                    self.expr(step, ASSIGN);
+                }
+                self.out.push(')');
+                self.body(parts.body);
+            }
+            NodeKind::Block(nodes) => {
+                if nodes.is_empty() {
+                    self.out.push_str("{}");
+                    return;
+                }
+                self.out.push_str("{\n");
+                self.depth += 1;
+                for node in nodes.iter() {
+                    self.stmt(node);
+                }
+                self.depth -= 1;
+                self.indent();
+                self.out.push('}');
+            }
+            NodeKind::Prototype(name, params) => {
+                let params = match params {
+                    None => String::new(),
+                    Some(params) if params.is_empty() => "void".to_string(),
+                    Some(params) => params
+                        .iter()
+                        .map(Type::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+                let ty = node.ty.unwrap_or(Type::Int);
+                self.out.push_str(&format!("{ty} {name}({params});"));
+            }
+            NodeKind::Declaration => {
+                let ty = node.ty.unwrap_or(Type::Long);
+                self.out.push_str(&format!("{ty} "));
+                self.child(&node.lhs, PRIMARY);
+                if let Some(init) = &node.rhs {
+                    self.out.push_str(" = ");
+                    self.expr(init, ASSIGN);
+                }
+                self.out.push(';');
+            }
+            _ => {
+                self.expr(node, ASSIGN);
+                self.out.push(';');
+            }
+        }
+    }
+
+    // body of a control statement, blocks open on the same line
+    fn body(&mut self, node: &Node) {
+        if is_block(node) {
+            self.out.push(' ');
+            self.stmt_inline(node);
+        } else {
+            self.out.push('\n');
+            self.depth += 1;
+            self.indent();
+            self.stmt_inline(node);
+            self.depth -= 1;
+        }
+    }
+
+    fn nested(&mut self, node: &Node) {
+        self.depth += 1;
+        self.stmt(node);
+        self.depth -= 1;
+    }
+
+    // print the expression, in parentheses when it binds looser than `min`
+    fn expr(&mut self, node: &Node, min: u8) {
+        let prec = precedence(node);
+        if prec < min {
+            self.out.push('(');
+        }
+        match &node.kind {
+            NodeKind::Num(n) => self.out.push_str(&n.to_string()),
+            NodeKind::LocalVar(name, _) => self.out.push_str(name),
+            NodeKind::Func(name, args) => {
+                self.out.push_str(name);
+                self.out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(arg, ASSIGN);
+                }
+                self.out.push(')');
+            }
+            NodeKind::Cast => {
+                let ty = node.ty.unwrap_or(Type::Long);
+                self.out.push_str(&format!("({ty})"));
+                self.child(&node.lhs, UNARY);
+            }
+            NodeKind::Sub if is_negation(node) => {
+                self.out.push('-');
+                self.child(&node.rhs, PRIMARY);
+            }
+            // assignment groups to the right, the other operators to the left
+            NodeKind::Assignment => {
+                self.child(&node.lhs, PRIMARY);
+                self.out.push_str(" = ");
+                self.child(&node.rhs, ASSIGN);
+            }
+            kind => {
+                self.child(&node.lhs, prec);
+                self.out.push_str(&format!(" {} ", operator(kind)));
+                self.child(&node.rhs, prec + 1);
+            }
+        }
+        if prec < min {
+            self.out.push(')');
+        }
+    }
+
+    fn child(&mut self, node: &Option<Box<Node>>, min: u8) {
+        if let Some(node) = node {
+            self.expr(node, min);
+        }
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+}
+
+fn is_block(node: &Node) -> bool {
+    matches!(node.kind, NodeKind::Block(_))
+}
+
+fn precedence(node: &Node) -> u8 {
+    match &node.kind {
+        NodeKind::Assignment => ASSIGN,
+        NodeKind::Equal | NodeKind::NotEqual => EQUALITY,
+        NodeKind::LessThan
+        | NodeKind::LessThanOrEqual
+        | NodeKind::GreaterThan
+        | NodeKind::GreaterThanOrEqual => RELATIONAL,
+        NodeKind::Sub if is_negation(node) => UNARY,
+        NodeKind::Add | NodeKind::Sub => ADD,
+        NodeKind::Mul | NodeKind::Div => MUL,
+        NodeKind::Cast => UNARY,
+        _ => PRIMARY,
+    }
+}
+
+fn operator(kind: &NodeKind) -> &'static str {
+    match kind {
+        NodeKind::Add => "+",
+        NodeKind::Sub => "-",
+        NodeKind::Mul => "*",
+        NodeKind::Div => "/",
+        NodeKind::Equal => "==",
+        NodeKind::NotEqual => "!=",
+        NodeKind::LessThan => "<",
+        NodeKind::LessThanOrEqual => "<=",
+        NodeKind::GreaterThan => ">",
+        NodeKind::GreaterThanOrEqual => ">=",
+        _ => "?",
+    }
+}
+
+// unary minus is parsed as a subtraction from 0
+fn is_negation(node: &Node) -> bool {
+    node.kind == NodeKind::Sub && node.lhs.as_ref().and_then(|lhs| lhs.num()) == Some(0)
+}
+
+// whether an else after the statement would belong to an if inside it
+fn dangles(node: &Node) -> bool {
+    match &node.kind {
+        NodeKind::If => match node.rhs.as_deref() {
+            Some(Node {
+                kind: NodeKind::Else,
+                rhs,
+                ..
+            }) => rhs.as_deref().is_some_and(dangles),
+            _ => true,
+        },
+        NodeKind::While => node.rhs.as_deref().is_some_and(dangles),
+        NodeKind::For => node.for_parts().is_some_and(|parts| dangles(parts.body)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::print;
+    use crate::{
+        ast::{Node, NodeKind},
+        parser::Parser,
+    };
+
+    fn parse(src: &str) -> Vec<Node> {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        parser.nodes
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("1+2*3;", "1 + 2 * 3;\n"),
+        case("(1+2)*3;", "(1 + 2) * 3;\n"),
+        case("1-(2-3);", "1 - (2 - 3);\n"),
+        case("(1-2)-3;", "1 - 2 - 3;\n"),
+        case("a=b=(1==2)<3;", "a = b = (1 == 2) < 3;\n"),
+        case("1 == (2 == 3);", "1 == (2 == 3);\n"),
+        case("a > b + 1;", "b + 1 < a;\n"),
+        case("-a*-(b+1);", "-a * -(b + 1);\n"),
+        case("0 - 7 / 2;", "-(7 / 2);\n"),
+        case("-(-1);", "-(-1);\n"),
+        case("foo(1, 2);", "foo(1, 2);\n"),
+        case(
+            "int add(int a, unsigned long b); void f(void); long g();",
+            "int add(int, unsigned long);\nvoid f(void);\nlong g();\n"
+        ),
+        case("unsigned char c = 255; int d;", "unsigned char c = 255;\nint d;\n"),
+        case(
+            "if (a) return 1; else if (b) return 2; else { c = 3; }",
+            "if (a)\n    return 1;\nelse if (b)\n    return 2;\nelse {\n    c = 3;\n}\n"
+        ),
+        case("if (a) { b; } else c;", "if (a) {\n    b;\n} else\n    c;\n"),
+        case("for (;; a) {}", "for (;; a) {}\n"),
+        case(
+            "for (i = 0; i < 3;) i = i + 1;",
+            "for (i = 0; i < 3;)\n    i = i + 1;\n"
+        ),
+        case(
+            "for (i = 0; i < 3; i = i + 1) { while (1) a; }",
+            "for (i = 0; i < 3; i = i + 1) {\n    while (1)\n        a;\n}\n"
+        ),
+        case("{ { 1; } }", "{\n    {\n        1;\n    }\n}\n")
+    )]
+    fn test_print(input: &str, expect: &str) {
+        assert_eq!(expect, print(&parse(input)));
+    }
+
+    // parse, print and parse again gives the same tree, and printing that
+    // gives the same text
+    #[rstest(
+        input,
+        case("a = 3; b = a * (a + 2) - 4 / (a - 1); return b + (a == 3);"),
+        case("a = 5; if (a > 3) return a * 2; else return 0 - 1;"),
+        case("b = 0; for (a = 0; a < 10; a = a + 1) b = b + a; return b;"),
+        case("a = 0; while (a < 5) { a = a + 1; if (a == 3) return a; } a;"),
+        case("for (; a < 3; a = a + 1) if (a) if (b) c; else d; else e;"),
+        case("x = -1 - -2 * -(3 / -4); y = x >= 2 != (x <= 3) == 1; z = (y = 2) + 1;"),
+        case("int add(int a, int b); long x = add(1, 2); return add(3, 4) + x;"),
+        case("unsigned a = 0; char c = 200; unsigned long n = 7; if (a - 1 > 5) return n / 2 + c; return c < 0;"),
+        case("{} { a; } if (1) {} else {} while (0) {} for (;;) {}")
+    )]
+    fn test_print_round_trip(input: &str) {
+        let nodes = parse(input);
+        let src = print(&nodes);
+        assert_eq!(nodes, parse(&src), "{src}");
+        assert_eq!(src, print(&parse(&src)));
+    }
+
+    #[test]
+    fn test_print_dangling_else() {
+        // the parser gives the else to the inner if, so only built trees need braces
+        let inner = parse("if (b) c;").remove(0);
+        let other = parse("d;").remove(0);
+        let node = Node::new(
+            NodeKind::If,
+            parse("a;").pop().map(Box::new),
+            Some(Box::new(Node::new(
+                NodeKind::Else,
+                Some(Box::new(inner)),
+                Some(Box::new(other)),
+            ))),
+        );
+        assert_eq!(
+            "if (a) {\n    if (b)\n        c;\n} else\n    d;\n",
+            print(&[node])
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("{0}: '{1}' file not found")]
+    IncludeNotFound(String, String),
+    #[error("{0}: #include nested too deeply")]
+    IncludeTooDeep(String),
+    #[error("{0}: invalid preprocessing directive #{1}")]
+    UnknownDirective(String, String),
+    #[error("{0}: #{1} without #if")]
+    UnbalancedConditional(String, String),
+    #[error("{0}: unterminated conditional directive")]
+    UnterminatedConditional(String),
+    #[error("{0}: function-like macro {1} is not supported")]
+    FunctionLikeMacro(String, String),
+    #[error("{0}: invalid #{1} directive")]
+    InvalidDirective(String, String),
+    #[error("{0}: invalid #if expression")]
+    InvalidExpression(String),
+    #[error("unterminated comment in {0}")]
+    UnterminatedComment(String),
+}
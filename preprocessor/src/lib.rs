@@ -0,0 +1,550 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub use error::Error;
+
+mod error;
+
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+/*
+supported directives
+    #include "file" | <file>
+    #define NAME value?
+    #undef NAME
+    #if expr | #ifdef NAME | #ifndef NAME
+    #elif expr
+    #else
+    #endif
+ */
+
+#[derive(Debug, Default)]
+pub struct Preprocessor {
+    include_dirs: Vec<PathBuf>,
+    macros: HashMap<String, String>,
+    depth: usize,
+}
+
+// state of one #if ... #endif group
+#[derive(Debug)]
+struct Conditional {
+    // lines of the enclosing group are emitted
+    parent_active: bool,
+    // lines of the current branch are emitted
+    active: bool,
+    // one of the branches has already been taken
+    taken: bool,
+}
+
+impl Preprocessor {
+    pub fn new(include_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            include_dirs,
+            ..Default::default()
+        }
+    }
+
+    /// Define a macro from a command line argument, `NAME` or `NAME=value`.
+    pub fn define_arg(&mut self, arg: &str) {
+        match arg.split_once('=') {
+            Some((name, value)) => self.define(name, value),
+            None => self.define(arg, "1"),
+        }
+    }
+
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.macros.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn undef(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    pub fn process_file(&mut self, path: &Path) -> Result<String, Error> {
+        let src = fs::read_to_string(path).map_err(|e| Error::Io(path.display().to_string(), e))?;
+        self.process(&src, path)
+    }
+
+    /// Preprocess `src`, which was read from `path`.
+    ///
+    /// Line numbers are kept by emitting an empty line for every directive and skipped line.
+    pub fn process(&mut self, src: &str, path: &Path) -> Result<String, Error> {
+        let name = path.display().to_string();
+        let src = strip_comments(&src.replace("\\\n", ""), &name)?;
+        let mut out = String::new();
+        let mut conds: Vec<Conditional> = Vec::new();
+
+        for (i, line) in src.lines().enumerate() {
+            let loc = format!("{name}:{}", i + 1);
+            let active = conds.last().map(|c| c.active).unwrap_or(true);
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    out += &self.expand(line, &mut HashSet::new());
+                }
+                out.push('\n');
+                continue;
+            };
+            let directive = directive.trim();
+            let (keyword, rest) = match directive.find(|c: char| !is_ident_char(c)) {
+                Some(n) => (&directive[..n], directive[n..].trim()),
+                None => (directive, ""),
+            };
+
+            match keyword {
+                "ifdef" | "ifndef" | "if" => {
+                    let cond = if !active {
+                        false
+                    } else if keyword == "if" {
+                        self.eval(rest, &loc)? != 0
+                    } else {
+                        let name = macro_name(rest, keyword, &loc)?;
+                        self.macros.contains_key(name) == (keyword == "ifdef")
+                    };
+                    conds.push(Conditional {
+                        parent_active: active,
+                        active: cond,
+                        taken: cond,
+                    });
+                }
+                "elif" => {
+                    let Some(c) = conds.last() else {
+                        return Err(Error::UnbalancedConditional(loc, keyword.to_string()));
+                    };
+                    let cond = c.parent_active && !c.taken && self.eval(rest, &loc)? != 0;
+                    let c = conds.last_mut().unwrap();
+                    c.active = cond;
+                    c.taken |= cond;
+                }
+                "else" => {
+                    let Some(c) = conds.last_mut() else {
+                        return Err(Error::UnbalancedConditional(loc, keyword.to_string()));
+                    };
+                    c.active = c.parent_active && !c.taken;
+                    c.taken = true;
+                }
+                "endif" => {
+                    if conds.pop().is_none() {
+                        return Err(Error::UnbalancedConditional(loc, keyword.to_string()));
+                    }
+                }
+                _ if !active => {}
+                "define" => {
+                    let n = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+                    let (name, value) = rest.split_at(n);
+                    if name.is_empty() {
+                        return Err(Error::InvalidDirective(loc, keyword.to_string()));
+                    }
+                    if value.starts_with('(') {
+                        return Err(Error::FunctionLikeMacro(loc, name.to_string()));
+                    }
+                    self.define(name, value.trim());
+                }
+                "undef" => {
+                    let name = macro_name(rest, keyword, &loc)?;
+                    self.undef(name);
+                }
+                "include" => {
+                    out += &self.include(rest, path, &loc)?;
+                }
+                // null directive
+                "" => {}
+                _ => return Err(Error::UnknownDirective(loc, keyword.to_string())),
+            }
+            out.push('\n');
+        }
+
+        if !conds.is_empty() {
+            return Err(Error::UnterminatedConditional(name));
+        }
+        Ok(out)
+    }
+
+    fn include(&mut self, arg: &str, path: &Path, loc: &str) -> Result<String, Error> {
+        let (file, quoted) =
+            if let Some(s) = arg.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                (s, true)
+            } else if let Some(s) = arg.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+                (s, false)
+            } else {
+                return Err(Error::InvalidDirective(
+                    loc.to_string(),
+                    "include".to_string(),
+                ));
+            };
+
+        // "file" is searched next to the including file first
+        let current = path.parent().map(|p| p.to_path_buf());
+        let found = quoted
+            .then_some(current)
+            .flatten()
+            .into_iter()
+            .chain(self.include_dirs.iter().cloned())
+            .map(|dir| dir.join(file))
+            .find(|p| p.is_file())
+            .ok_or_else(|| Error::IncludeNotFound(loc.to_string(), file.to_string()))?;
+
+        if self.depth >= MAX_INCLUDE_DEPTH {
+            return Err(Error::IncludeTooDeep(loc.to_string()));
+        }
+        self.depth += 1;
+        let res = self.process_file(&found);
+        self.depth -= 1;
+        // the trailing newline is added by the caller
+        res.map(|s| s.trim_end_matches('\n').to_string())
+    }
+
+    // replace macros in a line, `expanding` holds the macros being expanded to stop recursion
+    fn expand(&self, line: &str, expanding: &mut HashSet<String>) -> String {
+        let mut out = String::new();
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if c.is_ascii_alphabetic() || c == '_' {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.next_if(|(_, c)| is_ident_char(*c)) {
+                    end = i + c.len_utf8();
+                }
+                let ident = &line[start..end];
+                match self.macros.get(ident) {
+                    Some(value) if !expanding.contains(ident) => {
+                        expanding.insert(ident.to_string());
+                        out += &self.expand(value, expanding);
+                        expanding.remove(ident);
+                    }
+                    _ => out += ident,
+                }
+            } else if c.is_ascii_digit() {
+                // keep suffixes of numbers like 1U away from macros
+                out.push(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_ident_char(*c)) {
+                    out.push(c);
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn eval(&self, expr: &str, loc: &str) -> Result<i64, Error> {
+        // defined NAME and defined(NAME) have to be resolved before macro expansion
+        let mut resolved = String::new();
+        let mut rest = expr;
+        while let Some(n) = rest.find("defined") {
+            resolved += &rest[..n];
+            let after = rest[n + "defined".len()..].trim_start();
+            let (name, remain) = if let Some(inner) = after.strip_prefix('(') {
+                let close = inner
+                    .find(')')
+                    .ok_or_else(|| Error::InvalidExpression(loc.to_string()))?;
+                (inner[..close].trim(), &inner[close + 1..])
+            } else {
+                let end = after
+                    .find(|c: char| !is_ident_char(c))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            };
+            if name.is_empty() {
+                return Err(Error::InvalidExpression(loc.to_string()));
+            }
+            resolved += if self.macros.contains_key(name) {
+                " 1 "
+            } else {
+                " 0 "
+            };
+            rest = remain;
+        }
+        resolved += rest;
+
+        let expanded = self.expand(&resolved, &mut HashSet::new());
+        let mut parser = ExprParser {
+            chars: expanded.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.logical_or();
+        parser.skip_whitespace();
+        match value {
+            Some(v) if parser.pos == parser.chars.len() => Ok(v),
+            _ => Err(Error::InvalidExpression(loc.to_string())),
+        }
+    }
+}
+
+/*
+expr       = logical_or
+logical_or = logical_and ("||" logical_and)*
+logical_and = equality ("&&" equality)*
+equality   = relational ("==" relational | "!=" relational)*
+relational = add ("<" add | "<=" add | ">" add | ">=" add)*
+add        = mul ("+" mul | "-" mul)*
+mul        = unary ("*" unary | "/" unary)*
+unary      = ("+" | "-" | "!")? primary
+primary    = num | ident | "(" expr ")"
+ */
+struct ExprParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn logical_or(&mut self) -> Option<i64> {
+        let mut v = self.logical_and()?;
+        while self.consume("||") {
+            let rhs = self.logical_and()?;
+            v = (v != 0 || rhs != 0) as i64;
+        }
+        Some(v)
+    }
+
+    fn logical_and(&mut self) -> Option<i64> {
+        let mut v = self.equality()?;
+        while self.consume("&&") {
+            let rhs = self.equality()?;
+            v = (v != 0 && rhs != 0) as i64;
+        }
+        Some(v)
+    }
+
+    fn equality(&mut self) -> Option<i64> {
+        let mut v = self.relational()?;
+        loop {
+            if self.consume("==") {
+                v = (v == self.relational()?) as i64;
+            } else if self.consume("!=") {
+                v = (v != self.relational()?) as i64;
+            } else {
+                return Some(v);
+            }
+        }
+    }
+
+    fn relational(&mut self) -> Option<i64> {
+        let mut v = self.add()?;
+        loop {
+            if self.consume("<=") {
+                v = (v <= self.add()?) as i64;
+            } else if self.consume(">=") {
+                v = (v >= self.add()?) as i64;
+            } else if self.consume("<") {
+                v = (v < self.add()?) as i64;
+            } else if self.consume(">") {
+                v = (v > self.add()?) as i64;
+            } else {
+                return Some(v);
+            }
+        }
+    }
+
+    fn add(&mut self) -> Option<i64> {
+        let mut v = self.mul()?;
+        loop {
+            if self.consume("+") {
+                v = v.wrapping_add(self.mul()?);
+            } else if self.consume("-") {
+                v = v.wrapping_sub(self.mul()?);
+            } else {
+                return Some(v);
+            }
+        }
+    }
+
+    fn mul(&mut self) -> Option<i64> {
+        let mut v = self.unary()?;
+        loop {
+            if self.consume("*") {
+                v = v.wrapping_mul(self.unary()?);
+            } else if self.consume("/") {
+                v = v.checked_div(self.unary()?)?;
+            } else {
+                return Some(v);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<i64> {
+        if self.consume("+") {
+            self.primary()
+        } else if self.consume("-") {
+            Some(self.primary()?.wrapping_neg())
+        } else if self.consume("!") {
+            Some((self.primary()? == 0) as i64)
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Option<i64> {
+        if self.consume("(") {
+            let v = self.logical_or()?;
+            return self.consume(")").then_some(v);
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.chars.len() && is_ident_char(self.chars[self.pos]) {
+            self.pos += 1;
+        }
+        let word: String = self.chars[start..self.pos].iter().collect();
+        if word.is_empty() {
+            None
+        } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+            word.trim_end_matches(['u', 'U', 'l', 'L']).parse().ok()
+        } else {
+            // identifiers left after macro expansion evaluate to 0
+            Some(0)
+        }
+    }
+
+    fn consume(&mut self, op: &str) -> bool {
+        self.skip_whitespace();
+        let end = self.pos + op.len();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(op.chars()) {
+            // do not split "<=" into "<" and "="
+            if op.len() == 1 && matches!(op, "<" | ">" | "!") && self.chars.get(end) == Some(&'=') {
+                return false;
+            }
+            self.pos = end;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+}
+
+fn macro_name<'a>(rest: &'a str, keyword: &str, loc: &str) -> Result<&'a str, Error> {
+    if !rest.is_empty() && rest.chars().all(is_ident_char) {
+        Ok(rest)
+    } else {
+        Err(Error::InvalidDirective(
+            loc.to_string(),
+            keyword.to_string(),
+        ))
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// replace comments with a space while keeping the newlines inside them
+fn strip_comments(src: &str, name: &str) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                out.push(' ');
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                loop {
+                    match chars.next() {
+                        Some('*') if chars.next_if_eq(&'/').is_some() => break,
+                        Some('\n') => out.push('\n'),
+                        Some(_) => {}
+                        None => return Err(Error::UnterminatedComment(name.to_string())),
+                    }
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use rstest::rstest;
+
+    use super::Preprocessor;
+
+    #[rstest(
+        input,
+        expect,
+        case("a = 1;", "a = 1;\n"),
+        case("#define N 10\nreturn N;", "\nreturn 10;\n"),
+        case("#define N 10\n#define M N + N\nM;", "\n\n10 + 10;\n"),
+        case("#define N N\nN;", "\nN;\n"),
+        case("#define N 1\nNN + N1 + N;", "\nNN + N1 + 1;\n"),
+        case("#define N 1\n#undef N\nN;", "\n\nN;\n"),
+        case(
+            "a = 1; // comment\nb = 2; /* multi\nline */ c = 3;",
+            "a = 1;  \nb = 2; \n  c = 3;\n"
+        ),
+        case("#ifdef X\n1;\n#else\n2;\n#endif", "\n\n\n2;\n\n"),
+        case("#define X\n#ifdef X\n1;\n#else\n2;\n#endif", "\n\n1;\n\n\n\n"),
+        case("#ifndef X\n1;\n#endif", "\n1;\n\n"),
+        case(
+            "#if 0\n1;\n#elif 1 + 1 == 2\n2;\n#else\n3;\n#endif",
+            "\n\n\n2;\n\n\n\n"
+        ),
+        case(
+            "#define A 3\n#if defined(A) && A >= 2 && !defined B\n1;\n#endif",
+            "\n\n1;\n\n"
+        ),
+        case("#if 1\n#if 0\n1;\n#else\n2;\n#endif\n#endif", "\n\n\n\n2;\n\n\n"),
+        case("#if 0\n#if 1\n1;\n#endif\n#else\n2;\n#endif", "\n\n\n\n\n2;\n\n"),
+        case("a = 1 + \\\n2;", "a = 1 + 2;\n")
+    )]
+    fn test_preprocessor_process(input: &str, expect: &str) {
+        let mut pp = Preprocessor::default();
+        let res = pp.process(input, Path::new("test.c")).unwrap();
+        assert_eq!(expect, res);
+    }
+
+    #[rstest(
+        input,
+        case("#endif"),
+        case("#else"),
+        case("#ifdef X\n1;"),
+        case("#define F(x) x"),
+        case("#pragma once"),
+        case("#include <missing.h>"),
+        case("#if 1 +\n#endif"),
+        case("/* unterminated")
+    )]
+    fn test_preprocessor_process_error(input: &str) {
+        let mut pp = Preprocessor::default();
+        assert!(pp.process(input, Path::new("test.c")).is_err());
+    }
+
+    #[test]
+    fn test_preprocessor_command_line_macros() {
+        let mut pp = Preprocessor::default();
+        pp.define_arg("A");
+        pp.define_arg("B=42");
+        pp.define_arg("C=1");
+        pp.undef("C");
+        let res = pp.process("A; B; C;", Path::new("test.c")).unwrap();
+        assert_eq!("1; 42; C;\n", res);
+    }
+
+    #[test]
+    fn test_preprocessor_include() {
+        let dir = std::env::temp_dir().join(format!("teruc-pp-test-{}", std::process::id()));
+        let inc = dir.join("inc");
+        fs::create_dir_all(&inc).unwrap();
+        fs::write(dir.join("local.h"), "#define LOCAL 1\n").unwrap();
+        fs::write(inc.join("sys.h"), "#define SYS 2\nsys;\n").unwrap();
+        fs::write(
+            dir.join("main.c"),
+            "#include \"local.h\"\n#include <sys.h>\nLOCAL + SYS;\n",
+        )
+        .unwrap();
+
+        let mut pp = Preprocessor::new(vec![inc]);
+        let res = pp.process_file(&dir.join("main.c"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!("\n\nsys;\n1 + 2;\n", res.unwrap());
+    }
+}
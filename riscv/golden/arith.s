@@ -0,0 +1,35 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -16
+.Lmain.0:
+	li t0, 3
+	sd t0, 0(sp)
+	ld t2, 0(sp)
+	ld t3, 0(sp)
+	addi t4, t3, 2
+	mul t3, t2, t4
+	ld t2, 0(sp)
+	addi t4, t2, -1
+	li t0, 4
+	div t2, t0, t4
+	sub t4, t3, t2
+	sd t4, 8(sp)
+	ld t2, 8(sp)
+	ld t3, 0(sp)
+	li t1, 3
+	xor t4, t3, t1
+	seqz t4, t4
+	mv t3, t4
+	add t4, t2, t3
+	sext.w t2, t4
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
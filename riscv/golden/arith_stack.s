@@ -0,0 +1,61 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -128
+.Lmain.0:
+	li t0, 3
+	sd t0, 0(sp)
+	ld t0, 0(sp)
+	sd t0, 16(sp)
+	ld t0, 0(sp)
+	sd t0, 24(sp)
+	ld t0, 24(sp)
+	addi t0, t0, 2
+	sd t0, 32(sp)
+	ld t0, 16(sp)
+	ld t1, 32(sp)
+	mul t0, t0, t1
+	sd t0, 40(sp)
+	ld t0, 0(sp)
+	sd t0, 48(sp)
+	ld t0, 48(sp)
+	addi t0, t0, -1
+	sd t0, 56(sp)
+	li t0, 4
+	ld t1, 56(sp)
+	div t0, t0, t1
+	sd t0, 64(sp)
+	ld t0, 40(sp)
+	ld t1, 64(sp)
+	sub t0, t0, t1
+	sd t0, 72(sp)
+	ld t0, 72(sp)
+	sd t0, 8(sp)
+	ld t0, 8(sp)
+	sd t0, 80(sp)
+	ld t0, 0(sp)
+	sd t0, 88(sp)
+	ld t0, 88(sp)
+	li t1, 3
+	xor t0, t0, t1
+	seqz t0, t0
+	sd t0, 96(sp)
+	ld t0, 96(sp)
+	sd t0, 104(sp)
+	ld t0, 80(sp)
+	ld t1, 104(sp)
+	add t0, t0, t1
+	sd t0, 112(sp)
+	ld t0, 112(sp)
+	sext.w t0, t0
+	sd t0, 120(sp)
+	ld a0, 120(sp)
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
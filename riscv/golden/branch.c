@@ -0,0 +1,5 @@
+a = 5;
+if (a > 3)
+    return a * 2;
+else
+    return 0 - 1;
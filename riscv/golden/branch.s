@@ -0,0 +1,33 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -16
+.Lmain.0:
+	li t0, 5
+	sd t0, 0(sp)
+	ld t2, 0(sp)
+	li t0, 3
+	bge t0, t2, .Lmain.3
+.Lmain.1:
+	ld t2, 0(sp)
+	li t1, 2
+	mul t3, t2, t1
+	sext.w t2, t3
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
+.Lmain.3:
+	addi t2, zero, -1
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
@@ -0,0 +1,27 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -16
+.Lmain.0:
+	li a0, 1
+	li a1, 2
+	call add
+	mv t2, a0
+	sd t2, 0(sp)
+	li a0, 3
+	li a1, 4
+	call add
+	mv t2, a0
+	ld t3, 0(sp)
+	add t4, t2, t3
+	sext.w t2, t4
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
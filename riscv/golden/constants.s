@@ -0,0 +1,24 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -16
+.Lmain.0:
+	li t0, 81985529216486895
+	sd t0, 0(sp)
+	li t1, 70000
+	sub t2, zero, t1
+	sd t2, 8(sp)
+	ld t2, 8(sp)
+	ld t3, 0(sp)
+	slt t4, t2, t3
+	mv t2, t4
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
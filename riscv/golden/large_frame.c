@@ -0,0 +1,258 @@
+long v0 = 0;
+long v1 = 1;
+long v2 = 2;
+long v3 = 3;
+long v4 = 4;
+long v5 = 5;
+long v6 = 6;
+long v7 = 7;
+long v8 = 8;
+long v9 = 9;
+long v10 = 10;
+long v11 = 11;
+long v12 = 12;
+long v13 = 13;
+long v14 = 14;
+long v15 = 15;
+long v16 = 16;
+long v17 = 17;
+long v18 = 18;
+long v19 = 19;
+long v20 = 20;
+long v21 = 21;
+long v22 = 22;
+long v23 = 23;
+long v24 = 24;
+long v25 = 25;
+long v26 = 26;
+long v27 = 27;
+long v28 = 28;
+long v29 = 29;
+long v30 = 30;
+long v31 = 31;
+long v32 = 32;
+long v33 = 33;
+long v34 = 34;
+long v35 = 35;
+long v36 = 36;
+long v37 = 37;
+long v38 = 38;
+long v39 = 39;
+long v40 = 40;
+long v41 = 41;
+long v42 = 42;
+long v43 = 43;
+long v44 = 44;
+long v45 = 45;
+long v46 = 46;
+long v47 = 47;
+long v48 = 48;
+long v49 = 49;
+long v50 = 50;
+long v51 = 51;
+long v52 = 52;
+long v53 = 53;
+long v54 = 54;
+long v55 = 55;
+long v56 = 56;
+long v57 = 57;
+long v58 = 58;
+long v59 = 59;
+long v60 = 60;
+long v61 = 61;
+long v62 = 62;
+long v63 = 63;
+long v64 = 64;
+long v65 = 65;
+long v66 = 66;
+long v67 = 67;
+long v68 = 68;
+long v69 = 69;
+long v70 = 70;
+long v71 = 71;
+long v72 = 72;
+long v73 = 73;
+long v74 = 74;
+long v75 = 75;
+long v76 = 76;
+long v77 = 77;
+long v78 = 78;
+long v79 = 79;
+long v80 = 80;
+long v81 = 81;
+long v82 = 82;
+long v83 = 83;
+long v84 = 84;
+long v85 = 85;
+long v86 = 86;
+long v87 = 87;
+long v88 = 88;
+long v89 = 89;
+long v90 = 90;
+long v91 = 91;
+long v92 = 92;
+long v93 = 93;
+long v94 = 94;
+long v95 = 95;
+long v96 = 96;
+long v97 = 97;
+long v98 = 98;
+long v99 = 99;
+long v100 = 100;
+long v101 = 101;
+long v102 = 102;
+long v103 = 103;
+long v104 = 104;
+long v105 = 105;
+long v106 = 106;
+long v107 = 107;
+long v108 = 108;
+long v109 = 109;
+long v110 = 110;
+long v111 = 111;
+long v112 = 112;
+long v113 = 113;
+long v114 = 114;
+long v115 = 115;
+long v116 = 116;
+long v117 = 117;
+long v118 = 118;
+long v119 = 119;
+long v120 = 120;
+long v121 = 121;
+long v122 = 122;
+long v123 = 123;
+long v124 = 124;
+long v125 = 125;
+long v126 = 126;
+long v127 = 127;
+long v128 = 128;
+long v129 = 129;
+long v130 = 130;
+long v131 = 131;
+long v132 = 132;
+long v133 = 133;
+long v134 = 134;
+long v135 = 135;
+long v136 = 136;
+long v137 = 137;
+long v138 = 138;
+long v139 = 139;
+long v140 = 140;
+long v141 = 141;
+long v142 = 142;
+long v143 = 143;
+long v144 = 144;
+long v145 = 145;
+long v146 = 146;
+long v147 = 147;
+long v148 = 148;
+long v149 = 149;
+long v150 = 150;
+long v151 = 151;
+long v152 = 152;
+long v153 = 153;
+long v154 = 154;
+long v155 = 155;
+long v156 = 156;
+long v157 = 157;
+long v158 = 158;
+long v159 = 159;
+long v160 = 160;
+long v161 = 161;
+long v162 = 162;
+long v163 = 163;
+long v164 = 164;
+long v165 = 165;
+long v166 = 166;
+long v167 = 167;
+long v168 = 168;
+long v169 = 169;
+long v170 = 170;
+long v171 = 171;
+long v172 = 172;
+long v173 = 173;
+long v174 = 174;
+long v175 = 175;
+long v176 = 176;
+long v177 = 177;
+long v178 = 178;
+long v179 = 179;
+long v180 = 180;
+long v181 = 181;
+long v182 = 182;
+long v183 = 183;
+long v184 = 184;
+long v185 = 185;
+long v186 = 186;
+long v187 = 187;
+long v188 = 188;
+long v189 = 189;
+long v190 = 190;
+long v191 = 191;
+long v192 = 192;
+long v193 = 193;
+long v194 = 194;
+long v195 = 195;
+long v196 = 196;
+long v197 = 197;
+long v198 = 198;
+long v199 = 199;
+long v200 = 200;
+long v201 = 201;
+long v202 = 202;
+long v203 = 203;
+long v204 = 204;
+long v205 = 205;
+long v206 = 206;
+long v207 = 207;
+long v208 = 208;
+long v209 = 209;
+long v210 = 210;
+long v211 = 211;
+long v212 = 212;
+long v213 = 213;
+long v214 = 214;
+long v215 = 215;
+long v216 = 216;
+long v217 = 217;
+long v218 = 218;
+long v219 = 219;
+long v220 = 220;
+long v221 = 221;
+long v222 = 222;
+long v223 = 223;
+long v224 = 224;
+long v225 = 225;
+long v226 = 226;
+long v227 = 227;
+long v228 = 228;
+long v229 = 229;
+long v230 = 230;
+long v231 = 231;
+long v232 = 232;
+long v233 = 233;
+long v234 = 234;
+long v235 = 235;
+long v236 = 236;
+long v237 = 237;
+long v238 = 238;
+long v239 = 239;
+long v240 = 240;
+long v241 = 241;
+long v242 = 242;
+long v243 = 243;
+long v244 = 244;
+long v245 = 245;
+long v246 = 246;
+long v247 = 247;
+long v248 = 248;
+long v249 = 249;
+long v250 = 250;
+long v251 = 251;
+long v252 = 252;
+long v253 = 253;
+long v254 = 254;
+long v255 = 255;
+long v256 = 256;
+return v256 - v0;
@@ -0,0 +1,537 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	li t0, -2064
+	add sp, sp, t0
+.Lmain.0:
+	sd zero, 0(sp)
+	li t0, 1
+	sd t0, 8(sp)
+	li t0, 2
+	sd t0, 16(sp)
+	li t0, 3
+	sd t0, 24(sp)
+	li t0, 4
+	sd t0, 32(sp)
+	li t0, 5
+	sd t0, 40(sp)
+	li t0, 6
+	sd t0, 48(sp)
+	li t0, 7
+	sd t0, 56(sp)
+	li t0, 8
+	sd t0, 64(sp)
+	li t0, 9
+	sd t0, 72(sp)
+	li t0, 10
+	sd t0, 80(sp)
+	li t0, 11
+	sd t0, 88(sp)
+	li t0, 12
+	sd t0, 96(sp)
+	li t0, 13
+	sd t0, 104(sp)
+	li t0, 14
+	sd t0, 112(sp)
+	li t0, 15
+	sd t0, 120(sp)
+	li t0, 16
+	sd t0, 128(sp)
+	li t0, 17
+	sd t0, 136(sp)
+	li t0, 18
+	sd t0, 144(sp)
+	li t0, 19
+	sd t0, 152(sp)
+	li t0, 20
+	sd t0, 160(sp)
+	li t0, 21
+	sd t0, 168(sp)
+	li t0, 22
+	sd t0, 176(sp)
+	li t0, 23
+	sd t0, 184(sp)
+	li t0, 24
+	sd t0, 192(sp)
+	li t0, 25
+	sd t0, 200(sp)
+	li t0, 26
+	sd t0, 208(sp)
+	li t0, 27
+	sd t0, 216(sp)
+	li t0, 28
+	sd t0, 224(sp)
+	li t0, 29
+	sd t0, 232(sp)
+	li t0, 30
+	sd t0, 240(sp)
+	li t0, 31
+	sd t0, 248(sp)
+	li t0, 32
+	sd t0, 256(sp)
+	li t0, 33
+	sd t0, 264(sp)
+	li t0, 34
+	sd t0, 272(sp)
+	li t0, 35
+	sd t0, 280(sp)
+	li t0, 36
+	sd t0, 288(sp)
+	li t0, 37
+	sd t0, 296(sp)
+	li t0, 38
+	sd t0, 304(sp)
+	li t0, 39
+	sd t0, 312(sp)
+	li t0, 40
+	sd t0, 320(sp)
+	li t0, 41
+	sd t0, 328(sp)
+	li t0, 42
+	sd t0, 336(sp)
+	li t0, 43
+	sd t0, 344(sp)
+	li t0, 44
+	sd t0, 352(sp)
+	li t0, 45
+	sd t0, 360(sp)
+	li t0, 46
+	sd t0, 368(sp)
+	li t0, 47
+	sd t0, 376(sp)
+	li t0, 48
+	sd t0, 384(sp)
+	li t0, 49
+	sd t0, 392(sp)
+	li t0, 50
+	sd t0, 400(sp)
+	li t0, 51
+	sd t0, 408(sp)
+	li t0, 52
+	sd t0, 416(sp)
+	li t0, 53
+	sd t0, 424(sp)
+	li t0, 54
+	sd t0, 432(sp)
+	li t0, 55
+	sd t0, 440(sp)
+	li t0, 56
+	sd t0, 448(sp)
+	li t0, 57
+	sd t0, 456(sp)
+	li t0, 58
+	sd t0, 464(sp)
+	li t0, 59
+	sd t0, 472(sp)
+	li t0, 60
+	sd t0, 480(sp)
+	li t0, 61
+	sd t0, 488(sp)
+	li t0, 62
+	sd t0, 496(sp)
+	li t0, 63
+	sd t0, 504(sp)
+	li t0, 64
+	sd t0, 512(sp)
+	li t0, 65
+	sd t0, 520(sp)
+	li t0, 66
+	sd t0, 528(sp)
+	li t0, 67
+	sd t0, 536(sp)
+	li t0, 68
+	sd t0, 544(sp)
+	li t0, 69
+	sd t0, 552(sp)
+	li t0, 70
+	sd t0, 560(sp)
+	li t0, 71
+	sd t0, 568(sp)
+	li t0, 72
+	sd t0, 576(sp)
+	li t0, 73
+	sd t0, 584(sp)
+	li t0, 74
+	sd t0, 592(sp)
+	li t0, 75
+	sd t0, 600(sp)
+	li t0, 76
+	sd t0, 608(sp)
+	li t0, 77
+	sd t0, 616(sp)
+	li t0, 78
+	sd t0, 624(sp)
+	li t0, 79
+	sd t0, 632(sp)
+	li t0, 80
+	sd t0, 640(sp)
+	li t0, 81
+	sd t0, 648(sp)
+	li t0, 82
+	sd t0, 656(sp)
+	li t0, 83
+	sd t0, 664(sp)
+	li t0, 84
+	sd t0, 672(sp)
+	li t0, 85
+	sd t0, 680(sp)
+	li t0, 86
+	sd t0, 688(sp)
+	li t0, 87
+	sd t0, 696(sp)
+	li t0, 88
+	sd t0, 704(sp)
+	li t0, 89
+	sd t0, 712(sp)
+	li t0, 90
+	sd t0, 720(sp)
+	li t0, 91
+	sd t0, 728(sp)
+	li t0, 92
+	sd t0, 736(sp)
+	li t0, 93
+	sd t0, 744(sp)
+	li t0, 94
+	sd t0, 752(sp)
+	li t0, 95
+	sd t0, 760(sp)
+	li t0, 96
+	sd t0, 768(sp)
+	li t0, 97
+	sd t0, 776(sp)
+	li t0, 98
+	sd t0, 784(sp)
+	li t0, 99
+	sd t0, 792(sp)
+	li t0, 100
+	sd t0, 800(sp)
+	li t0, 101
+	sd t0, 808(sp)
+	li t0, 102
+	sd t0, 816(sp)
+	li t0, 103
+	sd t0, 824(sp)
+	li t0, 104
+	sd t0, 832(sp)
+	li t0, 105
+	sd t0, 840(sp)
+	li t0, 106
+	sd t0, 848(sp)
+	li t0, 107
+	sd t0, 856(sp)
+	li t0, 108
+	sd t0, 864(sp)
+	li t0, 109
+	sd t0, 872(sp)
+	li t0, 110
+	sd t0, 880(sp)
+	li t0, 111
+	sd t0, 888(sp)
+	li t0, 112
+	sd t0, 896(sp)
+	li t0, 113
+	sd t0, 904(sp)
+	li t0, 114
+	sd t0, 912(sp)
+	li t0, 115
+	sd t0, 920(sp)
+	li t0, 116
+	sd t0, 928(sp)
+	li t0, 117
+	sd t0, 936(sp)
+	li t0, 118
+	sd t0, 944(sp)
+	li t0, 119
+	sd t0, 952(sp)
+	li t0, 120
+	sd t0, 960(sp)
+	li t0, 121
+	sd t0, 968(sp)
+	li t0, 122
+	sd t0, 976(sp)
+	li t0, 123
+	sd t0, 984(sp)
+	li t0, 124
+	sd t0, 992(sp)
+	li t0, 125
+	sd t0, 1000(sp)
+	li t0, 126
+	sd t0, 1008(sp)
+	li t0, 127
+	sd t0, 1016(sp)
+	li t0, 128
+	sd t0, 1024(sp)
+	li t0, 129
+	sd t0, 1032(sp)
+	li t0, 130
+	sd t0, 1040(sp)
+	li t0, 131
+	sd t0, 1048(sp)
+	li t0, 132
+	sd t0, 1056(sp)
+	li t0, 133
+	sd t0, 1064(sp)
+	li t0, 134
+	sd t0, 1072(sp)
+	li t0, 135
+	sd t0, 1080(sp)
+	li t0, 136
+	sd t0, 1088(sp)
+	li t0, 137
+	sd t0, 1096(sp)
+	li t0, 138
+	sd t0, 1104(sp)
+	li t0, 139
+	sd t0, 1112(sp)
+	li t0, 140
+	sd t0, 1120(sp)
+	li t0, 141
+	sd t0, 1128(sp)
+	li t0, 142
+	sd t0, 1136(sp)
+	li t0, 143
+	sd t0, 1144(sp)
+	li t0, 144
+	sd t0, 1152(sp)
+	li t0, 145
+	sd t0, 1160(sp)
+	li t0, 146
+	sd t0, 1168(sp)
+	li t0, 147
+	sd t0, 1176(sp)
+	li t0, 148
+	sd t0, 1184(sp)
+	li t0, 149
+	sd t0, 1192(sp)
+	li t0, 150
+	sd t0, 1200(sp)
+	li t0, 151
+	sd t0, 1208(sp)
+	li t0, 152
+	sd t0, 1216(sp)
+	li t0, 153
+	sd t0, 1224(sp)
+	li t0, 154
+	sd t0, 1232(sp)
+	li t0, 155
+	sd t0, 1240(sp)
+	li t0, 156
+	sd t0, 1248(sp)
+	li t0, 157
+	sd t0, 1256(sp)
+	li t0, 158
+	sd t0, 1264(sp)
+	li t0, 159
+	sd t0, 1272(sp)
+	li t0, 160
+	sd t0, 1280(sp)
+	li t0, 161
+	sd t0, 1288(sp)
+	li t0, 162
+	sd t0, 1296(sp)
+	li t0, 163
+	sd t0, 1304(sp)
+	li t0, 164
+	sd t0, 1312(sp)
+	li t0, 165
+	sd t0, 1320(sp)
+	li t0, 166
+	sd t0, 1328(sp)
+	li t0, 167
+	sd t0, 1336(sp)
+	li t0, 168
+	sd t0, 1344(sp)
+	li t0, 169
+	sd t0, 1352(sp)
+	li t0, 170
+	sd t0, 1360(sp)
+	li t0, 171
+	sd t0, 1368(sp)
+	li t0, 172
+	sd t0, 1376(sp)
+	li t0, 173
+	sd t0, 1384(sp)
+	li t0, 174
+	sd t0, 1392(sp)
+	li t0, 175
+	sd t0, 1400(sp)
+	li t0, 176
+	sd t0, 1408(sp)
+	li t0, 177
+	sd t0, 1416(sp)
+	li t0, 178
+	sd t0, 1424(sp)
+	li t0, 179
+	sd t0, 1432(sp)
+	li t0, 180
+	sd t0, 1440(sp)
+	li t0, 181
+	sd t0, 1448(sp)
+	li t0, 182
+	sd t0, 1456(sp)
+	li t0, 183
+	sd t0, 1464(sp)
+	li t0, 184
+	sd t0, 1472(sp)
+	li t0, 185
+	sd t0, 1480(sp)
+	li t0, 186
+	sd t0, 1488(sp)
+	li t0, 187
+	sd t0, 1496(sp)
+	li t0, 188
+	sd t0, 1504(sp)
+	li t0, 189
+	sd t0, 1512(sp)
+	li t0, 190
+	sd t0, 1520(sp)
+	li t0, 191
+	sd t0, 1528(sp)
+	li t0, 192
+	sd t0, 1536(sp)
+	li t0, 193
+	sd t0, 1544(sp)
+	li t0, 194
+	sd t0, 1552(sp)
+	li t0, 195
+	sd t0, 1560(sp)
+	li t0, 196
+	sd t0, 1568(sp)
+	li t0, 197
+	sd t0, 1576(sp)
+	li t0, 198
+	sd t0, 1584(sp)
+	li t0, 199
+	sd t0, 1592(sp)
+	li t0, 200
+	sd t0, 1600(sp)
+	li t0, 201
+	sd t0, 1608(sp)
+	li t0, 202
+	sd t0, 1616(sp)
+	li t0, 203
+	sd t0, 1624(sp)
+	li t0, 204
+	sd t0, 1632(sp)
+	li t0, 205
+	sd t0, 1640(sp)
+	li t0, 206
+	sd t0, 1648(sp)
+	li t0, 207
+	sd t0, 1656(sp)
+	li t0, 208
+	sd t0, 1664(sp)
+	li t0, 209
+	sd t0, 1672(sp)
+	li t0, 210
+	sd t0, 1680(sp)
+	li t0, 211
+	sd t0, 1688(sp)
+	li t0, 212
+	sd t0, 1696(sp)
+	li t0, 213
+	sd t0, 1704(sp)
+	li t0, 214
+	sd t0, 1712(sp)
+	li t0, 215
+	sd t0, 1720(sp)
+	li t0, 216
+	sd t0, 1728(sp)
+	li t0, 217
+	sd t0, 1736(sp)
+	li t0, 218
+	sd t0, 1744(sp)
+	li t0, 219
+	sd t0, 1752(sp)
+	li t0, 220
+	sd t0, 1760(sp)
+	li t0, 221
+	sd t0, 1768(sp)
+	li t0, 222
+	sd t0, 1776(sp)
+	li t0, 223
+	sd t0, 1784(sp)
+	li t0, 224
+	sd t0, 1792(sp)
+	li t0, 225
+	sd t0, 1800(sp)
+	li t0, 226
+	sd t0, 1808(sp)
+	li t0, 227
+	sd t0, 1816(sp)
+	li t0, 228
+	sd t0, 1824(sp)
+	li t0, 229
+	sd t0, 1832(sp)
+	li t0, 230
+	sd t0, 1840(sp)
+	li t0, 231
+	sd t0, 1848(sp)
+	li t0, 232
+	sd t0, 1856(sp)
+	li t0, 233
+	sd t0, 1864(sp)
+	li t0, 234
+	sd t0, 1872(sp)
+	li t0, 235
+	sd t0, 1880(sp)
+	li t0, 236
+	sd t0, 1888(sp)
+	li t0, 237
+	sd t0, 1896(sp)
+	li t0, 238
+	sd t0, 1904(sp)
+	li t0, 239
+	sd t0, 1912(sp)
+	li t0, 240
+	sd t0, 1920(sp)
+	li t0, 241
+	sd t0, 1928(sp)
+	li t0, 242
+	sd t0, 1936(sp)
+	li t0, 243
+	sd t0, 1944(sp)
+	li t0, 244
+	sd t0, 1952(sp)
+	li t0, 245
+	sd t0, 1960(sp)
+	li t0, 246
+	sd t0, 1968(sp)
+	li t0, 247
+	sd t0, 1976(sp)
+	li t0, 248
+	sd t0, 1984(sp)
+	li t0, 249
+	sd t0, 1992(sp)
+	li t0, 250
+	sd t0, 2000(sp)
+	li t0, 251
+	sd t0, 2008(sp)
+	li t0, 252
+	sd t0, 2016(sp)
+	li t0, 253
+	sd t0, 2024(sp)
+	li t0, 254
+	sd t0, 2032(sp)
+	li t0, 255
+	sd t0, 2040(sp)
+	li t0, 256
+	li t1, 2048
+	add t1, sp, t1
+	sd t0, 0(t1)
+	li t1, 2048
+	add t1, sp, t1
+	ld t2, 0(t1)
+	ld t3, 0(sp)
+	sub t4, t2, t3
+	sext.w t2, t4
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
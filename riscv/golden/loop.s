@@ -0,0 +1,33 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -16
+.Lmain.0:
+	sd zero, 0(sp)
+	sd zero, 8(sp)
+.Lmain.1:
+	ld t2, 8(sp)
+	li t1, 10
+	bge t2, t1, .Lmain.3
+.Lmain.2:
+	ld t2, 0(sp)
+	ld t3, 8(sp)
+	add t4, t2, t3
+	sd t4, 0(sp)
+	ld t2, 8(sp)
+	addi t3, t2, 1
+	sd t3, 8(sp)
+	j .Lmain.1
+.Lmain.3:
+	ld t2, 0(sp)
+	sext.w t3, t2
+	mv a0, t3
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
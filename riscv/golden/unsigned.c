@@ -0,0 +1,6 @@
+unsigned a = 0;
+char c = 200;
+unsigned long n = 7;
+if (a - 1 > 5)
+    return n / 2 + c;
+return c < 0;
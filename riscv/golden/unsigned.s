@@ -0,0 +1,44 @@
+	.text
+	.globl main
+main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	addi s0, sp, 16
+	addi sp, sp, -32
+.Lmain.0:
+	sd zero, 0(sp)
+	li t0, -56
+	sd t0, 8(sp)
+	li t0, 7
+	sd t0, 16(sp)
+	ld t2, 0(sp)
+	addi t3, t2, -1
+	slli t2, t3, 32
+	srli t2, t2, 32
+	li t0, 5
+	bgeu t0, t2, .Lmain.2
+.Lmain.1:
+	ld t2, 16(sp)
+	li t1, 2
+	divu t3, t2, t1
+	ld t2, 8(sp)
+	add t4, t3, t2
+	sext.w t2, t4
+	mv a0, t2
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
+.Lmain.2:
+	ld t2, 8(sp)
+	sext.w t3, t2
+	slt t2, t3, zero
+	mv t3, t2
+	mv a0, t3
+	addi sp, s0, -16
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
@@ -0,0 +1,359 @@
+use std::fmt::{self, Display};
+
+use backend::{Backend, Frame, LoadStore};
+use ir::instr::{BinOp, CmpOp, Operand, Slot, Value};
+
+/// An integer register, printed by its ABI name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reg(&'static str);
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+const A0: Reg = Reg("a0");
+
+// LP64D passes the first eight integer arguments in a0 to a7
+const REGISTERS: [Reg; 8] = [
+    A0,
+    Reg("a1"),
+    Reg("a2"),
+    Reg("a3"),
+    Reg("a4"),
+    Reg("a5"),
+    Reg("a6"),
+    Reg("a7"),
+];
+
+// t0 and t1 stay free as temporaries, s0 is the frame pointer and the argument
+// registers are only written when setting up calls
+const CALLER_SAVED: [Reg; 5] = [Reg("t2"), Reg("t3"), Reg("t4"), Reg("t5"), Reg("t6")];
+const CALLEE_SAVED: [Reg; 11] = [
+    Reg("s1"),
+    Reg("s2"),
+    Reg("s3"),
+    Reg("s4"),
+    Reg("s5"),
+    Reg("s6"),
+    Reg("s7"),
+    Reg("s8"),
+    Reg("s9"),
+    Reg("s10"),
+    Reg("s11"),
+];
+const TMP0: Reg = Reg("t0");
+const TMP1: Reg = Reg("t1");
+
+/// Code generator for 64 bit RISC-V Linux, taking the IR to GNU assembler
+/// syntax for RV64GC.
+#[derive(Debug)]
+pub struct RiscvBackend {
+    out: String,
+}
+
+impl Default for RiscvBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RiscvBackend {
+    pub fn new() -> Self {
+        Self {
+            out: "\t.text\n".to_string(),
+        }
+    }
+
+    // d = l op r as 0 or 1, there is only set if less than
+    fn set(&mut self, op: CmpOp, d: Reg, l: Reg, r: Reg) {
+        match op {
+            CmpOp::Eq | CmpOp::Ne => {
+                let test = if op == CmpOp::Eq { "seqz" } else { "snez" };
+                self.emit(format!("xor {d}, {l}, {r}"));
+                self.emit(format!("{test} {d}, {d}"));
+            }
+            CmpOp::Lt => self.emit(format!("slt {d}, {l}, {r}")),
+            CmpOp::Ult => self.emit(format!("sltu {d}, {l}, {r}")),
+            CmpOp::Gt => self.emit(format!("slt {d}, {r}, {l}")),
+            CmpOp::Ugt => self.emit(format!("sltu {d}, {r}, {l}")),
+            // the negation of the strict comparison
+            CmpOp::Le | CmpOp::Ule | CmpOp::Ge | CmpOp::Uge => {
+                self.set(op.negate(), d, l, r);
+                self.emit(format!("xori {d}, {d}, 1"));
+            }
+        }
+    }
+
+    // load or store the register at a frame offset, building the address in
+    // t1 when the offset does not fit in the immediate, which is free then as
+    // t1 is only ever loaded into
+    fn access(&mut self, op: &str, reg: Reg, offset: i64) {
+        if is_imm12(offset) {
+            self.emit(format!("{op} {reg}, {offset}(sp)"));
+        } else {
+            self.emit(format!("li {TMP1}, {offset}"));
+            self.emit(format!("add {TMP1}, sp, {TMP1}"));
+            self.emit(format!("{op} {reg}, 0({TMP1})"));
+        }
+    }
+
+    fn add_sp(&mut self, n: i64) {
+        if is_imm12(n) {
+            self.emit(format!("addi sp, sp, {n}"));
+        } else {
+            self.emit(format!("li {TMP0}, {n}"));
+            self.emit(format!("add sp, sp, {TMP0}"));
+        }
+    }
+
+    fn emit(&mut self, instr: impl AsRef<str>) {
+        self.out.push('\t');
+        self.out.push_str(instr.as_ref());
+        self.out.push('\n');
+    }
+}
+
+impl LoadStore for RiscvBackend {
+    const SCRATCH: Reg = TMP0;
+
+    fn constant(&mut self, n: i64, tmp: Reg) -> Reg {
+        if n == 0 {
+            return Reg("zero");
+        }
+        self.emit(format!("li {tmp}, {n}"));
+        tmp
+    }
+
+    fn load_offset(&mut self, dst: Reg, offset: i64) {
+        self.access("ld", dst, offset);
+    }
+
+    fn store_offset(&mut self, offset: i64, src: Reg) {
+        self.access("sd", src, offset);
+    }
+
+    fn move_reg(&mut self, dst: Reg, src: Reg) {
+        self.emit(format!("mv {dst}, {src}"));
+    }
+}
+
+impl Backend for RiscvBackend {
+    type Reg = Reg;
+    type Output = String;
+
+    fn argument_registers(&self) -> &'static [Reg] {
+        &REGISTERS
+    }
+
+    fn caller_saved(&self) -> &'static [Reg] {
+        &CALLER_SAVED
+    }
+
+    fn callee_saved(&self) -> &'static [Reg] {
+        &CALLEE_SAVED
+    }
+
+    fn prologue(&mut self, frame: &Frame<Reg>, params: &[Value]) {
+        self.out
+            .push_str(&format!("\t.globl {0}\n{0}:\n", frame.name));
+        self.emit("addi sp, sp, -16");
+        self.emit("sd ra, 8(sp)");
+        self.emit("sd s0, 0(sp)");
+        self.emit("addi s0, sp, 16");
+        if frame.size() > 0 {
+            self.add_sp(-frame.size());
+        }
+        for (i, reg) in frame.alloc.callee_saved.iter().enumerate() {
+            self.access("sd", *reg, frame.saved(i));
+        }
+        for (param, reg) in params.iter().zip(REGISTERS.iter()) {
+            self.write(frame, *param, *reg);
+        }
+    }
+
+    fn epilogue(&mut self, frame: &Frame<Reg>) {
+        for (i, reg) in frame.alloc.callee_saved.iter().enumerate() {
+            self.access("ld", *reg, frame.saved(i));
+        }
+        self.emit("addi sp, s0, -16");
+        self.emit("ld ra, 8(sp)");
+        self.emit("ld s0, 0(sp)");
+        self.emit("addi sp, sp, 16");
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push_str(&format!("{label}:\n"));
+    }
+
+    fn load(&mut self, frame: &Frame<Reg>, dst: Value, slot: Slot) {
+        let d = self.dst_reg(frame, dst);
+        self.load_offset(d, frame.slot(slot));
+        self.write(frame, dst, d);
+    }
+
+    fn store(&mut self, frame: &Frame<Reg>, slot: Slot, src: &Operand) {
+        let s = self.read(frame, src, TMP0);
+        self.store_offset(frame.slot(slot), s);
+    }
+
+    fn copy(&mut self, frame: &Frame<Reg>, dst: Value, src: &Operand) {
+        let s = self.read(frame, src, self.dst_reg(frame, dst));
+        self.write(frame, dst, s);
+    }
+
+    fn extend(&mut self, frame: &Frame<Reg>, dst: Value, src: &Operand, bits: u32, signed: bool) {
+        let s = self.read(frame, src, TMP0);
+        let d = self.dst_reg(frame, dst);
+        match (bits, signed) {
+            (8, false) => self.emit(format!("andi {d}, {s}, 255")),
+            (32, true) => self.emit(format!("sext.w {d}, {s}")),
+            // shift the value to the top and back down
+            (8 | 16 | 32, signed) => {
+                let shift = 64 - bits;
+                let right = if signed { "srai" } else { "srli" };
+                self.emit(format!("slli {d}, {s}, {shift}"));
+                self.emit(format!("{right} {d}, {d}, {shift}"));
+            }
+            _ => self.emit(format!("mv {d}, {s}")),
+        }
+        self.write(frame, dst, d);
+    }
+
+    fn binary(&mut self, frame: &Frame<Reg>, op: BinOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+        let l = self.read(frame, lhs, TMP0);
+        let d = self.dst_reg(frame, dst);
+        match (op, rhs) {
+            (BinOp::Add, Operand::Const(n)) if is_imm12(*n) => {
+                self.emit(format!("addi {d}, {l}, {n}"));
+            }
+            (BinOp::Sub, Operand::Const(n)) if n.checked_neg().is_some_and(is_imm12) => {
+                self.emit(format!("addi {d}, {l}, {}", -n));
+            }
+            _ => {
+                let r = self.read(frame, rhs, TMP1);
+                self.emit(format!("{} {d}, {l}, {r}", mnemonic(op)));
+            }
+        }
+        self.write(frame, dst, d);
+    }
+
+    fn compare(&mut self, frame: &Frame<Reg>, op: CmpOp, dst: Value, lhs: &Operand, rhs: &Operand) {
+        let l = self.read(frame, lhs, TMP0);
+        let r = self.read(frame, rhs, TMP1);
+        let d = self.dst_reg(frame, dst);
+        self.set(op, d, l, r);
+        self.write(frame, dst, d);
+    }
+
+    fn compare_and_branch(
+        &mut self,
+        frame: &Frame<Reg>,
+        op: CmpOp,
+        lhs: &Operand,
+        rhs: &Operand,
+        target: &str,
+    ) {
+        let l = self.read(frame, lhs, TMP0);
+        let r = self.read(frame, rhs, TMP1);
+        self.emit(format!("{} {l}, {r}, {target}", branch(op)));
+    }
+
+    fn jump(&mut self, target: &str) {
+        self.emit(format!("j {target}"));
+    }
+
+    fn call(&mut self, frame: &Frame<Reg>, dst: Value, func: &str, args: &[Operand]) {
+        self.arguments(frame, args);
+        self.emit(format!("call {func}"));
+        self.write(frame, dst, A0);
+    }
+
+    fn ret(&mut self, frame: &Frame<Reg>, value: &Operand) {
+        self.move_to(frame, A0, value);
+        self.epilogue(frame);
+        self.emit("ret");
+    }
+
+    fn tail_call(&mut self, frame: &Frame<Reg>, func: &str, args: &[Operand]) {
+        self.arguments(frame, args);
+        self.epilogue(frame);
+        self.emit(format!("tail {func}"));
+    }
+
+    fn data(&mut self, name: &str, values: &[i64]) {
+        self.out
+            .push_str(&format!("\t.data\n\t.globl {name}\n{name}:\n"));
+        for value in values.iter() {
+            self.emit(format!(".quad {value}"));
+        }
+        self.out.push_str("\t.text\n");
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::replace(&mut self.out, "\t.text\n".to_string())
+    }
+}
+
+// immediates of addi
+fn is_imm12(n: i64) -> bool {
+    (-2048..2048).contains(&n)
+}
+
+fn mnemonic(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "sub",
+        BinOp::Mul => "mul",
+        BinOp::Div => "div",
+        BinOp::UDiv => "divu",
+    }
+}
+
+// conditional branch, greater and less or equal are assembler aliases with
+// the operands swapped
+fn branch(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Eq => "beq",
+        CmpOp::Ne => "bne",
+        CmpOp::Lt => "blt",
+        CmpOp::Le => "ble",
+        CmpOp::Gt => "bgt",
+        CmpOp::Ge => "bge",
+        CmpOp::Ult => "bltu",
+        CmpOp::Ule => "bleu",
+        CmpOp::Ugt => "bgtu",
+        CmpOp::Uge => "bgeu",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use backend::Backend;
+    use rstest::rstest;
+
+    use super::RiscvBackend;
+
+    #[rstest(
+        name,
+        regalloc,
+        case("return", true),
+        case("arith", true),
+        case("arith_stack", false),
+        case("branch", true),
+        case("loop", true),
+        case("call", true),
+        case("unsigned", true),
+        case("constants", true),
+        case("large_frame", true)
+    )]
+    fn test_golden(name: &str, regalloc: bool) {
+        golden::check(env!("CARGO_MANIFEST_DIR"), name, "s", |nodes| {
+            let module = ir::lower::lower(&nodes).unwrap();
+            let mut riscv = RiscvBackend::new();
+            backend::generate_module(&mut riscv, &module, regalloc).unwrap();
+            riscv.finish()
+        });
+    }
+}
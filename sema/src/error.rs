@@ -0,0 +1,29 @@
+use parser::ast::Type;
+use thiserror::Error;
+use token::Location;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}: use of undeclared identifier '{1}'")]
+    UndeclaredIdentifier(Location, String),
+    #[error("{0}: call to undeclared function '{1}'")]
+    UndeclaredFunction(Location, String),
+    #[error("{0}: invalid operands to binary expression ('{1}' and '{2}')")]
+    InvalidOperands(Location, Type, Type),
+    #[error("{0}: expression is not assignable")]
+    NotAssignable(Location),
+    #[error("{0}: assigning to '{1}' from incompatible type '{2}'")]
+    IncompatibleAssignment(Location, Type, Type),
+    #[error("{0}: returning '{2}' from a function with incompatible result type '{1}'")]
+    IncompatibleReturn(Location, Type, Type),
+    #[error("{0}: statement requires expression of scalar type ('{1}' invalid)")]
+    NotScalar(Location, Type),
+    #[error("{0}: variable '{1}' has incomplete type 'void'")]
+    VoidVariable(Location, String),
+    #[error("{0}: redefinition of '{1}'")]
+    Redefinition(Location, String),
+    #[error("{0}: '{1}' takes {2} arguments, but {3} were given")]
+    ArgumentCount(Location, String, usize, usize),
+    #[error("{0}: expected an expression, found {1}")]
+    InvalidExpression(Location, String),
+}
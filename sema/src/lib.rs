@@ -0,0 +1,5 @@
+mod error;
+pub mod resolve;
+pub mod types;
+
+pub use error::Error;
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use parser::ast::{Node, NodeKind};
+
+use crate::Error;
+
+/// Check that every identifier refers to a declaration that comes before it.
+///
+/// Variables are declared with a type or implicitly by the first assignment
+/// to them, and functions by a prototype. Reading a variable before either or
+/// calling a function without a prototype is an error.
+pub fn resolve(nodes: &[Node]) -> Result<(), Error> {
+    let mut resolver = Resolver::default();
+    nodes.iter().try_for_each(|node| resolver.node(node))
+}
+
+#[derive(Debug, Default)]
+struct Resolver {
+    vars: HashSet<String>,
+    funcs: HashSet<String>,
+}
+
+impl Resolver {
+    fn node(&mut self, node: &Node) -> Result<(), Error> {
+        match &node.kind {
+            NodeKind::Assignment | NodeKind::Declaration => {
+                if let Some(rhs) = &node.rhs {
+                    self.node(rhs)?;
+                }
+                // declared after the value, so `a = a + 1` alone is still an error
+                match node.lhs.as_deref() {
+                    Some(Node {
+                        kind: NodeKind::LocalVar(name, _),
+                        ..
+                    }) => {
+                        self.vars.insert(name.clone());
+                    }
+                    Some(lhs) => self.node(lhs)?,
+                    None => {}
+                }
+                return Ok(());
+            }
+            NodeKind::LocalVar(name, _) if !self.vars.contains(name) => {
+                return Err(Error::UndeclaredIdentifier(node.span.start, name.clone()));
+            }
+            NodeKind::Func(name, args) => {
+                if !self.funcs.contains(name) {
+                    return Err(Error::UndeclaredFunction(node.span.start, name.clone()));
+                }
+                args.iter().try_for_each(|arg| self.node(arg))?;
+            }
+            NodeKind::Prototype(name, _) => {
+                self.funcs.insert(name.clone());
+            }
+            NodeKind::Block(nodes) => nodes.iter().try_for_each(|node| self.node(node))?,
+            _ => {}
+        }
+        if let Some(lhs) = &node.lhs {
+            self.node(lhs)?;
+        }
+        if let Some(rhs) = &node.rhs {
+            self.node(rhs)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::parser::Parser;
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::resolve;
+
+    fn check(src: &str) -> Result<(), String> {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        resolve(&parser.nodes).map_err(|e| e.to_string())
+    }
+
+    #[rstest(
+        input,
+        case("return 0;"),
+        case("a = 1; return a;"),
+        case("a = b = 2; return a + b;"),
+        case("for (i = 0; i < 3; i = i + 1) { s = i; } return i;"),
+        case("int foo(); return foo();"),
+        case("int add(int a, int b); { x = add(1, 2); } return x;"),
+        case("int a; unsigned b = a; return b;")
+    )]
+    fn test_resolve(input: &str) {
+        assert_eq!(Ok(()), check(input));
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case(
+            "retrun_val = 1;\nreturn retrun_va1;",
+            "2:8: use of undeclared identifier 'retrun_va1'"
+        ),
+        case("a = a + 1;", "1:5: use of undeclared identifier 'a'"),
+        case(
+            "if (1) a = 2; return b * a;",
+            "1:22: use of undeclared identifier 'b'"
+        ),
+        case("while (n) n = n - 1;", "1:8: use of undeclared identifier 'n'"),
+        case("return foo();", "1:8: call to undeclared function 'foo'"),
+        case("x = foo(1); int foo(int);", "1:5: call to undeclared function 'foo'"),
+        case("long n = n;", "1:10: use of undeclared identifier 'n'")
+    )]
+    fn test_resolve_undeclared(input: &str, expect: &str) {
+        assert_eq!(Err(expect.to_string()), check(input));
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+
+use parser::ast::{Node, NodeKind, Type};
+use token::Span;
+
+use crate::Error;
+
+/// Give every expression a type and make implicit conversions explicit.
+///
+/// Operands of arithmetic and comparisons go through the integer promotions
+/// and the usual arithmetic conversions, and values are converted to the type
+/// of the variable, parameter or result they are stored in. Conversions become
+/// `Cast` nodes and declarations become assignments of their initializer.
+///
+/// Variables assigned without a declaration are `long` and the program is the
+/// body of `main`, which returns `int`.
+pub fn check(nodes: Vec<Node>) -> Result<Vec<Node>, Error> {
+    Checker::default().stmts(nodes)
+}
+
+// integer promotions, everything narrower than int computes as int
+fn promote(ty: Type) -> Type {
+    match ty {
+        Type::Char | Type::UChar => Type::Int,
+        ty => ty,
+    }
+}
+
+// usual arithmetic conversions, the type both operands are converted to
+fn common(l: Type, r: Type) -> Type {
+    let (l, r) = (promote(l), promote(r));
+    if l.is_unsigned() == r.is_unsigned() {
+        return if l.rank() >= r.rank() { l } else { r };
+    }
+    let (unsigned, signed) = if l.is_unsigned() { (l, r) } else { (r, l) };
+    // a signed type of higher rank holds every value of the unsigned one
+    if unsigned.rank() >= signed.rank() {
+        unsigned
+    } else {
+        signed
+    }
+}
+
+fn literal(n: u64) -> Type {
+    if n <= i32::MAX as u64 {
+        Type::Int
+    } else if n <= i64::MAX as u64 {
+        Type::Long
+    } else {
+        Type::ULong
+    }
+}
+
+fn type_of(node: &Node) -> Type {
+    node.ty.unwrap_or(Type::Long)
+}
+
+fn child(node: Option<Box<Node>>) -> Node {
+    *node.expect("missing operand")
+}
+
+// convert the value of the node to the type, constants are converted in place
+fn convert(node: Node, to: Type) -> Node {
+    if node.ty == Some(to) {
+        return node;
+    }
+    let span = node.span;
+    match node.kind {
+        NodeKind::Num(n) => Node::new_num(to.convert(n)),
+        _ => Node::new(NodeKind::Cast, Some(Box::new(node)), None),
+    }
+    .with_ty(to)
+    .at(span)
+}
+
+#[derive(Debug, Default)]
+struct Checker {
+    vars: HashMap<String, Type>,
+    // return and parameter types, unless unspecified
+    funcs: HashMap<String, (Type, Option<Vec<Type>>)>,
+}
+
+impl Checker {
+    fn stmts(&mut self, nodes: Vec<Node>) -> Result<Vec<Node>, Error> {
+        let mut checked = Vec::new();
+        for node in nodes {
+            let code = !(node.kind == NodeKind::Declaration && node.rhs.is_none());
+            let node = self.stmt(node)?;
+            if code {
+                checked.push(node);
+            }
+        }
+        Ok(checked)
+    }
+
+    fn stmt(&mut self, node: Node) -> Result<Node, Error> {
+        let Node {
+            kind,
+            lhs,
+            rhs,
+            ty,
+            span,
+        } = node;
+        let node = match kind {
+            NodeKind::Return => {
+                let value = self.expr(child(lhs))?;
+                if !type_of(&value).is_integer() {
+                    return Err(Error::IncompatibleReturn(
+                        value.span.start,
+                        Type::Int,
+                        type_of(&value),
+                    ));
+                }
+                Node::new(kind, Some(Box::new(convert(value, Type::Int))), None)
+            }
+            NodeKind::If | NodeKind::While => {
+                let cond = lhs.map(|n| self.cond(*n)).transpose()?;
+                let body = rhs.map(|n| self.stmt(*n)).transpose()?;
+                Node::new(kind, cond.map(Box::new), body.map(Box::new))
+            }
+            NodeKind::Else | NodeKind::For => {
+                let lhs = lhs.map(|n| self.stmt(*n)).transpose()?;
+                let rhs = rhs.map(|n| self.stmt(*n)).transpose()?;
+                Node::new(kind, lhs.map(Box::new), rhs.map(Box::new))
+            }
+            NodeKind::Block(nodes) => Node::new(NodeKind::Block(self.stmts(nodes)?), None, None),
+            NodeKind::Prototype(name, params) => {
+                let ret = ty.unwrap_or(Type::Int);
+                self.funcs.insert(name.clone(), (ret, params.clone()));
+                Node::new(NodeKind::Prototype(name, params), None, None).with_ty(ret)
+            }
+            NodeKind::Declaration => return self.declaration(child(lhs), rhs, ty, span),
+            kind => {
+                let node = Node {
+                    kind,
+                    lhs,
+                    rhs,
+                    ty,
+                    span,
+                };
+                return self.expr(node);
+            }
+        };
+        Ok(node.at(span))
+    }
+
+    // a declaration with an initializer is an assignment of it, one without
+    // takes no code
+    fn declaration(
+        &mut self,
+        var: Node,
+        init: Option<Box<Node>>,
+        ty: Option<Type>,
+        span: Span,
+    ) -> Result<Node, Error> {
+        let ty = ty.unwrap_or(Type::Int);
+        let name = var.local_var().expect("declaration of a non variable");
+        if !ty.is_integer() {
+            return Err(Error::VoidVariable(var.span.start, name));
+        }
+        if self.vars.contains_key(&name) {
+            return Err(Error::Redefinition(var.span.start, name));
+        }
+        let init = init.map(|n| self.expr(*n)).transpose()?;
+        self.vars.insert(name, ty);
+        let Some(init) = init else {
+            return Ok(Node::new(NodeKind::Block(Vec::new()), None, None).at(span));
+        };
+        if !type_of(&init).is_integer() {
+            return Err(Error::IncompatibleAssignment(
+                init.span.start,
+                ty,
+                type_of(&init),
+            ));
+        }
+        let node = Node::new(
+            NodeKind::Assignment,
+            Some(Box::new(var.with_ty(ty))),
+            Some(Box::new(convert(init, ty))),
+        );
+        Ok(node.with_ty(ty).at(span))
+    }
+
+    // conditions compare against zero, which any integer can
+    fn cond(&mut self, node: Node) -> Result<Node, Error> {
+        let node = self.expr(node)?;
+        if !type_of(&node).is_integer() {
+            return Err(Error::NotScalar(node.span.start, type_of(&node)));
+        }
+        Ok(node)
+    }
+
+    fn expr(&mut self, node: Node) -> Result<Node, Error> {
+        let Node {
+            kind,
+            lhs,
+            rhs,
+            ty,
+            span,
+        } = node;
+        let node = match kind {
+            NodeKind::Num(n) => Node::new_num(n).with_ty(literal(n)),
+            NodeKind::LocalVar(ref name, _) => {
+                let var = self.vars.get(name).copied().unwrap_or(Type::Long);
+                Node::new(kind, None, None).with_ty(var)
+            }
+            NodeKind::Assignment => {
+                let var = child(lhs);
+                let Some(name) = var.local_var() else {
+                    return Err(Error::NotAssignable(var.span.start));
+                };
+                let value = self.expr(child(rhs))?;
+                let to = *self.vars.entry(name).or_insert(Type::Long);
+                if !type_of(&value).is_integer() {
+                    return Err(Error::IncompatibleAssignment(
+                        value.span.start,
+                        to,
+                        type_of(&value),
+                    ));
+                }
+                Node::new(
+                    kind,
+                    Some(Box::new(var.with_ty(to))),
+                    Some(Box::new(convert(value, to))),
+                )
+                .with_ty(to)
+            }
+            NodeKind::Func(name, args) => {
+                let (ret, params) = self.funcs.get(&name).cloned().unwrap_or((Type::Int, None));
+                if let Some(params) = &params {
+                    if params.len() != args.len() {
+                        return Err(Error::ArgumentCount(
+                            span.start,
+                            name,
+                            params.len(),
+                            args.len(),
+                        ));
+                    }
+                }
+                let args = args
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let arg = self.expr(arg)?;
+                        Ok(match params.as_ref().map(|p| p[i]) {
+                            Some(ty) => convert(arg, ty),
+                            None => arg,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?;
+                Node::new(NodeKind::Func(name, args), None, None).with_ty(ret)
+            }
+            NodeKind::Add
+            | NodeKind::Sub
+            | NodeKind::Mul
+            | NodeKind::Div
+            | NodeKind::Equal
+            | NodeKind::NotEqual
+            | NodeKind::LessThan
+            | NodeKind::LessThanOrEqual
+            | NodeKind::GreaterThan
+            | NodeKind::GreaterThanOrEqual => {
+                let (l, r) = (self.expr(child(lhs))?, self.expr(child(rhs))?);
+                let (lt, rt) = (type_of(&l), type_of(&r));
+                if !lt.is_integer() || !rt.is_integer() {
+                    return Err(Error::InvalidOperands(span.start, lt, rt));
+                }
+                let common = common(lt, rt);
+                let (l, r) = (convert(l, common), convert(r, common));
+                let arithmetic = matches!(
+                    kind,
+                    NodeKind::Add | NodeKind::Sub | NodeKind::Mul | NodeKind::Div
+                );
+                let node = Node::new(kind, Some(Box::new(l)), Some(Box::new(r)));
+                if !arithmetic {
+                    node.with_ty(Type::Int)
+                } else if common.is_unsigned() && common.bits() < 64 {
+                    // unsigned arithmetic wraps around at the width of the type
+                    let node = node.with_ty(Type::ULong).at(span);
+                    Node::new(NodeKind::Cast, Some(Box::new(node)), None).with_ty(common)
+                } else {
+                    node.with_ty(common)
+                }
+            }
+            // already checked
+            NodeKind::Cast => Node {
+                kind,
+                lhs,
+                rhs,
+                ty,
+                span,
+            },
+            kind => return Err(Error::InvalidExpression(span.start, kind.to_string())),
+        };
+        Ok(node.at(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::{
+        ast::{Node, NodeKind, Type},
+        parser::Parser,
+    };
+    use rstest::rstest;
+    use tokenizer::Tokenizer;
+
+    use super::{check, common};
+
+    fn check_src(src: &str) -> Result<Vec<Node>, String> {
+        let tokens = Tokenizer::default().locate(src.to_string()).unwrap();
+        let mut parser = Parser::with_locations(tokens);
+        parser.parse().unwrap();
+        check(parser.nodes).map_err(|e| e.to_string())
+    }
+
+    #[rstest(
+        l,
+        r,
+        expect,
+        case(Type::Char, Type::Char, Type::Int),
+        case(Type::UChar, Type::Int, Type::Int),
+        case(Type::Int, Type::UInt, Type::UInt),
+        case(Type::UInt, Type::Long, Type::Long),
+        case(Type::Long, Type::UInt, Type::Long),
+        case(Type::ULong, Type::Long, Type::ULong),
+        case(Type::Int, Type::Long, Type::Long)
+    )]
+    fn test_common(l: Type, r: Type, expect: Type) {
+        assert_eq!(expect, common(l, r));
+        assert_eq!(expect, common(r, l));
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("1;", Type::Int),
+        case("3000000000;", Type::Long),
+        case("a = 1; a;", Type::Long),
+        case("char c = 1; c + c;", Type::Int),
+        case("unsigned a = 1; a - 2;", Type::UInt),
+        case("unsigned long a = 1; int b = 2; a / b;", Type::ULong),
+        case("unsigned a = 1; a < 2;", Type::Int),
+        case("char f(); f();", Type::Char)
+    )]
+    fn test_check_type(input: &str, expect: Type) {
+        let nodes = check_src(input).unwrap();
+        assert_eq!(Some(expect), nodes.last().unwrap().ty);
+    }
+
+    #[test]
+    fn test_check_conversions() {
+        let nodes = check_src("char c = 200; unsigned u = c; u < 1;").unwrap();
+        // constants are converted in place, declarations become assignments
+        let init = nodes[0].rhs.as_ref().unwrap();
+        assert_eq!(NodeKind::Assignment, nodes[0].kind);
+        assert_eq!(NodeKind::Num(-56i64 as u64), init.kind);
+        assert_eq!(Some(Type::Char), init.ty);
+        // other values get a cast
+        let value = nodes[1].rhs.as_ref().unwrap();
+        assert_eq!(NodeKind::Cast, value.kind);
+        assert_eq!(Some(Type::UInt), value.ty);
+        // the comparison is done on unsigned operands
+        assert!(nodes[2].lhs.as_ref().unwrap().is_unsigned());
+        assert!(nodes[2].rhs.as_ref().unwrap().is_unsigned());
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case(
+            "void f(); a = f();",
+            "1:15: assigning to 'long' from incompatible type 'void'"
+        ),
+        case(
+            "void f(); return f() + 1;",
+            "1:18: invalid operands to binary expression ('void' and 'int')"
+        ),
+        case(
+            "void f(); return f();",
+            "1:18: returning 'void' from a function with incompatible result type 'int'"
+        ),
+        case(
+            "void f(); if (f()) return 1;",
+            "1:15: statement requires expression of scalar type ('void' invalid)"
+        ),
+        case("void v;", "1:6: variable 'v' has incomplete type 'void'"),
+        case("int a; long a;", "1:13: redefinition of 'a'"),
+        case(
+            "int add(int, int); add(1);",
+            "1:20: 'add' takes 2 arguments, but 1 were given"
+        ),
+        case("a = 1; 1 = a;", "1:8: expression is not assignable")
+    )]
+    fn test_check_error(input: &str, expect: &str) {
+        assert_eq!(Err(expect.to_string()), check_src(input).map(|_| ()));
+    }
+}
@@ -1,6 +1,169 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use asm::x86::Syntax;
+use clap::{Parser, Subcommand};
 
 #[derive(Debug, Parser)]
+#[command(
+    name = "teruc",
+    about = "my C compiler by Rust",
+    subcommand_negates_reqs = true
+)]
 pub struct Args {
-    pub input: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// C sources, assembly files and objects to link
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+    /// Place the output into this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Preprocess only
+    #[arg(short = 'E')]
+    pub preprocess_only: bool,
+    /// Compile only, do not assemble or link
+    #[arg(short = 'S')]
+    pub assembly_only: bool,
+    /// Compile and assemble, but do not link
+    #[arg(short = 'c')]
+    pub compile_only: bool,
+    /// Add a directory to the include search path
+    #[arg(short = 'I', value_name = "DIR", global = true)]
+    pub include_dirs: Vec<PathBuf>,
+    /// Define a macro, `NAME` or `NAME=VALUE`
+    #[arg(short = 'D', value_name = "MACRO", global = true)]
+    pub defines: Vec<String>,
+    /// Undefine a macro
+    #[arg(short = 'U', value_name = "MACRO", global = true)]
+    pub undefs: Vec<String>,
+    /// Optimization level, `-O0` generates code for a stack machine straight from
+    /// the syntax tree, `-O1` folds constants, removes dead code and allocates
+    /// registers on the IR and `-O2` also optimizes loops, turns calls in tail
+    /// position into jumps and runs the peephole pass
+    #[arg(short = 'O', value_name = "LEVEL", value_parser = parse_opt_level, default_value = "1")]
+    pub opt_level: OptLevel,
+    /// Code generation flag overriding the optimization level: `-fno-integrated-as`
+    /// assembles with the system cc, `-fno-regalloc` keeps every value on the stack,
+    /// `-fno-ir` generates code straight from the syntax tree, `-fno-fold`,
+    /// `-fno-dce`, `-fno-loops`, `-fno-tail-calls` and `-fno-peephole` disable
+    /// constant folding, dead code elimination, loop invariant code motion and
+    /// strength reduction, tail calls and the peephole pass over the assembly and
+    /// `-fdump-<pass>` prints the output of `fold`, `dce`, `ir`, `loops`,
+    /// `tail-calls` or `peephole` to stderr
+    #[arg(short = 'f', value_name = "FLAG")]
+    pub flags: Vec<String>,
+    /// Warning option, `-Wunreachable-code` reports removed unreachable code
+    #[arg(short = 'W', value_name = "WARNING")]
+    pub warnings: Vec<String>,
+    /// Emit LLVM IR as text in a `.ll` file instead of assembly, with `-S`
+    #[arg(long)]
+    pub emit_llvm: bool,
+    /// Print the syntax tree of each C source as parsed, with declared types and
+    /// source spans, as `json` of a single source or `sexpr`, instead of
+    /// compiling it
+    #[arg(long, value_name = "FORMAT", value_parser = parse_ast_format)]
+    pub dump_ast: Option<AstFormat>,
+    /// Compile a C source into memory and run it in this process, exiting with
+    /// the value returned by main
+    #[arg(long)]
+    pub jit: bool,
+    /// Print debug traces and the commands being run to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// Machine option, `-masm=intel` or `-masm=att` selects the assembly syntax
+    #[arg(short = 'm', value_name = "asm=SYNTAX", value_parser = parse_asm_syntax, default_value = "asm=intel")]
+    pub asm_syntax: Syntax,
+    /// Generate code for this target, `x86_64-linux-gnu`, `aarch64-linux-gnu`,
+    /// `riscv64-linux-gnu` or `wasm32`, whose text format modules are only
+    /// emitted with `-S`
+    #[arg(long, value_name = "TRIPLE", value_parser = parse_target, default_value = "x86_64-linux-gnu")]
+    pub target: Target,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Interpret a C source without compiling it, exiting with the value
+    /// returned by main
+    Run {
+        /// C source to run
+        input: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    X86_64,
+    Aarch64,
+    Riscv64,
+    Wasm32,
+}
+
+impl Target {
+    /// Compiler driver used to assemble and link for the target, none when
+    /// its output is not assembled.
+    pub fn cc(&self) -> Option<&'static str> {
+        match self {
+            Target::X86_64 => Some("cc"),
+            Target::Aarch64 => Some("aarch64-linux-gnu-gcc"),
+            Target::Riscv64 => Some("riscv64-linux-gnu-gcc"),
+            Target::Wasm32 => None,
+        }
+    }
+
+    /// Extension of the files written with `-S`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Target::Wasm32 => "wat",
+            _ => "s",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstFormat {
+    Json,
+    Sexpr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+}
+
+fn parse_opt_level(s: &str) -> Result<OptLevel, String> {
+    match s {
+        "0" => Ok(OptLevel::O0),
+        "1" => Ok(OptLevel::O1),
+        "2" => Ok(OptLevel::O2),
+        _ => Err(format!("unknown optimization level: {s}")),
+    }
+}
+
+fn parse_ast_format(s: &str) -> Result<AstFormat, String> {
+    match s {
+        "json" => Ok(AstFormat::Json),
+        "sexpr" => Ok(AstFormat::Sexpr),
+        _ => Err(format!("unknown syntax tree format: {s}")),
+    }
+}
+
+fn parse_target(s: &str) -> Result<Target, String> {
+    match s {
+        "x86_64-linux-gnu" | "x86_64-unknown-linux-gnu" | "x86_64-pc-linux-gnu" => {
+            Ok(Target::X86_64)
+        }
+        "aarch64-linux-gnu" | "aarch64-unknown-linux-gnu" => Ok(Target::Aarch64),
+        "riscv64-linux-gnu" | "riscv64-unknown-linux-gnu" => Ok(Target::Riscv64),
+        "wasm32" | "wasm32-unknown-unknown" => Ok(Target::Wasm32),
+        _ => Err(format!("unknown target: {s}")),
+    }
+}
+
+fn parse_asm_syntax(s: &str) -> Result<Syntax, String> {
+    match s.strip_prefix("asm=") {
+        Some(syntax) => syntax.parse(),
+        None => Err(format!("unknown machine option: {s}")),
+    }
 }
@@ -0,0 +1,477 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use aarch64::Aarch64Backend;
+use asm::{
+    elf,
+    x86::{encoder, module::AsmModule},
+};
+use backend::Backend;
+use generator::Generator;
+use interpreter::Interpreter;
+use ir::function::Module;
+use jit::JitModule;
+use llvm::LlvmGenerator;
+use optimizer::pass::{
+    ConstantFolding, DeadCodeElimination, LoopOptimization, PassManager, Peephole, Program,
+    TailCalls,
+};
+use parser::{ast::Node, dump, parser::Parser};
+use preprocessor::Preprocessor;
+use riscv::RiscvBackend;
+use tokenizer::Tokenizer;
+use wasm::WatGenerator;
+
+use crate::{
+    cmd::{Args, AstFormat, OptLevel, Target},
+    error::Error,
+};
+
+const DEFAULT_EXECUTABLE: &str = "a.out";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    Source,
+    Assembly,
+    // objects, archives and anything else is handed to the linker
+    Linker,
+}
+
+impl InputKind {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("c") => InputKind::Source,
+            Some("s") | Some("S") => InputKind::Assembly,
+            _ => InputKind::Linker,
+        }
+    }
+}
+
+// code generated for a source file, x86 code stays structured for the
+// integrated assembler and the peephole pass
+enum Assembly {
+    X86(AsmModule),
+    Text(String),
+}
+
+#[derive(Debug)]
+pub struct Driver {
+    args: Args,
+    integrated_as: bool,
+    regalloc: bool,
+    ir: bool,
+    passes: PassManager<Program>,
+    ir_passes: PassManager<Module>,
+    asm_passes: PassManager<AsmModule>,
+    // print the IR as lowered, before the passes run
+    dump_ir: bool,
+    warn_unreachable: bool,
+    temps: Vec<PathBuf>,
+}
+
+impl Driver {
+    pub fn new(args: Args) -> Result<Self, Error> {
+        // the optimization level picks the defaults which -f flags override
+        let optimize = args.opt_level >= OptLevel::O1;
+        let mut integrated_as = true;
+        let mut regalloc = optimize;
+        let mut ir = optimize;
+        let mut dump_ir = false;
+        let mut passes = PassManager::new();
+        passes.add(ConstantFolding, optimize);
+        passes.add(DeadCodeElimination, optimize);
+        let mut ir_passes = PassManager::new();
+        ir_passes.add(LoopOptimization, args.opt_level >= OptLevel::O2);
+        let max_args = match args.target {
+            Target::X86_64 => Generator::new(false).argument_registers().len(),
+            Target::Aarch64 => Aarch64Backend::new().argument_registers().len(),
+            Target::Riscv64 => RiscvBackend::new().argument_registers().len(),
+            // generated from the syntax tree, without the IR
+            Target::Wasm32 => 0,
+        };
+        ir_passes.add(TailCalls { max_args }, args.opt_level >= OptLevel::O2);
+        let mut asm_passes = PassManager::new();
+        asm_passes.add(Peephole, args.opt_level >= OptLevel::O2);
+        for flag in args.flags.iter() {
+            match flag.as_str() {
+                "integrated-as" => integrated_as = true,
+                "no-integrated-as" => integrated_as = false,
+                "regalloc" => regalloc = true,
+                "no-regalloc" => regalloc = false,
+                "ir" => ir = true,
+                "no-ir" => ir = false,
+                "dump-ir" => dump_ir = true,
+                _ if passes.flag(flag) || ir_passes.flag(flag) || asm_passes.flag(flag) => {}
+                _ => return Err(Error::UnknownFlag(flag.clone())),
+            }
+        }
+        let mut warn_unreachable = false;
+        for warning in args.warnings.iter() {
+            match warning.as_str() {
+                "unreachable-code" => warn_unreachable = true,
+                "no-unreachable-code" => warn_unreachable = false,
+                _ => return Err(Error::UnknownWarning(warning.clone())),
+            }
+        }
+
+        Ok(Self {
+            args,
+            integrated_as,
+            regalloc,
+            ir,
+            passes,
+            ir_passes,
+            asm_passes,
+            dump_ir,
+            warn_unreachable,
+            temps: Vec::new(),
+        })
+    }
+
+    pub fn run(&mut self) -> Result<(), Error> {
+        let stops_early = self.args.dump_ast.is_some()
+            || self.args.preprocess_only
+            || self.args.assembly_only
+            || self.args.compile_only;
+        if stops_early && self.args.output.is_some() && self.args.inputs.len() > 1 {
+            return Err(Error::OutputWithMultipleInputs);
+        }
+
+        if self.args.emit_llvm && !self.args.preprocess_only && !self.args.assembly_only {
+            return Err(Error::LlvmTextOnly);
+        }
+
+        // the dumps of several sources would be several JSON documents
+        let sources = self
+            .args
+            .inputs
+            .iter()
+            .filter(|input| InputKind::of(input) == InputKind::Source)
+            .count();
+        if self.args.dump_ast == Some(AstFormat::Json) && sources > 1 {
+            return Err(Error::JsonInput);
+        }
+
+        let inputs = self.args.inputs.clone();
+        let mut objects = Vec::new();
+        for input in inputs.iter() {
+            let kind = InputKind::of(input);
+            if let Some(format) = self.args.dump_ast {
+                if kind == InputKind::Source {
+                    let name = input.display().to_string();
+                    let nodes = self.parse(&name, input)?;
+                    let dump = match format {
+                        AstFormat::Json => dump::json(&nodes),
+                        AstFormat::Sexpr => dump::sexpr(&nodes),
+                    };
+                    self.write_output(self.args.output.clone(), dump.as_bytes())?;
+                } else {
+                    self.unused(input);
+                }
+            } else if self.args.preprocess_only {
+                if kind == InputKind::Source {
+                    let src = self.preprocess(input)?;
+                    self.write_output(self.args.output.clone(), src.as_bytes())?;
+                } else {
+                    self.unused(input);
+                }
+            } else if self.args.assembly_only {
+                if kind == InputKind::Source {
+                    let module = self.compile(input)?;
+                    let extension = match self.args.emit_llvm {
+                        true => "ll",
+                        false => self.args.target.extension(),
+                    };
+                    let output = self.output_for(input, extension);
+                    self.write_assembly(&module, &output)?;
+                } else {
+                    self.unused(input);
+                }
+            } else if self.args.compile_only {
+                let output = self.output_for(input, "o");
+                match kind {
+                    InputKind::Source => self.compile_to_object(input, &output)?,
+                    InputKind::Assembly => self.assemble(input, &output)?,
+                    InputKind::Linker => self.unused(input),
+                }
+            } else {
+                match kind {
+                    InputKind::Source => {
+                        let output = self.temp("o");
+                        self.compile_to_object(input, &output)?;
+                        objects.push(output);
+                    }
+                    InputKind::Assembly => {
+                        let output = self.temp("o");
+                        self.assemble(input, &output)?;
+                        objects.push(output);
+                    }
+                    InputKind::Linker => objects.push(input.clone()),
+                }
+            }
+        }
+
+        if !stops_early {
+            let output = self
+                .args
+                .output
+                .clone()
+                .unwrap_or(PathBuf::from(DEFAULT_EXECUTABLE));
+            let mut cmd = Command::new(self.cc()?);
+            cmd.arg("-o").arg(output).args(objects);
+            self.exec(cmd)?;
+        }
+        Ok(())
+    }
+
+    fn preprocess(&self, input: &Path) -> Result<String, Error> {
+        let mut pp = Preprocessor::new(self.args.include_dirs.clone());
+        for define in self.args.defines.iter() {
+            pp.define_arg(define);
+        }
+        for undef in self.args.undefs.iter() {
+            pp.undef(undef);
+        }
+        Ok(pp.process_file(input)?)
+    }
+
+    // preprocess and parse the source
+    fn parse(&self, name: &str, input: &Path) -> Result<Vec<Node>, Error> {
+        let src = self.preprocess(input)?;
+
+        let tokenizer = Tokenizer::default();
+        let tokens = tokenizer
+            .locate(src)
+            .map_err(|e| Error::Tokenize(name.to_string(), e))?;
+
+        let mut parser = Parser::with_locations(tokens);
+        parser
+            .parse()
+            .map_err(|e| Error::Parse(name.to_string(), e))?;
+        Ok(parser.nodes)
+    }
+
+    // parse and check the source
+    fn analyze(&self, name: &str, input: &Path) -> Result<Vec<Node>, Error> {
+        let nodes = self.parse(name, input)?;
+        sema::resolve::resolve(&nodes).map_err(|e| Error::Sema(name.to_string(), e))?;
+        sema::types::check(nodes).map_err(|e| Error::Sema(name.to_string(), e))
+    }
+
+    /// Interpret the source and return the exit status of the program.
+    pub fn interpret(&self, input: &Path) -> Result<u8, Error> {
+        let name = input.display().to_string();
+        let nodes = self.analyze(&name, input)?;
+        let value = Interpreter::new(io::stdout().lock())
+            .run(&nodes)
+            .map_err(|e| Error::Interpret(name, e))?;
+        // only the low byte survives as the status, like with exit
+        Ok(value as u8)
+    }
+
+    /// Compile the only input into memory, run it and return its exit status.
+    pub fn jit(&self) -> Result<u8, Error> {
+        let input = match self.args.inputs.as_slice() {
+            [input] if InputKind::of(input) == InputKind::Source => input,
+            _ => return Err(Error::JitInput),
+        };
+        let Assembly::X86(module) = self.compile(input)? else {
+            return Err(Error::JitTarget);
+        };
+        let name = input.display().to_string();
+        let object = encoder::assemble(&module).map_err(|e| Error::Assemble(name.clone(), e))?;
+        let jit = JitModule::load(&object).map_err(|e| Error::Jit(name.clone(), e))?;
+        // SAFETY: the code comes from our own x86 generator
+        let value = unsafe { jit.run_main() }.map_err(|e| Error::Jit(name, e))?;
+        Ok(value as u8)
+    }
+
+    fn compile(&self, input: &Path) -> Result<Assembly, Error> {
+        let name = input.display().to_string();
+        let nodes = self.analyze(&name, input)?;
+        let mut program = Program::new(nodes);
+        let Ok(()) = self.passes.run(&mut program);
+        let Program { nodes, warnings } = program;
+        if self.warn_unreachable {
+            for warning in warnings.iter() {
+                eprintln!("{name}: warning: {warning}");
+            }
+        }
+
+        // LLVM IR leaves the target to llc
+        if self.args.emit_llvm {
+            let ll = LlvmGenerator::new()
+                .generate_program(&nodes)
+                .map_err(|e| Error::Llvm(name, e))?;
+            return Ok(Assembly::Text(ll));
+        }
+
+        // only x86 has a code generator working on the syntax tree
+        let mut module = match self.args.target {
+            Target::X86_64 if self.ir => {
+                let generator = Generator::new(self.args.verbose).with_regalloc(self.regalloc);
+                self.generate(generator, &name, &nodes)?
+            }
+            Target::X86_64 => Generator::new(self.args.verbose)
+                .with_regalloc(self.regalloc)
+                .generate_program(&nodes)
+                .map_err(|e| Error::Generate(name, e))?,
+            Target::Aarch64 => {
+                let asm = self.generate(Aarch64Backend::new(), &name, &nodes)?;
+                return Ok(Assembly::Text(asm));
+            }
+            Target::Riscv64 => {
+                let asm = self.generate(RiscvBackend::new(), &name, &nodes)?;
+                return Ok(Assembly::Text(asm));
+            }
+            // structured control flow is generated straight from the syntax tree
+            Target::Wasm32 => {
+                let wat = WatGenerator::new()
+                    .generate_program(&nodes)
+                    .map_err(|e| Error::Wasm(name, e))?;
+                return Ok(Assembly::Text(wat));
+            }
+        };
+        let Ok(()) = self.asm_passes.run(&mut module);
+        Ok(Assembly::X86(module))
+    }
+
+    // generate the optimized IR of the nodes with the backend of the target
+    fn generate<B: Backend>(
+        &self,
+        mut backend: B,
+        name: &str,
+        nodes: &[Node],
+    ) -> Result<B::Output, Error> {
+        let module = self.optimize_ir(name, nodes)?;
+        backend::generate_module(&mut backend, &module, self.regalloc)
+            .map_err(|e| Error::Backend(name.to_string(), e))?;
+        Ok(backend.finish())
+    }
+
+    // lower to the IR and run the passes on it
+    fn optimize_ir(&self, name: &str, nodes: &[Node]) -> Result<Module, Error> {
+        let ir_error = |e| Error::Ir(name.to_string(), e);
+        let mut module = ir::lower::lower(nodes).map_err(ir_error)?;
+        ir::verify::verify(&module).map_err(ir_error)?;
+        if self.dump_ir {
+            eprint!("{module}");
+        }
+        self.ir_passes.run(&mut module).map_err(ir_error)?;
+        Ok(module)
+    }
+
+    fn compile_to_object(&mut self, input: &Path, output: &Path) -> Result<(), Error> {
+        let module = match self.compile(input)? {
+            Assembly::X86(module) if self.integrated_as => module,
+            asm => {
+                let path = self.temp("s");
+                self.write_assembly(&asm, &path)?;
+                return self.assemble(&path, output);
+            }
+        };
+        let object = encoder::assemble(&module)
+            .map_err(|e| Error::Assemble(input.display().to_string(), e))?;
+        let mut buf = Vec::new();
+        elf::write_elf(&object, &mut buf).map_err(|e| io_error(output, e))?;
+        self.write_output(Some(output.to_path_buf()), &buf)
+    }
+
+    fn assemble(&self, input: &Path, output: &Path) -> Result<(), Error> {
+        let mut cmd = Command::new(self.cc()?);
+        cmd.arg("-c").arg(input).arg("-o").arg(output);
+        self.exec(cmd)
+    }
+
+    fn write_assembly(&self, asm: &Assembly, output: &Path) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        match asm {
+            Assembly::X86(module) => self
+                .args
+                .asm_syntax
+                .write_module(module, &mut buf)
+                .map_err(|e| io_error(output, e))?,
+            Assembly::Text(text) => buf.extend_from_slice(text.as_bytes()),
+        }
+        self.write_output(Some(output.to_path_buf()), &buf)
+    }
+
+    // write to the file, or to stdout when there is no output file
+    fn write_output(&self, output: Option<PathBuf>, buf: &[u8]) -> Result<(), Error> {
+        match output {
+            Some(path) if path != Path::new("-") => {
+                let file = File::create(&path).map_err(|e| io_error(&path, e))?;
+                let mut w = BufWriter::new(file);
+                w.write_all(buf)
+                    .and_then(|_| w.flush())
+                    .map_err(|e| io_error(&path, e))
+            }
+            _ => {
+                let mut w = io::stdout().lock();
+                w.write_all(buf)
+                    .and_then(|_| w.flush())
+                    .map_err(|e| io_error(Path::new("<stdout>"), e))
+            }
+        }
+    }
+
+    // -o if given, otherwise the input file name with the extension replaced
+    fn output_for(&self, input: &Path, extension: &str) -> PathBuf {
+        match &self.args.output {
+            Some(output) => output.clone(),
+            None => PathBuf::from(input.file_name().unwrap_or(input.as_os_str()))
+                .with_extension(extension),
+        }
+    }
+
+    fn temp(&mut self, extension: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "teruc-{}-{}.{extension}",
+            std::process::id(),
+            self.temps.len()
+        ));
+        self.temps.push(path.clone());
+        path
+    }
+
+    fn cc(&self) -> Result<&'static str, Error> {
+        self.args.target.cc().ok_or(Error::TextOnly)
+    }
+
+    fn exec(&self, mut cmd: Command) -> Result<(), Error> {
+        let line = format!("{cmd:?}").replace('"', "");
+        if self.args.verbose {
+            eprintln!("{line}");
+        }
+        let program = cmd.get_program().to_string_lossy().to_string();
+        let status = cmd.status().map_err(|e| Error::Spawn(program, e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::Command(line, status))
+        }
+    }
+
+    fn unused(&self, input: &Path) {
+        eprintln!(
+            "teruc: warning: {}: linker input file unused because linking not done",
+            input.display()
+        );
+    }
+}
+
+impl Drop for Driver {
+    fn drop(&mut self) {
+        for temp in self.temps.iter() {
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+fn io_error(path: &Path, e: io::Error) -> Error {
+    Error::Io(path.display().to_string(), e)
+}
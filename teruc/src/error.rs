@@ -0,0 +1,53 @@
+use std::process::ExitStatus;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}: {1}")]
+    Io(String, std::io::Error),
+    #[error(transparent)]
+    Preprocess(#[from] preprocessor::Error),
+    #[error("{0}: {1}")]
+    Tokenize(String, tokenizer::Error),
+    #[error("{0}: {1}")]
+    Parse(String, parser::Error),
+    #[error("{0}:{1}")]
+    Sema(String, sema::Error),
+    #[error("{0}: {1}")]
+    Ir(String, ir::Error),
+    #[error("{0}: {1}")]
+    Generate(String, generator::Error),
+    #[error("{0}: {1}")]
+    Backend(String, backend::Error),
+    #[error("{0}: {1}")]
+    Wasm(String, wasm::Error),
+    #[error("{0}: {1}")]
+    Llvm(String, llvm::Error),
+    #[error("{0}: {1}")]
+    Interpret(String, interpreter::Error),
+    #[error("{0}: {1}")]
+    Jit(String, jit::Error),
+    #[error("{0}: {1}")]
+    Assemble(String, asm::error::Error),
+    #[error("the target has no assembler or linker, use -S")]
+    TextOnly,
+    #[error("--emit-llvm only writes LLVM IR, use -S")]
+    LlvmTextOnly,
+    #[error("--jit takes a single C source")]
+    JitInput,
+    #[error("--jit only supports the x86_64-linux-gnu target")]
+    JitTarget,
+    #[error("--dump-ast=json takes a single C source")]
+    JsonInput,
+    #[error("cannot specify -o with -c, -S, -E or --dump-ast with multiple files")]
+    OutputWithMultipleInputs,
+    #[error("unknown flag: -f{0}")]
+    UnknownFlag(String),
+    #[error("unknown warning option: -W{0}")]
+    UnknownWarning(String),
+    #[error("failed to run {0}: {1}")]
+    Spawn(String, std::io::Error),
+    #[error("{0} failed with {1}")]
+    Command(String, ExitStatus),
+}
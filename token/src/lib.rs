@@ -31,6 +31,11 @@ pub enum Token {
     Else,               // else
     While,              // while
     For,                // for
+    Int,                // int
+    Char,               // char
+    Long,               // long
+    Unsigned,           // unsigned
+    Void,               // void
     Eof,                // EOF
 }
 
@@ -62,11 +67,55 @@ impl Display for Token {
             Self::Else => write!(f, "else"),
             Self::While => write!(f, "while"),
             Self::For => write!(f, "for"),
+            Self::Int => write!(f, "int"),
+            Self::Char => write!(f, "char"),
+            Self::Long => write!(f, "long"),
+            Self::Unsigned => write!(f, "unsigned"),
+            Self::Void => write!(f, "void"),
             Self::Eof => write!(f, "EOF"),
         }
     }
 }
 
 pub fn is_reserved(c: char) -> bool {
-    RESERVED_CHARS.iter().any(|p| c == *p)
+    RESERVED_CHARS.contains(&c)
+}
+
+/// Where a token starts in the source, lines and columns count from 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Location {
+    pub fn new(line: u32, column: u32) -> Self {
+        Self { line, column }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Where a token or node starts in the source and where it ends, just past
+/// its last character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
 }
@@ -26,6 +26,11 @@ pub const IF: &str = "if";
 pub const ELSE: &str = "else";
 pub const WHILE: &str = "while";
 pub const FOR: &str = "for";
+pub const INT: &str = "int";
+pub const CHAR: &str = "char";
+pub const LONG: &str = "long";
+pub const UNSIGNED: &str = "unsigned";
+pub const VOID: &str = "void";
 
 pub const RESERVED_CHARS: [char; 14] = [
     WHITE_SPACE,
@@ -44,4 +49,6 @@ pub const RESERVED_CHARS: [char; 14] = [
     CLOSE_BRACE,
 ];
 
-pub const RESERVED_STR: [&str; 5] = [RETURN, IF, ELSE, WHILE, FOR];
+pub const RESERVED_STR: [&str; 10] = [
+    RETURN, IF, ELSE, WHILE, FOR, INT, CHAR, LONG, UNSIGNED, VOID,
+];
@@ -1,7 +1,7 @@
-use std::{iter::Peekable, str::Chars};
+use std::{cell::Cell, iter::Peekable};
 
-use error::Error;
-use token::{is_reserved, reserved, Token};
+pub use error::Error;
+use token::{reserved, Location, Span, Token};
 
 mod error;
 
@@ -13,139 +13,123 @@ impl Tokenizer {
     }
 
     pub fn process(&self, src: String) -> Result<Vec<Token>, Error> {
+        let tokens = self.locate(src)?;
+        Ok(tokens.into_iter().map(|(t, _)| t).collect())
+    }
+
+    /// Tokenize the source along with the span of each token.
+    pub fn locate(&self, src: String) -> Result<Vec<(Token, Span)>, Error> {
         let mut tokens = vec![];
-        let mut chars = src.chars().peekable();
+        // location of the last char taken from the source, once the loop has
+        // taken the head of a token nothing after it has been looked at
+        let head = Cell::new(Location::default());
+        let next = Cell::new(Location::new(1, 1));
+        let mut chars = src
+            .chars()
+            .inspect(|c| {
+                let loc = next.get();
+                head.set(loc);
+                next.set(if *c == '\n' {
+                    Location::new(loc.line + 1, 1)
+                } else {
+                    Location::new(loc.line, loc.column + 1)
+                });
+            })
+            .peekable();
 
         while let Some(p) = chars.next() {
-            match p {
-                reserved::WHITE_SPACE => {}
-                reserved::PLUS => tokens.push(Token::Add),
-                reserved::MINUS => tokens.push(Token::Sub),
-                reserved::ASTERISK => tokens.push(Token::Mul),
-                reserved::SLASH => tokens.push(Token::Div),
-                reserved::OPEN_PAREN => tokens.push(Token::OpenParen),
-                reserved::CLOSE_PAREN => tokens.push(Token::CloseParen),
-                reserved::OPEN_BRACE => tokens.push(Token::OpenBrace),
-                reserved::CLOSE_BRACE => tokens.push(Token::CloseBrace),
-                reserved::EQUAL => {
-                    let t = self.process_equal(&mut chars)?;
-                    tokens.push(t);
-                }
-                reserved::EXCLAMATION => {
-                    let t = self.process_exclamation(&mut chars)?;
-                    tokens.push(t);
-                }
-                reserved::LESS_THAN => {
-                    let t = self.process_less_than(&mut chars)?;
-                    tokens.push(t);
-                }
-                reserved::GREATER_THAN => {
-                    let t = self.process_greater_than(&mut chars)?;
-                    tokens.push(t);
-                }
-                reserved::SEMICOLON => tokens.push(Token::Semicolon),
-                // reserved::COMMA => tokens.push(Token::Comma),
-                reserved::COMMA => {} // should I ignore comma?
+            let loc = head.get();
+            let token = match p {
+                c if c.is_ascii_whitespace() => continue,
+                reserved::PLUS => Token::Add,
+                reserved::MINUS => Token::Sub,
+                reserved::ASTERISK => Token::Mul,
+                reserved::SLASH => Token::Div,
+                reserved::OPEN_PAREN => Token::OpenParen,
+                reserved::CLOSE_PAREN => Token::CloseParen,
+                reserved::OPEN_BRACE => Token::OpenBrace,
+                reserved::CLOSE_BRACE => Token::CloseBrace,
+                reserved::EQUAL => self.process_equal(&mut chars)?,
+                reserved::EXCLAMATION => self.process_exclamation(&mut chars)?,
+                reserved::LESS_THAN => self.process_less_than(&mut chars)?,
+                reserved::GREATER_THAN => self.process_greater_than(&mut chars)?,
+                reserved::SEMICOLON => Token::Semicolon,
+                // reserved::COMMA => Token::Comma,
+                reserved::COMMA => continue, // should I ignore comma?
                 _ => {
                     if p.is_ascii_digit() {
-                        let n = get_num(&mut chars, p)?;
-                        tokens.push(Token::Num(n));
-                    } else if p.is_ascii_alphabetic() {
-                        let ident = self.process_identifier(&mut chars, p)?;
-                        tokens.push(ident);
+                        Token::Num(get_num(&mut chars, p)?)
+                    } else if p.is_ascii_alphabetic() || p == '_' {
+                        self.process_identifier(&mut chars, p)?
                     } else {
                         return Err(Error::UnknownToken(p));
                     }
                 }
-            }
+            };
+            // peeking makes head the location of the char after the token
+            let end = match chars.peek() {
+                Some(_) => head.get(),
+                None => next.get(),
+            };
+            tokens.push((token, Span::new(loc, end)));
         }
 
         Ok(tokens)
     }
 
-    fn process_equal(&self, chars: &mut Peekable<Chars>) -> Result<Token, Error> {
-        if let Some(p) = chars.peek() {
-            match *p {
-                reserved::WHITE_SPACE => Ok(Token::Assignment),
-                reserved::EQUAL => {
-                    let _ = chars.next();
-                    Ok(Token::Equal)
-                }
-                _ => {
-                    // err
-                    Err(Error::FailedToTokenize)
-                }
-            }
+    fn process_equal(
+        &self,
+        chars: &mut Peekable<impl Iterator<Item = char>>,
+    ) -> Result<Token, Error> {
+        if chars.next_if_eq(&reserved::EQUAL).is_some() {
+            Ok(Token::Equal)
         } else {
-            Err(Error::FailedToTokenize)
+            Ok(Token::Assignment)
         }
     }
 
-    fn process_exclamation(&self, chars: &mut Peekable<Chars>) -> Result<Token, Error> {
-        if let Some(p) = chars.peek() {
-            match *p {
-                reserved::WHITE_SPACE => Ok(Token::Not),
-                reserved::EQUAL => {
-                    let _ = chars.next();
-                    Ok(Token::NotEqual)
-                }
-                _ => Err(Error::FailedToTokenize),
-            }
+    fn process_exclamation(
+        &self,
+        chars: &mut Peekable<impl Iterator<Item = char>>,
+    ) -> Result<Token, Error> {
+        if chars.next_if_eq(&reserved::EQUAL).is_some() {
+            Ok(Token::NotEqual)
         } else {
-            Err(Error::FailedToTokenize)
+            Ok(Token::Not)
         }
     }
 
-    fn process_less_than(&self, chars: &mut Peekable<Chars>) -> Result<Token, Error> {
-        if let Some(p) = chars.peek() {
-            match *p {
-                reserved::WHITE_SPACE => Ok(Token::LessThan),
-                reserved::EQUAL => {
-                    let _ = chars.next();
-                    Ok(Token::LessThanOrEqual)
-                }
-                _ => Err(Error::FailedToTokenize),
-            }
+    fn process_less_than(
+        &self,
+        chars: &mut Peekable<impl Iterator<Item = char>>,
+    ) -> Result<Token, Error> {
+        if chars.next_if_eq(&reserved::EQUAL).is_some() {
+            Ok(Token::LessThanOrEqual)
         } else {
-            Err(Error::FailedToTokenize)
+            Ok(Token::LessThan)
         }
     }
 
-    fn process_greater_than(&self, chars: &mut Peekable<Chars>) -> Result<Token, Error> {
-        if let Some(p) = chars.peek() {
-            match *p {
-                reserved::WHITE_SPACE => Ok(Token::GreaterThan),
-                reserved::EQUAL => {
-                    let _ = chars.next();
-                    Ok(Token::GreaterThanOrEqual)
-                }
-                _ => Err(Error::FailedToTokenize),
-            }
+    fn process_greater_than(
+        &self,
+        chars: &mut Peekable<impl Iterator<Item = char>>,
+    ) -> Result<Token, Error> {
+        if chars.next_if_eq(&reserved::EQUAL).is_some() {
+            Ok(Token::GreaterThanOrEqual)
         } else {
-            Err(Error::FailedToTokenize)
+            Ok(Token::GreaterThan)
         }
     }
 
-    fn process_identifier(&self, chars: &mut Peekable<Chars>, head: char) -> Result<Token, Error> {
+    fn process_identifier(
+        &self,
+        chars: &mut Peekable<impl Iterator<Item = char>>,
+        head: char,
+    ) -> Result<Token, Error> {
         let mut ident = head.to_string();
-        while let Some(c) = chars.peek() {
-            if is_reserved(*c) {
-                return match Self::reserved_identifier(&ident) {
-                    Some(t) => Ok(t),
-                    None => Ok(Token::Identifier(ident)),
-                };
-            }
-            if let Some(p) = chars.next() {
-                ident += &p.to_string();
-            } else {
-                // got EOF
-                return match Self::reserved_identifier(&ident) {
-                    Some(t) => Ok(t),
-                    None => Ok(Token::Identifier(ident)),
-                };
-            }
+        while let Some(c) = chars.next_if(|c| is_identifier_char(*c)) {
+            ident.push(c);
         }
-        // got EOF while generating an identifier
         match Self::reserved_identifier(&ident) {
             Some(t) => Ok(t),
             None => Ok(Token::Identifier(ident)),
@@ -159,6 +143,11 @@ impl Tokenizer {
             reserved::ELSE => Some(Token::Else),
             reserved::WHILE => Some(Token::While),
             reserved::FOR => Some(Token::For),
+            reserved::INT => Some(Token::Int),
+            reserved::CHAR => Some(Token::Char),
+            reserved::LONG => Some(Token::Long),
+            reserved::UNSIGNED => Some(Token::Unsigned),
+            reserved::VOID => Some(Token::Void),
             _ => None,
         }
     }
@@ -170,7 +159,11 @@ impl Default for Tokenizer {
     }
 }
 
-fn get_num(chars: &mut Peekable<Chars>, head: char) -> Result<u64, Error> {
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn get_num(chars: &mut Peekable<impl Iterator<Item = char>>, head: char) -> Result<u64, Error> {
     let mut num_c = vec![head];
 
     while let Some(c) = chars.peek() {
@@ -262,6 +255,9 @@ mod tests {
         case("{}", vec![Token::OpenBrace, Token::CloseBrace]),
         case("{a = 0;}", vec![Token::OpenBrace, Token::Identifier("a".to_string()), Token::Assignment, Token::Num(0), Token::Semicolon, Token::CloseBrace]),
         case("foo()", vec![Token::Identifier("foo".to_string()), Token::OpenParen, Token::CloseParen]),
+        case("a=1;\nb<=a", vec![Token::Identifier("a".to_string()), Token::Assignment, Token::Num(1), Token::Semicolon, Token::Identifier("b".to_string()), Token::LessThanOrEqual, Token::Identifier("a".to_string())]),
+        case("a!=b\tfoo_1\r\n", vec![Token::Identifier("a".to_string()), Token::NotEqual, Token::Identifier("b".to_string()), Token::Identifier("foo_1".to_string())]),
+        case("_x>1", vec![Token::Identifier("_x".to_string()), Token::GreaterThan, Token::Num(1)]),
         case("foo(1, 2)", vec![Token::Identifier("foo".to_string()), Token::OpenParen, Token::Num(1), Token::Num(2), Token::CloseParen]),
     )]
     fn test_tokenizer_process(input: &str, expect: Vec<Token>) {
@@ -269,4 +265,29 @@ mod tests {
         let res = tokenizer.process(input.to_string()).unwrap();
         assert_eq!(expect, res);
     }
+
+    #[test]
+    fn test_tokenizer_locate() {
+        let tokenizer = Tokenizer::default();
+        let res = tokenizer
+            .locate("int foo();\n  a = 10;\nreturn a;".to_string())
+            .unwrap();
+        let spans: Vec<_> = res.iter().map(|(_, span)| span.to_string()).collect();
+        let expect = vec![
+            "1:1-1:4",
+            "1:5-1:8",
+            "1:8-1:9",
+            "1:9-1:10",
+            "1:10-1:11",
+            "2:3-2:4",
+            "2:5-2:6",
+            "2:7-2:9",
+            "2:9-2:10",
+            "3:1-3:7",
+            "3:8-3:9",
+            "3:9-3:10",
+        ];
+        assert_eq!(expect, spans);
+        assert_eq!(Token::Int, res[0].0);
+    }
 }
@@ -0,0 +1,29 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $a i64)
+    (local $b i64)
+    i64.const 3
+    local.set $a
+    local.get $a
+    local.get $a
+    i64.const 2
+    i64.add
+    i64.mul
+    i64.const 4
+    local.get $a
+    i64.const 1
+    i64.sub
+    i64.div_s
+    i64.sub
+    local.set $b
+    local.get $b
+    local.get $a
+    i64.const 3
+    i64.eq
+    i64.extend_i32_u
+    i64.add
+    i64.extend32_s
+    return
+  )
+)
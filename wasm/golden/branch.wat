@@ -0,0 +1,24 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $a i64)
+    i64.const 5
+    local.set $a
+    i64.const 3
+    local.get $a
+    i64.lt_s
+    if
+      local.get $a
+      i64.const 2
+      i64.mul
+      i64.extend32_s
+      return
+    else
+      i64.const 0
+      i64.const 1
+      i64.sub
+      return
+    end
+    i64.const 0
+  )
+)
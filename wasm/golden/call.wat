@@ -0,0 +1,18 @@
+(module
+  (import "env" "add" (func $add (param i64 i64) (result i64)))
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $x i64)
+    i64.const 1
+    i64.const 2
+    call $add
+    local.set $x
+    i64.const 3
+    i64.const 4
+    call $add
+    local.get $x
+    i64.add
+    i64.extend32_s
+    return
+  )
+)
@@ -0,0 +1,18 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $big i64)
+    (local $neg i64)
+    i64.const 81985529216486895
+    local.set $big
+    i64.const 0
+    i64.const 70000
+    i64.sub
+    local.set $neg
+    local.get $neg
+    local.get $big
+    i64.lt_s
+    i64.extend_i32_u
+    return
+  )
+)
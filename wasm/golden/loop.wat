@@ -0,0 +1,31 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $b i64)
+    (local $a i64)
+    i64.const 0
+    local.set $b
+    i64.const 0
+    local.set $a
+    block $end0
+      loop $begin0
+        local.get $a
+        i64.const 10
+        i64.ge_s
+        br_if $end0
+        local.get $b
+        local.get $a
+        i64.add
+        local.set $b
+        local.get $a
+        i64.const 1
+        i64.add
+        local.set $a
+        br $begin0
+      end
+    end
+    local.get $b
+    i64.extend32_s
+    return
+  )
+)
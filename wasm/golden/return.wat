@@ -0,0 +1,7 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    i64.const 42
+    return
+  )
+)
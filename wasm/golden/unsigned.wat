@@ -0,0 +1,36 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $a i64)
+    (local $c i64)
+    (local $n i64)
+    i64.const 0
+    local.set $a
+    i64.const -56
+    local.set $c
+    i64.const 7
+    local.set $n
+    i64.const 5
+    local.get $a
+    i64.const 1
+    i64.sub
+    i64.const 4294967295
+    i64.and
+    i64.lt_u
+    if
+      local.get $n
+      i64.const 2
+      i64.div_u
+      local.get $c
+      i64.add
+      i64.extend32_s
+      return
+    end
+    local.get $c
+    i64.extend32_s
+    i64.const 0
+    i64.lt_s
+    i64.extend_i32_u
+    return
+  )
+)
@@ -0,0 +1,7 @@
+a = 0;
+while (a < 5) {
+    a = a + 1;
+    if (a == 3)
+        return a;
+}
+a;
@@ -0,0 +1,30 @@
+(module
+  (memory (export "memory") 1)
+  (func $main (export "main") (result i64)
+    (local $a i64)
+    i64.const 0
+    local.set $a
+    block $end0
+      loop $begin0
+        local.get $a
+        i64.const 5
+        i64.ge_s
+        br_if $end0
+        local.get $a
+        i64.const 1
+        i64.add
+        local.set $a
+        local.get $a
+        i64.const 3
+        i64.eq
+        if
+          local.get $a
+          i64.extend32_s
+          return
+        end
+        br $begin0
+      end
+    end
+    local.get $a
+  )
+)
@@ -0,0 +1,18 @@
+use parser::ast::MissingChild;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid node")]
+    InvalidNode,
+    #[error("left value of assignment must be identifier")]
+    LeftValueMustBeIdentifier,
+    #[error("{0} is called with {1} arguments, but imported with {2}")]
+    ArgumentCount(String, usize, usize),
+}
+
+impl From<MissingChild> for Error {
+    fn from(_: MissingChild) -> Self {
+        Error::InvalidNode
+    }
+}
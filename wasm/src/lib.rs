@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+pub use error::Error;
+use parser::ast::{child, local_vars, Node, NodeKind, Type};
+
+mod error;
+
+// a function called but not defined, provided by the host as `env.<name>`
+#[derive(Debug)]
+struct Import {
+    name: String,
+    params: usize,
+    void: bool,
+}
+
+/// Code generator for WebAssembly, taking the syntax tree to a text format
+/// module with an exported function per C function.
+///
+/// Values are kept in 64 bits like on the other targets, so every variable is
+/// an `i64` local. Nothing takes the address of a variable yet, the exported
+/// memory is where such variables would live.
+#[derive(Debug, Default)]
+pub struct WatGenerator {
+    labels: u32,
+    // nesting of the instruction being emitted within the function body
+    depth: usize,
+    body: String,
+    imports: Vec<Import>,
+    // parameter count unless unspecified and whether the result is void
+    prototypes: HashMap<String, (Option<usize>, bool)>,
+}
+
+impl WatGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate the program as the body of `main`, which returns the value of
+    /// the last statement when it does not return, see `ir::lower::lower`.
+    pub fn generate_program(&mut self, nodes: &[Node]) -> Result<String, Error> {
+        let locals = local_vars(nodes);
+
+        self.depth = 2;
+        let value = match nodes.split_last() {
+            Some((last, nodes)) => {
+                for node in nodes.iter() {
+                    self.body(node)?;
+                }
+                // nothing follows a return
+                self.stmt(last)? || last.kind == NodeKind::Return
+            }
+            None => false,
+        };
+        if !value {
+            self.emit("i64.const 0");
+        }
+
+        let mut out = String::from("(module\n");
+        for import in self.imports.iter() {
+            out.push_str(&format!(
+                "  (import \"env\" \"{0}\" (func ${0}{1}))\n",
+                import.name,
+                signature(import.params, import.void)
+            ));
+        }
+        out.push_str("  (memory (export \"memory\") 1)\n");
+        out.push_str("  (func $main (export \"main\") (result i64)\n");
+        for local in locals.iter() {
+            out.push_str(&format!("    (local ${local} i64)\n"));
+        }
+        out.push_str(&std::mem::take(&mut self.body));
+        out.push_str("  )\n)\n");
+        self.imports.clear();
+        self.prototypes.clear();
+        Ok(out)
+    }
+
+    // generate a statement, returning whether it leaves a value on the stack
+    fn stmt(&mut self, node: &Node) -> Result<bool, Error> {
+        match &node.kind {
+            NodeKind::Return => {
+                self.expr(child(&node.lhs)?)?;
+                self.emit("return");
+            }
+            NodeKind::If => {
+                self.cond(child(&node.lhs)?, false)?;
+                let body = child(&node.rhs)?;
+                self.emit("if");
+                if body.kind == NodeKind::Else {
+                    self.nested(body.lhs.as_deref())?;
+                    self.emit("else");
+                    self.nested(body.rhs.as_deref())?;
+                } else {
+                    self.nested(Some(body))?;
+                }
+                self.emit("end");
+            }
+            NodeKind::While => self.generate_loop(node.lhs.as_deref(), child(&node.rhs)?)?,
+            NodeKind::For => {
+                let parts = node.for_parts().ok_or(Error::InvalidNode)?;
+                if let Some(init) = parts.init {
+                    self.body(init)?;
+                }
+                self.generate_loop(parts.cond, parts.iteration)?;
+            }
+            NodeKind::Block(nodes) => {
+                let Some((last, nodes)) = nodes.split_last() else {
+                    return Ok(false);
+                };
+                for node in nodes.iter() {
+                    self.body(node)?;
+                }
+                return self.stmt(last);
+            }
+            NodeKind::Prototype(name, params) => {
+                let params = params.as_ref().map(|p| p.len());
+                let void = node.ty == Some(Type::Void);
+                self.prototypes.insert(name.clone(), (params, void));
+            }
+            _ => {
+                self.expr(node)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // generate a statement whose value is not used
+    fn body(&mut self, node: &Node) -> Result<(), Error> {
+        match &node.kind {
+            NodeKind::Assignment => {
+                let Some(name) = child(&node.lhs)?.local_var() else {
+                    return Err(Error::LeftValueMustBeIdentifier);
+                };
+                self.expr(child(&node.rhs)?)?;
+                self.emit(format!("local.set ${name}"));
+            }
+            NodeKind::Block(nodes) => {
+                for node in nodes.iter() {
+                    self.body(node)?;
+                }
+            }
+            _ => {
+                if self.stmt(node)? {
+                    self.emit("drop");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // generate the statement one level deeper
+    fn nested(&mut self, node: Option<&Node>) -> Result<(), Error> {
+        self.depth += 1;
+        if let Some(node) = node {
+            self.body(node)?;
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn generate_loop(&mut self, cond: Option<&Node>, body: &Node) -> Result<(), Error> {
+        let id = self.new_label_id();
+        self.emit(format!("block $end{id}"));
+        self.depth += 1;
+        self.emit(format!("loop $begin{id}"));
+        self.depth += 1;
+        if let Some(cond) = cond {
+            self.cond(cond, true)?;
+            self.emit(format!("br_if $end{id}"));
+        }
+        self.body(body)?;
+        self.emit(format!("br $begin{id}"));
+        self.depth -= 1;
+        self.emit("end");
+        self.depth -= 1;
+        self.emit("end");
+        Ok(())
+    }
+
+    // leave the truth of the expression as an i32, or its negation
+    fn cond(&mut self, node: &Node, negate: bool) -> Result<(), Error> {
+        let Some(op) = compare_op(node, negate) else {
+            self.expr(node)?;
+            if negate {
+                self.emit("i64.eqz");
+            } else {
+                self.emit("i64.const 0");
+                self.emit("i64.ne");
+            }
+            return Ok(());
+        };
+        self.expr(child(&node.lhs)?)?;
+        self.expr(child(&node.rhs)?)?;
+        self.emit(op);
+        Ok(())
+    }
+
+    fn expr(&mut self, node: &Node) -> Result<(), Error> {
+        match &node.kind {
+            NodeKind::Num(n) => self.emit(format!("i64.const {}", *n as i64)),
+            NodeKind::LocalVar(name, _) => self.emit(format!("local.get ${name}")),
+            NodeKind::Assignment => {
+                let Some(name) = child(&node.lhs)?.local_var() else {
+                    return Err(Error::LeftValueMustBeIdentifier);
+                };
+                self.expr(child(&node.rhs)?)?;
+                self.emit(format!("local.tee ${name}"));
+            }
+            NodeKind::Cast => {
+                self.expr(child(&node.lhs)?)?;
+                match node.ty {
+                    Some(Type::Char) => self.emit("i64.extend8_s"),
+                    Some(Type::UChar) => {
+                        self.emit("i64.const 255");
+                        self.emit("i64.and");
+                    }
+                    Some(Type::Int) => self.emit("i64.extend32_s"),
+                    Some(Type::UInt) => {
+                        self.emit("i64.const 4294967295");
+                        self.emit("i64.and");
+                    }
+                    _ => {}
+                }
+            }
+            NodeKind::Func(name, args) => {
+                let void = self.import(name, args.len())?;
+                for arg in args.iter() {
+                    self.expr(arg)?;
+                }
+                self.emit(format!("call ${name}"));
+                // the value of a void call is never used but statements drop one
+                if void {
+                    self.emit("i64.const 0");
+                }
+            }
+            NodeKind::Equal
+            | NodeKind::NotEqual
+            | NodeKind::LessThan
+            | NodeKind::LessThanOrEqual
+            | NodeKind::GreaterThan
+            | NodeKind::GreaterThanOrEqual => {
+                self.cond(node, false)?;
+                self.emit("i64.extend_i32_u");
+            }
+            kind => {
+                let op = match kind {
+                    NodeKind::Add => "i64.add",
+                    NodeKind::Sub => "i64.sub",
+                    NodeKind::Mul => "i64.mul",
+                    NodeKind::Div if node.is_unsigned() => "i64.div_u",
+                    NodeKind::Div => "i64.div_s",
+                    _ => return Err(Error::InvalidNode),
+                };
+                self.expr(child(&node.lhs)?)?;
+                self.expr(child(&node.rhs)?)?;
+                self.emit(op);
+            }
+        }
+        Ok(())
+    }
+
+    // import a function the first time it is called, returning whether it is void
+    fn import(&mut self, name: &str, args: usize) -> Result<bool, Error> {
+        // main is the function being generated
+        if name == "main" {
+            return Ok(false);
+        }
+        if let Some(import) = self.imports.iter().find(|i| i.name == name) {
+            if import.params != args {
+                return Err(Error::ArgumentCount(name.to_string(), args, import.params));
+            }
+            return Ok(import.void);
+        }
+        let (params, void) = self.prototypes.get(name).copied().unwrap_or((None, false));
+        self.imports.push(Import {
+            name: name.to_string(),
+            params: params.unwrap_or(args),
+            void,
+        });
+        Ok(void)
+    }
+
+    fn emit(&mut self, instr: impl AsRef<str>) {
+        self.body.push_str(&"  ".repeat(self.depth));
+        self.body.push_str(instr.as_ref());
+        self.body.push('\n');
+    }
+
+    fn new_label_id(&mut self) -> u32 {
+        let id = self.labels;
+        self.labels += 1;
+        id
+    }
+}
+
+fn signature(params: usize, void: bool) -> String {
+    let mut s = String::new();
+    if params > 0 {
+        s.push_str(" (param");
+        s.push_str(&" i64".repeat(params));
+        s.push(')');
+    }
+    if !void {
+        s.push_str(" (result i64)");
+    }
+    s
+}
+
+// comparison instruction of the node, unsigned when its operands are
+fn compare_op(node: &Node, negate: bool) -> Option<&'static str> {
+    let unsigned = node.lhs.as_ref().is_some_and(|lhs| lhs.is_unsigned());
+    let kind = match (&node.kind, negate) {
+        (kind, false) => kind.clone(),
+        (NodeKind::Equal, true) => NodeKind::NotEqual,
+        (NodeKind::NotEqual, true) => NodeKind::Equal,
+        (NodeKind::LessThan, true) => NodeKind::GreaterThanOrEqual,
+        (NodeKind::LessThanOrEqual, true) => NodeKind::GreaterThan,
+        (NodeKind::GreaterThan, true) => NodeKind::LessThanOrEqual,
+        (NodeKind::GreaterThanOrEqual, true) => NodeKind::LessThan,
+        _ => return None,
+    };
+    match (kind, unsigned) {
+        (NodeKind::Equal, _) => Some("i64.eq"),
+        (NodeKind::NotEqual, _) => Some("i64.ne"),
+        (NodeKind::LessThan, false) => Some("i64.lt_s"),
+        (NodeKind::LessThan, true) => Some("i64.lt_u"),
+        (NodeKind::LessThanOrEqual, false) => Some("i64.le_s"),
+        (NodeKind::LessThanOrEqual, true) => Some("i64.le_u"),
+        (NodeKind::GreaterThan, false) => Some("i64.gt_s"),
+        (NodeKind::GreaterThan, true) => Some("i64.gt_u"),
+        (NodeKind::GreaterThanOrEqual, false) => Some("i64.ge_s"),
+        (NodeKind::GreaterThanOrEqual, true) => Some("i64.ge_u"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{Error, WatGenerator};
+
+    fn generate(src: &str) -> Result<String, Error> {
+        WatGenerator::new().generate_program(&golden::checked(src))
+    }
+
+    #[rstest(
+        name,
+        case("return"),
+        case("arith"),
+        case("branch"),
+        case("loop"),
+        case("while"),
+        case("call"),
+        case("unsigned"),
+        case("constants")
+    )]
+    fn test_golden(name: &str) {
+        golden::check(env!("CARGO_MANIFEST_DIR"), name, "wat", |nodes| {
+            WatGenerator::new().generate_program(&nodes).unwrap()
+        });
+    }
+
+    #[rstest(
+        input,
+        expect,
+        case("1; 2;", vec!["    i64.const 1", "    drop", "    i64.const 2", "  )"]),
+        case("a = 1; a = 2;", vec!["    local.set $a", "    i64.const 2", "    local.tee $a", "  )"]),
+        case("if (1) 2;", vec!["    end", "    i64.const 0", "  )"]),
+        case("return 3;", vec!["    i64.const 3", "    return", "  )"])
+    )]
+    fn test_generate_result(input: &str, expect: Vec<&str>) {
+        let wat = generate(input).unwrap();
+        let lines: Vec<&str> = wat.lines().collect();
+        assert_eq!(
+            expect,
+            lines[lines.len() - expect.len() - 1..lines.len() - 1]
+        );
+    }
+
+    #[test]
+    fn test_generate_argument_count() {
+        assert!(matches!(
+            generate("foo(1); foo(1, 2);"),
+            Err(Error::ArgumentCount(name, 2, 1)) if name == "foo"
+        ));
+    }
+}